@@ -1,4 +1,4 @@
-use std::fs;
+use std::{fs, ops::Range};
 
 use ::toml::{from_str, to_string, Spanned};
 use indexmap::IndexMap;
@@ -10,7 +10,7 @@ use crate::{
     config::toml::ConfigLoader,
     integrations::git,
     step::{
-        releases::{find_packages, Package},
+        releases::{find_packages, Package, PackageGroup, PackageName},
         PrepareRelease, Step,
     },
     variables::Variable,
@@ -25,6 +25,8 @@ pub(crate) use self::toml::{ChangeLogSectionName, CommitFooter, CustomChangeType
 #[derive(Debug)]
 pub(crate) struct Config {
     pub(crate) packages: Vec<Package>,
+    /// Groups of `packages` which are released together
+    pub(crate) package_groups: Vec<PackageGroup>,
     /// The list of defined workflows that are selectable
     pub(crate) workflows: Vec<Workflow>,
     /// Optional configuration for Jira
@@ -102,7 +104,7 @@ impl TryFrom<(ConfigLoader, String)> for Config {
                     .try_into()
                     .map_err(|err| Error::PackageFormat {
                         inner: err,
-                        source_code,
+                        source_code: source_code.clone(),
                         span: span.into(),
                     })?]
             }
@@ -121,8 +123,10 @@ impl TryFrom<(ConfigLoader, String)> for Config {
                 .collect::<Result<Vec<Package>, Error>>()?,
             (None, None) => Vec::new(),
         };
+        let package_groups = package_groups(&packages, config.package_groups, &source_code)?;
         Ok(Self {
             packages,
+            package_groups,
             workflows: config
                 .workflows
                 .into_inner()
@@ -135,6 +139,39 @@ impl TryFrom<(ConfigLoader, String)> for Config {
     }
 }
 
+/// Check that every member of `groups` is a defined package, and is in no more than one group.
+fn package_groups(
+    packages: &[Package],
+    groups: Vec<Spanned<PackageGroup>>,
+    source_code: &str,
+) -> Result<Vec<PackageGroup>, Error> {
+    let mut grouped: Vec<(&PackageName, Range<usize>)> = Vec::new();
+    for group in &groups {
+        for name in &group.get_ref().packages {
+            if !packages
+                .iter()
+                .any(|package| package.name.as_ref() == Some(name))
+            {
+                return Err(Error::UnknownGroupPackage {
+                    package: name.to_string(),
+                    source_code: source_code.to_string(),
+                    span: group.span().into(),
+                });
+            }
+            if let Some((_, first_group)) = grouped.iter().find(|(grouped, _)| *grouped == name) {
+                return Err(Error::PackageInMultipleGroups {
+                    package: name.to_string(),
+                    source_code: source_code.to_string(),
+                    first_group: first_group.clone().into(),
+                    second_group: group.span().into(),
+                });
+            }
+            grouped.push((name, group.span()));
+        }
+    }
+    Ok(groups.into_iter().map(Spanned::into_inner).collect())
+}
+
 /// Where the config came from
 pub(crate) enum ConfigSource {
     /// There is no config file, this is the default config.
@@ -194,6 +231,34 @@ pub(crate) enum Error {
         url("https://knope-dev.github.io/knope/config/packages.html")
     )]
     EmptyPackages,
+    #[error("The package group contains {package}, which is not a package")]
+    #[diagnostic(
+        code(config::unknown_group_package),
+        help("Groups can only contain the names of packages defined in `[packages]`."),
+        url("https://knope-dev.github.io/knope/config/packages.html#package-groups")
+    )]
+    UnknownGroupPackage {
+        package: String,
+        #[source_code]
+        source_code: String,
+        #[label("group defined here")]
+        span: SourceSpan,
+    },
+    #[error("The package {package} is in more than one group")]
+    #[diagnostic(
+        code(config::package_in_multiple_groups),
+        help("Combine the groups, or remove the package from one of them."),
+        url("https://knope-dev.github.io/knope/config/packages.html#package-groups")
+    )]
+    PackageInMultipleGroups {
+        package: String,
+        #[source_code]
+        source_code: String,
+        #[label("first group")]
+        first_group: SourceSpan,
+        #[label("second group")]
+        second_group: SourceSpan,
+    },
 }
 
 #[cfg(test)]
@@ -217,6 +282,63 @@ mod test_package_configs {
         let config = Config::try_from((config, toml_string));
         assert!(config.is_err(), "Expected an error, got {config:?}");
     }
+
+    fn config_with_groups(groups: &str) -> Result<Config, super::Error> {
+        let toml_string = format!(
+            r#"
+            [packages.first]
+            [packages.second]
+            {groups}
+            [[workflows]]
+            name = "default"
+            [[workflows.steps]]
+            type = "Command"
+            command = "echo this is nothing, really"
+        "#
+        );
+        let config: super::toml::ConfigLoader = toml::from_str(&toml_string).unwrap();
+        Config::try_from((config, toml_string))
+    }
+
+    #[test]
+    fn package_groups() {
+        let config = config_with_groups(
+            r#"[[package_groups]]
+            type = "fixed"
+            packages = ["first", "second"]"#,
+        )
+        .unwrap();
+        assert_eq!(config.package_groups.len(), 1);
+    }
+
+    #[test]
+    fn unknown_group_package() {
+        let config = config_with_groups(
+            r#"[[package_groups]]
+            type = "linked"
+            packages = ["first", "third"]"#,
+        );
+        assert!(
+            matches!(config, Err(super::Error::UnknownGroupPackage { .. })),
+            "Expected UnknownGroupPackage, got {config:?}"
+        );
+    }
+
+    #[test]
+    fn package_in_multiple_groups() {
+        let config = config_with_groups(
+            r#"[[package_groups]]
+            type = "linked"
+            packages = ["first"]
+            [[package_groups]]
+            type = "fixed"
+            packages = ["first", "second"]"#,
+        );
+        assert!(
+            matches!(config, Err(super::Error::PackageInMultipleGroups { .. })),
+            "Expected PackageInMultipleGroups, got {config:?}"
+        );
+    }
 }
 
 /// Generate a brand new Config for the project in the current directory.
@@ -288,5 +410,6 @@ pub(crate) fn generate() -> Config {
         jira: None,
         github,
         packages: find_packages().ok().into_iter().collect(),
+        package_groups: Vec::new(),
     }
 }
@@ -3,7 +3,10 @@ use serde::{Deserialize, Serialize};
 use toml::Spanned;
 
 use super::package::Package;
-use crate::{step::releases::package::PackageName, workflow::Workflow};
+use crate::{
+    step::releases::{package::PackageName, PackageGroup},
+    workflow::Workflow,
+};
 
 /// Loads a `crate::Config` from a TOML file with as much span information as possible for better
 /// error messages.
@@ -13,6 +16,9 @@ pub(crate) struct ConfigLoader {
     pub(crate) package: Option<Spanned<Package>>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub(crate) packages: Option<IndexMap<PackageName, Spanned<Package>>>,
+    /// Groups of packages which are released together
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) package_groups: Vec<Spanned<PackageGroup>>,
     /// The list of defined workflows that are selectable
     pub(crate) workflows: Spanned<Vec<Spanned<Workflow>>>,
     /// Optional configuration for Jira
@@ -3,17 +3,31 @@ use std::{
     fmt,
     fmt::Display,
     path::PathBuf,
+    str::FromStr,
 };
 
 use git_conventional::FooterToken;
+use glob::Pattern;
 use indexmap::IndexMap;
 use miette::Diagnostic;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-use crate::step::releases::{
-    changelog, changelog::Changelog, package::Asset, versioned_file, versioned_file::VersionedFile,
-    ChangelogSectionSource, PackageName,
+use crate::{
+    fs,
+    step::releases::{
+        calver, changelog,
+        changelog::Changelog,
+        git,
+        git::TagTemplate,
+        package::{Asset, CommitDetails, CommitTypeRule, Versioning},
+        semver::BuildMetadataSource,
+        template,
+        template::Template,
+        versioned_file,
+        versioned_file::{PackageFormat, VersionedFile},
+        ChangelogSectionSource, DateFormat, PackageName,
+    },
 };
 
 /// Represents a single package in `knope.toml`.
@@ -21,15 +35,68 @@ use crate::step::releases::{
 pub struct Package {
     /// The files which define the current version of the package.
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
-    pub(crate) versioned_files: Vec<PathBuf>,
+    pub(crate) versioned_files: Vec<VersionedFileConfig>,
     /// The path to the `CHANGELOG.md` file (if any) to be updated when running [`Step::PrepareRelease`].
     pub(crate) changelog: Option<PathBuf>,
     /// Optional scopes that can be used to filter commits when running [`Step::PrepareRelease`].
     pub(crate) scopes: Option<Vec<String>>,
+    /// Glob patterns of the files which belong to this package, used to filter commits.
+    pub(crate) paths: Option<Vec<String>>,
     /// Extra sections that should be added to the changelog from custom footers in commit messages.
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub(crate) extra_changelog_sections: Vec<ChangelogSection>,
+    /// How commits of each conventional commit type affect releases, like `perf = { bump = "patch" }`.
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub(crate) commit_types: IndexMap<String, CommitTypeRule>,
+    /// What to add to the changelog entries of commits, like their body or author.
+    pub(crate) commit_details: Option<CommitDetails>,
+    /// A template file to render new changelog entries with, instead of the built-in format.
+    pub(crate) changelog_template: Option<PathBuf>,
+    /// How dates are written in release titles, like `[year]-[month]-[day]`—which is the default.
+    pub(crate) date_format: Option<String>,
+    /// Whether to link versions in the changelog to their changes on GitHub—`false` if not set.
+    pub(crate) compare_links: Option<bool>,
     assets: Option<Vec<Asset>>,
+    /// Build metadata to attach to new versions, like the Git commit or the date.
+    pub(crate) build_metadata: Option<BuildMetadataSource>,
+    /// How new versions are determined—[`VersioningScheme::SemVer`] if not set.
+    pub(crate) versioning: Option<VersioningScheme>,
+    /// The format of versions when `versioning` is [`VersioningScheme::CalVer`].
+    pub(crate) calver_format: Option<String>,
+    /// Whether 0.x versions bump the minor version for breaking changes—`true` if not set.
+    pub(crate) pre_1_0_convention: Option<bool>,
+    /// The name of release tags, like `v{version}`—which is the default.
+    pub(crate) tag_template: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum VersioningScheme {
+    SemVer,
+    CalVer,
+}
+
+fn versioning_toml_to_config(
+    versioning: Option<VersioningScheme>,
+    calver_format: Option<String>,
+    pre_1_0_convention: Option<bool>,
+) -> Result<Versioning> {
+    match (versioning, calver_format) {
+        (Some(VersioningScheme::CalVer), _) if pre_1_0_convention.is_some() => {
+            Err(Error::Pre1_0ConventionWithCalVer)
+        }
+        (Some(VersioningScheme::CalVer), format) => Ok(Versioning::CalVer(
+            format
+                .as_deref()
+                .map(str::parse)
+                .transpose()?
+                .unwrap_or_default(),
+        )),
+        (_, Some(_)) => Err(Error::CalVerFormatWithoutCalVer),
+        (_, None) => Ok(Versioning::SemVer {
+            pre_1_0_convention: pre_1_0_convention.unwrap_or(true),
+        }),
+    }
 }
 
 impl TryFrom<(Option<PackageName>, Package)> for crate::step::releases::Package {
@@ -42,16 +109,59 @@ impl TryFrom<(Option<PackageName>, Package)> for crate::step::releases::Package
                 .into_iter()
                 .map(VersionedFile::try_from)
                 .collect::<std::result::Result<Vec<_>, _>>()?,
-            name,
             changelog: package.changelog.map(Changelog::try_from).transpose()?,
             scopes: package.scopes,
+            paths: package
+                .paths
+                .map(|paths| {
+                    paths
+                        .iter()
+                        .map(|path| {
+                            Pattern::new(path).map_err(|source| Error::InvalidPath {
+                                path: path.clone(),
+                                source,
+                            })
+                        })
+                        .collect::<Result<Vec<_>>>()
+                })
+                .transpose()?,
             extra_changelog_sections: changelog_sections_toml_to_config(
                 package.extra_changelog_sections,
             ),
+            commit_types: package.commit_types,
+            commit_details: package.commit_details.unwrap_or_default(),
+            changelog_template: package
+                .changelog_template
+                .map(|path| {
+                    let source = fs::read_to_string(&path)?;
+                    Template::parse(path, source).map_err(|err| Error::from(Box::new(err)))
+                })
+                .transpose()?,
+            date_format: package
+                .date_format
+                .map(|format| {
+                    DateFormat::from_str(&format).map_err(|source| Error::InvalidDateFormat {
+                        format: format.clone(),
+                        source,
+                    })
+                })
+                .transpose()?,
+            compare_links: package.compare_links.unwrap_or(false),
             pending_changes: vec![],
             prepared_release: None,
             override_version: None,
             assets: package.assets,
+            build_metadata: package.build_metadata,
+            versioning: versioning_toml_to_config(
+                package.versioning,
+                package.calver_format,
+                package.pre_1_0_convention,
+            )?,
+            tag_template: package
+                .tag_template
+                .map(|template| TagTemplate::new(&template, name.as_ref()))
+                .transpose()?,
+            name,
         })
     }
 }
@@ -74,18 +184,48 @@ impl TryFrom<Package> for crate::step::releases::Package {
 
 impl From<crate::step::releases::Package> for Package {
     fn from(package: crate::step::releases::Package) -> Self {
+        let (versioning, calver_format, pre_1_0_convention) = match package.versioning {
+            Versioning::SemVer {
+                pre_1_0_convention: true,
+            } => (None, None, None),
+            Versioning::SemVer {
+                pre_1_0_convention: false,
+            } => (None, None, Some(false)),
+            Versioning::CalVer(format) => (
+                Some(VersioningScheme::CalVer),
+                Some(format.to_string()),
+                None,
+            ),
+        };
         Self {
             versioned_files: package
                 .versioned_files
                 .into_iter()
-                .map(|file| file.path)
+                .map(VersionedFileConfig::from)
                 .collect(),
             changelog: package.changelog.map(|changelog| changelog.path),
             scopes: package.scopes,
+            paths: package.paths.map(|paths| {
+                paths
+                    .into_iter()
+                    .map(|pattern| pattern.as_str().to_string())
+                    .collect()
+            }),
             extra_changelog_sections: changelog_sections_config_to_toml(
                 package.extra_changelog_sections,
             ),
+            commit_types: package.commit_types,
+            commit_details: Some(package.commit_details)
+                .filter(|details| *details != CommitDetails::default()),
+            changelog_template: package.changelog_template.map(|template| template.path),
+            date_format: package.date_format.map(|format| format.to_string()),
+            compare_links: package.compare_links.then_some(true),
             assets: package.assets,
+            build_metadata: package.build_metadata,
+            versioning,
+            calver_format,
+            pre_1_0_convention,
+            tag_template: package.tag_template.map(|template| template.to_string()),
         }
     }
 }
@@ -133,6 +273,8 @@ fn changelog_sections_config_to_toml(
             ChangelogSectionSource::CustomChangeType(change_type) => {
                 types.entry(name).or_insert_with(Vec::new).push(change_type);
             }
+            // Sections for commit types are part of `commit_types`
+            ChangelogSectionSource::CommitType(_) => {}
         }
     }
     section_names
@@ -153,10 +295,140 @@ pub(crate) enum Error {
     #[error(transparent)]
     #[diagnostic(transparent)]
     VersionedFile(#[from] versioned_file::Error),
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    CalVer(#[from] calver::Error),
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    TagTemplate(#[from] git::Error),
+    #[error("`calver_format` can only be used with `versioning = \"calver\"`")]
+    #[diagnostic(
+        code(config::calver_format_without_calver),
+        help("Set `versioning = \"calver\"` for the package, or remove `calver_format`."),
+        url("https://knope-dev.github.io/knope/config/packages.html#versioning")
+    )]
+    CalVerFormatWithoutCalVer,
+    #[error("`pre_1_0_convention` can't be used with `versioning = \"calver\"`")]
+    #[diagnostic(
+        code(config::pre_1_0_convention_with_calver),
+        help("Calendar versions don't depend on the kinds of changes, so remove `pre_1_0_convention`."),
+        url("https://knope-dev.github.io/knope/config/packages.html#pre_1_0_convention")
+    )]
+    Pre1_0ConventionWithCalVer,
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Fs(#[from] fs::Error),
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    ChangelogTemplate(#[from] Box<template::Error>),
+    #[error("Invalid date format {format}: {source}")]
+    #[diagnostic(
+        code(config::invalid_date_format),
+        help("Date formats are made of components like `[year]`, `[month]`, and `[day]`."),
+        url("https://time-rs.github.io/book/api/format-description.html")
+    )]
+    InvalidDateFormat {
+        format: String,
+        #[source]
+        source: time::error::InvalidFormatDescription,
+    },
+    #[error("Invalid path pattern {path}: {source}")]
+    #[diagnostic(
+        code(config::invalid_path),
+        help("`paths` are glob patterns relative to the root of the repository, like `crates/core/**`."),
+        url("https://knope-dev.github.io/knope/config/packages.html#paths")
+    )]
+    InvalidPath {
+        path: String,
+        #[source]
+        source: glob::PatternError,
+    },
 }
 
 type Result<T> = std::result::Result<T, Error>;
 
+/// An entry in `versioned_files`—either a path or a table with a path and extra options.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(untagged)]
+pub(crate) enum VersionedFileConfig {
+    Path(PathBuf),
+    Table(VersionedFileTable),
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub(crate) struct VersionedFileTable {
+    path: PathBuf,
+    /// A regex with a named `version` capture group to find the version in any file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pattern: Option<String>,
+    /// Bump the Android `versionCode` in a Gradle build script with every release.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    increment_version_code: bool,
+    /// Keep the `appVersion` in a Helm `Chart.yaml` the same as `version`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    app_version: bool,
+    /// Bump the build number (like `+45`) in a Dart `pubspec.yaml` with every release.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    increment_build_number: bool,
+}
+
+impl TryFrom<VersionedFileConfig> for VersionedFile {
+    type Error = versioned_file::Error;
+
+    fn try_from(config: VersionedFileConfig) -> std::result::Result<Self, Self::Error> {
+        let table = match config {
+            VersionedFileConfig::Path(path) => return Self::try_from(path),
+            VersionedFileConfig::Table(table) => table,
+        };
+        let mut versioned_file = match table.pattern {
+            Some(pattern) => Self::with_pattern(table.path, &pattern)?,
+            None => Self::try_from(table.path)?,
+        };
+        if table.increment_version_code {
+            versioned_file = versioned_file.increment_version_code()?;
+        }
+        if table.app_version {
+            versioned_file = versioned_file.app_version()?;
+        }
+        if table.increment_build_number {
+            versioned_file = versioned_file.increment_build_number()?;
+        }
+        Ok(versioned_file)
+    }
+}
+
+impl From<VersionedFile> for VersionedFileConfig {
+    fn from(versioned_file: VersionedFile) -> Self {
+        let table = VersionedFileTable {
+            path: versioned_file.path,
+            ..VersionedFileTable::default()
+        };
+        match versioned_file.format {
+            PackageFormat::Gradle {
+                increment_version_code: true,
+            } => Self::Table(VersionedFileTable {
+                increment_version_code: true,
+                ..table
+            }),
+            PackageFormat::Helm { app_version: true } => Self::Table(VersionedFileTable {
+                app_version: true,
+                ..table
+            }),
+            PackageFormat::Pubspec {
+                increment_build_number: true,
+            } => Self::Table(VersionedFileTable {
+                increment_build_number: true,
+                ..table
+            }),
+            PackageFormat::Pattern(pattern) => Self::Table(VersionedFileTable {
+                pattern: Some(pattern.to_string()),
+                ..table
+            }),
+            _ => Self::Path(table.path),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub(crate) struct ChangelogSection {
     pub(crate) name: ChangeLogSectionName,
@@ -6,7 +6,13 @@ use std::{
 };
 
 use git2::{build::CheckoutBuilder, Branch, BranchType, IndexAddOption, Repository};
-use gix::{object::Kind, refs::transaction::PreviousValue, ObjectId};
+use gix::{
+    diff::tree::{recorder::Change as RecordedChange, Changes, Recorder, State},
+    object::Kind,
+    objs::TreeRefIter,
+    refs::transaction::PreviousValue,
+    ObjectId,
+};
 use itertools::Itertools;
 use log::error;
 use miette::Diagnostic;
@@ -18,7 +24,7 @@ use crate::{
     state,
     step::{
         issues::Issue,
-        releases::{semver::Version, CurrentVersions},
+        releases::{git::TagTemplate, pep440, semver::Version, CurrentVersions},
     },
     workflow::Verbose,
     RunType,
@@ -172,6 +178,16 @@ enum ErrorKind {
     PeelOid(#[from] gix::reference::peel::Error),
     #[error("Could not walk commits back from HEAD: {0}")]
     RevisionWalk(#[from] gix::revision::walk::Error),
+    #[error("Could not find the files changed by commit {commit}: {source}")]
+    #[diagnostic(
+        code(git::changed_paths),
+        help("Please check that the full history of the Git repository is available.")
+    )]
+    ChangedPaths {
+        commit: ObjectId,
+        #[source]
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
 }
 
 /// Rebase the current branch onto the selected one.
@@ -404,10 +420,13 @@ pub(crate) fn add_files(file_names: &[PathBuf]) -> Result<(), Error> {
 /// means that there could be paths which jump _behind_ the target tag... and we want to exclude
 /// those as well. There's probably a way to optimize performance with some cool graph magic
 /// eventually, but this is good enough for now.
+///
+/// Finding the [`Commit::changed_paths`] is slow, so it's only done if `with_changed_paths`.
 pub(crate) fn get_commit_messages_after_tag(
     tag: Option<String>,
+    with_changed_paths: bool,
     verbose: Verbose,
-) -> Result<Vec<String>, Error> {
+) -> Result<Vec<Commit>, Error> {
     let repo = gix::open(".")?;
     let commits_to_exclude = tag
         .map(|tag| format!("refs/tags/{tag}"))
@@ -442,19 +461,94 @@ pub(crate) fn get_commit_messages_after_tag(
         .all()?
         .filter_map(Result::ok)
         .filter(|info| !commits_to_exclude.contains(&info.id))
-        .filter_map(|info| {
-            info.object().ok().and_then(|commit| {
-                commit
-                    .decode()
-                    .ok()
-                    .map(|commit| commit.message.to_string())
-            })
+        .filter_map(|info| info.object().ok())
+        .filter_map(|commit| {
+            let decoded = commit.decode().ok()?;
+            let message = decoded.message.to_string();
+            let author = decoded.author.name.to_string();
+            let changed_paths = if with_changed_paths {
+                changed_paths(&repo, &commit)
+            } else {
+                Ok(Vec::new())
+            };
+            Some(changed_paths.map(|changed_paths| Commit {
+                id: commit.id.to_string(),
+                author,
+                message,
+                changed_paths,
+            }))
         })
-        .collect_vec();
+        .collect::<Result<Vec<_>, _>>()?;
     reverse_commits.reverse();
     Ok(reverse_commits)
 }
 
+/// A commit found by [`get_commit_messages_after_tag`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub(crate) struct Commit {
+    /// The full hex ID of the commit
+    pub(crate) id: String,
+    /// The name of the author of the commit
+    pub(crate) author: String,
+    pub(crate) message: String,
+    /// Paths of the files that this commit added, modified, or deleted—relative to the root of
+    /// the repository and separated by `/`. Empty if they weren't requested or can't be known.
+    pub(crate) changed_paths: Vec<String>,
+}
+
+/// The paths of every file changed by `commit`, compared to its first parent.
+///
+/// If the parent is missing, like at the edge of a shallow clone, the changes are unknown—so no
+/// paths are returned.
+fn changed_paths(repo: &gix::Repository, commit: &gix::Commit) -> Result<Vec<String>, Error> {
+    let error = |source: Box<dyn std::error::Error + Send + Sync>| ErrorKind::ChangedPaths {
+        commit: commit.id,
+        source,
+    };
+    let tree = commit.tree().map_err(|err| error(err.into()))?;
+    let parent_tree = match commit.parent_ids().next() {
+        Some(parent_id) => {
+            let Ok(parent) = parent_id.object() else {
+                return Ok(Vec::new());
+            };
+            parent
+                .into_commit()
+                .tree()
+                .map_err(|err| error(err.into()))?
+        }
+        None => repo.empty_tree(),
+    };
+    let mut recorder = Recorder::default();
+    Changes::from(TreeRefIter::from_bytes(&parent_tree.data))
+        .needed_to_obtain(
+            TreeRefIter::from_bytes(&tree.data),
+            State::default(),
+            |oid, buf| {
+                let object = repo.find_object(oid)?;
+                buf.clear();
+                buf.extend_from_slice(&object.data);
+                Ok::<_, gix::object::find::existing::Error>(TreeRefIter::from_bytes(buf))
+            },
+            &mut recorder,
+        )
+        .map_err(|err| error(err.into()))?;
+    Ok(recorder
+        .records
+        .into_iter()
+        .filter_map(|change| match change {
+            RecordedChange::Addition {
+                entry_mode, path, ..
+            }
+            | RecordedChange::Deletion {
+                entry_mode, path, ..
+            }
+            | RecordedChange::Modification {
+                entry_mode, path, ..
+            } => (!entry_mode.is_tree()).then(|| path.to_string()),
+        })
+        .collect())
+}
+
 pub(crate) fn create_tag(dry_run: DryRun, name: String) -> Result<(), Error> {
     if let Some(stdout) = dry_run {
         return writeln!(stdout, "Would create Git tag {name}")
@@ -477,13 +571,32 @@ pub(crate) fn create_tag(dry_run: DryRun, name: String) -> Result<(), Error> {
 }
 
 pub(crate) fn get_current_versions_from_tags(
-    prefix: Option<&str>,
+    tag_template: &TagTemplate,
+    pep440: bool,
     verbose: Verbose,
 ) -> Result<CurrentVersions, Error> {
+    let mut current_versions = CurrentVersions::default();
+    for (_, version) in get_tagged_versions(tag_template, pep440, verbose)? {
+        let is_stable = !version.is_prerelease();
+        current_versions.update_version(version);
+        if is_stable {
+            break; // Only prereleases newer than the last stable version are relevant
+        }
+    }
+
+    Ok(current_versions)
+}
+
+/// Every tag made by `tag_template` on the current branch, along with its version—newest first.
+///
+/// If `pep440`, versions which aren't valid semver are parsed as PEP 440 versions, like `1.0.0a1`.
+/// That's only for Python packages, since it would read floating tags like `v1` as `1.0.0`.
+pub(crate) fn get_tagged_versions(
+    tag_template: &TagTemplate,
+    pep440: bool,
+    verbose: Verbose,
+) -> Result<Vec<(String, Version)>, Error> {
     let repo = gix::open(current_dir().map_err(ErrorKind::CurrentDirectory)?)?;
-    let pattern = prefix
-        .as_ref()
-        .map_or_else(|| String::from("v"), |prefix| format!("{prefix}/v"));
     let mut all_tags: HashMap<ObjectId, Vec<String>> = HashMap::new();
     for (id, tag) in repo
         .references()?
@@ -501,7 +614,7 @@ pub(crate) fn get_current_versions_from_tags(
                 )
             })
         })
-        .filter(|(_id, tag_name)| tag_name.starts_with(&pattern))
+        .filter(|(_id, tag_name)| tag_template.version_from_tag(tag_name).is_some())
     {
         all_tags.entry(id).or_default().push(tag);
     }
@@ -526,21 +639,28 @@ pub(crate) fn get_current_versions_from_tags(
             );
         }
         if tags.is_empty() {
-            println!("No tags found matching pattern {pattern}");
+            println!("No tags found matching template {tag_template}");
         }
     }
 
-    let mut current_versions = CurrentVersions::default();
-    for tag in tags {
-        let version_string = tag.replace(&pattern, "");
-        if let Ok(version) = Version::from_str(version_string.as_str()) {
-            let is_stable = !version.is_prerelease();
-            current_versions.update_version(version);
-            if is_stable {
-                break; // Only prereleases newer than the last stable version are relevant
-            }
-        }
-    }
+    Ok(tags
+        .into_iter()
+        .filter_map(|tag| {
+            let version = tag_template
+                .version_from_tag(&tag)
+                .and_then(|version_string| {
+                    Version::from_str(version_string)
+                        .ok()
+                        .or_else(|| pep440.then(|| pep440::parse(version_string).ok()).flatten())
+                })?;
+            Some((tag, version))
+        })
+        .collect())
+}
 
-    Ok(current_versions)
+/// The abbreviated hash of the current commit, like `git rev-parse --short HEAD`.
+pub(crate) fn short_head_commit_id() -> Result<String, Error> {
+    let repo = gix::open(current_dir().map_err(ErrorKind::CurrentDirectory)?)?;
+    let head_commit = repo.head_commit()?;
+    Ok(head_commit.id.to_hex_with_len(7).to_string())
 }
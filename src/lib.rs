@@ -147,7 +147,8 @@ fn build_cli(config: &ConfigSource) -> Command {
         }
         ConfigSource::File(config) => {
             command = command.arg(arg!(--upgrade "Upgrade to the latest `knope.toml` syntax from any deprecated (but still supported) syntax."));
-            command = command.arg(arg!(--validate "Check that the `knope.toml` file is valid."));
+            command = command
+                .arg(arg!(--validate "Check that the `knope.toml` file and changelogs are valid."));
             config
         }
     };
@@ -203,6 +204,7 @@ fn create_state(
 ) -> Result<(State, Vec<Workflow>)> {
     let Config {
         mut packages,
+        package_groups,
         workflows,
         jira,
         github,
@@ -255,7 +257,7 @@ fn create_state(
         }
     }
 
-    let state = State::new(jira, github, packages, verbose);
+    let state = State::new(jira, github, packages, package_groups, verbose);
     Ok((state, workflows))
 }
 
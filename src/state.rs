@@ -14,6 +14,7 @@ pub(crate) struct State {
     pub(crate) github_config: Option<config::GitHub>,
     pub(crate) issue: Issue,
     pub(crate) packages: Vec<releases::Package>,
+    pub(crate) package_groups: Vec<releases::PackageGroup>,
     pub(crate) verbose: Verbose,
 }
 
@@ -23,6 +24,7 @@ impl State {
         jira_config: Option<config::Jira>,
         github_config: Option<config::GitHub>,
         packages: Vec<releases::Package>,
+        package_groups: Vec<releases::PackageGroup>,
         verbose: Verbose,
     ) -> Self {
         State {
@@ -31,6 +33,7 @@ impl State {
             github_config,
             issue: Issue::Initial,
             packages,
+            package_groups,
             verbose,
         }
     }
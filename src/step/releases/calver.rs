@@ -0,0 +1,293 @@
+use std::{fmt, fmt::Display, str::FromStr};
+
+use itertools::Itertools;
+use miette::Diagnostic;
+use thiserror::Error;
+use time::Date;
+
+use super::{
+    semver::{next_prerelease, StableVersion, Version},
+    CurrentVersions, Rule,
+};
+use crate::workflow::Verbose;
+
+/// A [calendar versioning](https://calver.org) format, like `YYYY.MM.MICRO`.
+///
+/// Versions have to stay valid semantic versions, so a format is always three segments: a year,
+/// then the month, then either the day or `MICRO`—a counter of releases within the same month.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct CalVerFormat([Segment; 3]);
+
+impl Default for CalVerFormat {
+    fn default() -> Self {
+        Self([Segment::FullYear, Segment::Month, Segment::Micro])
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Segment {
+    /// `YYYY`, like `2024`
+    FullYear,
+    /// `YY`, like `24` (years since 2000)
+    ShortYear,
+    /// `MM`, like `1` or `12`
+    Month,
+    /// `DD`, like `1` or `31`
+    Day,
+    /// `MICRO`, incremented for each release within the same period
+    Micro,
+}
+
+impl Segment {
+    fn value(self, date: Date) -> u64 {
+        match self {
+            Self::FullYear => u64::try_from(date.year()).unwrap_or_default(),
+            Self::ShortYear => u64::try_from(date.year() - 2000).unwrap_or_default(),
+            Self::Month => u64::from(u8::from(date.month())),
+            Self::Day => u64::from(date.day()),
+            Self::Micro => 0,
+        }
+    }
+}
+
+impl FromStr for Segment {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "YYYY" => Ok(Self::FullYear),
+            "YY" => Ok(Self::ShortYear),
+            "MM" => Ok(Self::Month),
+            "DD" => Ok(Self::Day),
+            "MICRO" => Ok(Self::Micro),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Display for Segment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::FullYear => write!(f, "YYYY"),
+            Self::ShortYear => write!(f, "YY"),
+            Self::Month => write!(f, "MM"),
+            Self::Day => write!(f, "DD"),
+            Self::Micro => write!(f, "MICRO"),
+        }
+    }
+}
+
+impl FromStr for CalVerFormat {
+    type Err = Error;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        let segments = format
+            .split('.')
+            .map(Segment::from_str)
+            .collect::<Result<Vec<_>, _>>()
+            .ok()
+            .and_then(|segments| <[Segment; 3]>::try_from(segments).ok())
+            .ok_or_else(|| Error::InvalidFormat(format.to_string()))?;
+        match segments {
+            [Segment::FullYear | Segment::ShortYear, Segment::Month, Segment::Day | Segment::Micro] => {
+                Ok(Self(segments))
+            }
+            _ => Err(Error::InvalidFormat(format.to_string())),
+        }
+    }
+}
+
+impl Display for CalVerFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.iter().join("."))
+    }
+}
+
+impl CalVerFormat {
+    /// The next stable version released on `today`, after the latest `stable` version.
+    fn next_stable(
+        &self,
+        stable: Option<StableVersion>,
+        today: Date,
+    ) -> Result<StableVersion, Error> {
+        let [major, minor, patch] = self.0.map(|segment| segment.value(today));
+        let mut next = StableVersion {
+            major,
+            minor,
+            patch,
+        };
+        if let Some(stable) = stable {
+            if self.0[2] == Segment::Micro && stable.major == major && stable.minor == minor {
+                next.patch = stable.patch + 1;
+            } else if stable >= next {
+                return Err(Error::AlreadyReleased {
+                    version: stable.to_string(),
+                    format: self.to_string(),
+                });
+            }
+        }
+        Ok(next)
+    }
+}
+
+/// Determine the next version of a package which uses calendar versioning.
+///
+/// The version is based on `today`, not on `rule`—except that [`Rule::Pre`] still creates a
+/// pre-release and [`Rule::Release`] still releases the latest pre-release.
+pub(crate) fn bump(
+    mut versions: CurrentVersions,
+    rule: &Rule,
+    format: &CalVerFormat,
+    today: Date,
+    verbose: Verbose,
+) -> Result<Version, Error> {
+    if let Rule::Release = rule {
+        return versions
+            .prereleases
+            .pop_last()
+            .map(|(stable_component, _pre)| Version::from(stable_component))
+            .ok_or(Error::NoPrerelease);
+    }
+    let next = format.next_stable(versions.stable, today)?;
+    if let Verbose::Yes = verbose {
+        match versions.stable {
+            Some(stable) => {
+                println!("Using CalVer format {format} to bump from {stable} to {next}");
+            }
+            None => println!("Using CalVer format {format} to create {next}"),
+        }
+    }
+    match rule {
+        Rule::Pre { label, .. } => Ok(next_prerelease(next, &versions.prereleases, label, verbose)),
+        _ => Ok(Version::from(next)),
+    }
+}
+
+#[derive(Debug, Diagnostic, Error)]
+pub(crate) enum Error {
+    #[error("Invalid CalVer format {0}")]
+    #[diagnostic(
+        code(calver::invalid_format),
+        help(
+            "The format must be a year (`YYYY` or `YY`), then `MM`, then `DD` or `MICRO`, \
+            separated by `.`—like `YYYY.MM.MICRO`."
+        ),
+        url("https://knope-dev.github.io/knope/config/packages.html#versioning")
+    )]
+    InvalidFormat(String),
+    #[error(
+        "Version {version} was already released, there is no newer version for format {format} yet"
+    )]
+    #[diagnostic(
+        code(calver::already_released),
+        help("Use `MICRO` as the last segment of the format to release more than once a day."),
+        url("https://knope-dev.github.io/knope/config/packages.html#versioning")
+    )]
+    AlreadyReleased { version: String, format: String },
+    #[error("No prerelease version found, but a Release rule was requested")]
+    #[diagnostic(
+        code(calver::no_prerelease),
+        help("The Release rule releases the latest pre-release, so create a pre-release first."),
+        url("https://knope-dev.github.io/knope/config/step/BumpVersion.html#release")
+    )]
+    NoPrerelease,
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use time::macros::date;
+
+    use super::*;
+    use crate::step::releases::semver::{ConventionalRule, Label};
+
+    const TODAY: Date = date!(2024 - 03 - 15);
+
+    fn calver(format: &str, versions: CurrentVersions, rule: &Rule) -> Result<Version, Error> {
+        bump(
+            versions,
+            rule,
+            &CalVerFormat::from_str(format).unwrap(),
+            TODAY,
+            Verbose::No,
+        )
+    }
+
+    #[test]
+    fn first_release() {
+        assert_eq!(
+            calver("YYYY.MM.MICRO", CurrentVersions::default(), &Rule::Patch).unwrap(),
+            Version::new(2024, 3, 0, None)
+        );
+        assert_eq!(
+            calver("YY.MM.DD", CurrentVersions::default(), &Rule::Major).unwrap(),
+            Version::new(24, 3, 15, None)
+        );
+    }
+
+    #[test]
+    fn micro_in_same_month() {
+        let versions = CurrentVersions::from(Version::new(2024, 3, 4, None));
+        assert_eq!(
+            calver("YYYY.MM.MICRO", versions, &Rule::Minor).unwrap(),
+            Version::new(2024, 3, 5, None)
+        );
+    }
+
+    #[test]
+    fn micro_resets_in_new_month() {
+        let versions = CurrentVersions::from(Version::new(2024, 2, 4, None));
+        assert_eq!(
+            calver("YYYY.MM.MICRO", versions, &Rule::Minor).unwrap(),
+            Version::new(2024, 3, 0, None)
+        );
+    }
+
+    #[test]
+    fn already_released_today() {
+        let versions = CurrentVersions::from(Version::new(2024, 3, 15, None));
+        let result = calver("YYYY.MM.DD", versions, &Rule::Patch);
+        assert!(
+            matches!(result, Err(Error::AlreadyReleased { .. })),
+            "Expected AlreadyReleased, got {result:?}"
+        );
+    }
+
+    #[test]
+    fn prerelease() {
+        let mut versions = CurrentVersions::from(Version::new(2024, 3, 0, None));
+        versions.update_version(Version::from_str("2024.3.1-rc.0").unwrap());
+        let rule = Rule::Pre {
+            label: Label::from("rc"),
+            stable_rule: ConventionalRule::default(),
+        };
+        assert_eq!(
+            calver("YYYY.MM.MICRO", versions.clone(), &rule).unwrap(),
+            Version::from_str("2024.3.1-rc.1").unwrap()
+        );
+        assert_eq!(
+            calver("YYYY.MM.MICRO", versions, &Rule::Release).unwrap(),
+            Version::new(2024, 3, 1, None)
+        );
+    }
+
+    #[test]
+    fn invalid_formats() {
+        for format in [
+            "YYYY.MM",
+            "MM.YYYY.MICRO",
+            "YYYY.DD.MICRO",
+            "YYYY.0M.MICRO",
+            "",
+        ] {
+            assert!(
+                matches!(CalVerFormat::from_str(format), Err(Error::InvalidFormat(_))),
+                "{format} should be invalid"
+            );
+        }
+        assert_eq!(
+            CalVerFormat::from_str("YY.MM.MICRO").unwrap().to_string(),
+            "YY.MM.MICRO"
+        );
+    }
+}
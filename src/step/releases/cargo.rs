@@ -1,45 +1,259 @@
 use std::{
+    collections::BTreeMap,
+    fmt,
+    ops::Range,
     path::{Path, PathBuf},
     str::FromStr,
 };
 
+use itertools::Itertools;
 use miette::Diagnostic;
-use serde::Deserialize;
+use serde::{
+    de::{IgnoredAny, MapAccess, Visitor},
+    Deserialize, Deserializer,
+};
 use thiserror::Error;
 use toml::Spanned;
 
-use super::semver::Version;
+use super::{dependencies::update_requirement, semver::Version};
 use crate::{dry_run::DryRun, fs};
 
+/// Get the version of the crate defined in `content`.
+///
+/// If the crate inherits its version from a workspace (`version.workspace = true`), the version
+/// is read from `[workspace.package]` of the nearest workspace root instead.
+///
+/// `path` is used for error reporting and to locate the workspace root.
 pub(crate) fn get_version(content: &str, path: &Path) -> Result<Version, Error> {
+    let doc = parse(content, path)?;
+    let version = match doc.version_source(content, path)? {
+        VersionSource::Local { version, .. } => version,
+        VersionSource::Workspace(root_path) => {
+            let root_content = fs::read_to_string(&root_path)?;
+            parse(&root_content, &root_path)?
+                .workspace_version()
+                .ok_or(Error::NoWorkspaceVersion(root_path))?
+                .into_inner()
+        }
+    };
+    Version::from_str(&version).map_err(Error::from)
+}
+
+pub(crate) fn set_version(
+    dry_run: DryRun,
+    mut cargo_toml: String,
+    new_version: &str,
+    path: &Path,
+) -> Result<String, Error> {
+    let doc = parse(&cargo_toml, path)?;
+
+    let mut replacements = doc.dependency_replacements(path, new_version);
+    match doc.version_source(&cargo_toml, path)? {
+        VersionSource::Local { span, .. } => replacements.push(quoted_span(span, new_version)),
+        VersionSource::Workspace(root_path) => {
+            let root_content = fs::read_to_string(&root_path)?;
+            set_version(dry_run, root_content, new_version, &root_path)?;
+        }
+    }
+    if let Some(workspace_version) = doc.workspace_version() {
+        let replacement = quoted_span(workspace_version.span(), new_version);
+        if !replacements.contains(&replacement) {
+            replacements.push(replacement);
+        }
+    }
+
+    replace_all(&mut cargo_toml, replacements);
+    fs::write(dry_run, new_version, path, &cargo_toml)?;
+
+    Ok(cargo_toml)
+}
+
+/// The name of the crate defined in `content`, if any.
+pub(crate) fn package_name(content: &str) -> Option<String> {
+    toml::from_str::<Cargo>(content).ok()?.package?.name
+}
+
+/// The names of every crate that `content` requires a version of, except for dev-dependencies.
+pub(crate) fn dependencies(content: &str) -> Vec<String> {
     toml::from_str::<Cargo>(content)
-        .map(|cargo| cargo.package.version.into_inner())
-        .map_err(|source| Error::Deserialize {
-            path: path.into(),
-            source,
+        .map(|doc| {
+            doc.requirements()
+                .map(|(name, _)| name.to_string())
+                .collect()
         })
-        .and_then(|version| Version::from_str(&version).map_err(Error::from))
+        .unwrap_or_default()
 }
 
-pub(crate) fn set_version(
+/// Set the requirement on the crate named `dependency` to `new_version`, keeping its operator.
+///
+/// `path` is used for error reporting.
+pub(crate) fn set_dependency_version(
     dry_run: DryRun,
     mut cargo_toml: String,
+    dependency: &str,
     new_version: &str,
     path: &Path,
 ) -> Result<String, Error> {
-    let doc: Cargo = toml::from_str(&cargo_toml).map_err(|source| Error::Deserialize {
-        path: path.into(),
+    let doc = parse(&cargo_toml, path)?;
+    let replacements = doc
+        .requirements()
+        .filter(|(name, _)| *name == dependency)
+        .filter_map(|(_, (span, requirement))| {
+            let new_requirement = update_requirement(requirement, new_version)?;
+            Some(quoted_span(span, &new_requirement))
+        })
+        .unique()
+        .collect_vec();
+    if replacements.is_empty() {
+        return Ok(cargo_toml);
+    }
+    replace_all(&mut cargo_toml, replacements);
+    fs::write(
+        dry_run,
+        &format!("{dependency} {new_version}"),
+        path,
+        &cargo_toml,
+    )?;
+    Ok(cargo_toml)
+}
+
+/// Every file other than `path` which will be modified by [`set_version`] or [`set_lockfile_versions`].
+pub(crate) fn related_paths(content: &str, path: &Path) -> Vec<PathBuf> {
+    parse(content, path)
+        .and_then(|doc| doc.version_source(content, path))
+        .ok()
+        .and_then(|source| match source {
+            VersionSource::Workspace(root_path) => Some(root_path),
+            VersionSource::Local { .. } => None,
+        })
+        .into_iter()
+        .chain(find_lockfile(path))
+        .collect()
+}
+
+/// The crates whose version is defined by a manifest, and the `Cargo.lock` which records them.
+#[derive(Debug, Eq, PartialEq)]
+pub(crate) struct LockedCrates {
+    pub(crate) lockfile: PathBuf,
+    pub(crate) names: Vec<String>,
+    /// The version of the crates before any changes are made.
+    pub(crate) version: String,
+}
+
+/// Determine which entries in `Cargo.lock` need to change when the manifest at `path` does.
+///
+/// For a crate which defines its own version, that's only the crate itself. When the version
+/// comes from `[workspace.package]`, that's every crate which inherits it.
+pub(crate) fn locked_crates(content: &str, path: &Path) -> Option<LockedCrates> {
+    let lockfile = find_lockfile(path)?;
+    let doc = parse(content, path).ok()?;
+    let (names, version) = match doc.version_source(content, path).ok()? {
+        VersionSource::Local {
+            version,
+            is_workspace,
+            ..
+        } => {
+            let names = if is_workspace {
+                inheriting_crates(&doc, path)
+            } else {
+                doc.package
+                    .and_then(|package| package.name)
+                    .into_iter()
+                    .collect()
+            };
+            (names, version)
+        }
+        VersionSource::Workspace(root_path) => {
+            let root_content = std::fs::read_to_string(&root_path).ok()?;
+            let root = parse(&root_content, &root_path).ok()?;
+            let version = root.workspace_version()?.into_inner();
+            (inheriting_crates(&root, &root_path), version)
+        }
+    };
+    Some(LockedCrates {
+        lockfile,
+        names,
+        version,
+    })
+}
+
+/// Set `new_version` for every local `[[package]]` in `lockfile` matching the name and old
+/// version of one of `crates`.
+pub(crate) fn set_lockfile_versions(
+    dry_run: DryRun,
+    lockfile: &Path,
+    crates: &[(String, String)],
+    new_version: &str,
+) -> Result<(), Error> {
+    let mut content = fs::read_to_string(lockfile)?;
+    let lock: Lock = toml::from_str(&content).map_err(|source| Error::Deserialize {
+        path: lockfile.into(),
         source,
     })?;
+    let replacements = lock
+        .package
+        .iter()
+        .filter(|package| package.source.is_none())
+        .filter(|package| {
+            crates.iter().any(|(name, version)| {
+                *name == package.name && version == package.version.get_ref()
+            })
+        })
+        .map(|package| quoted_span(package.version.span(), new_version))
+        .collect::<Vec<_>>();
+    if replacements.is_empty() {
+        return Ok(());
+    }
+    replace_all(&mut content, replacements);
+    fs::write(dry_run, new_version, lockfile, &content)?;
+    Ok(())
+}
 
-    // Account for quotes with +- 1
-    let start = doc.package.version.span().start + 1;
-    let end = doc.package.version.span().end - 1;
+/// The closest `Cargo.lock` in the same directory as `path` or any parent directory.
+fn find_lockfile(path: &Path) -> Option<PathBuf> {
+    path.parent()?
+        .ancestors()
+        .map(|dir| dir.join("Cargo.lock"))
+        .find(|candidate| candidate.exists())
+}
 
-    cargo_toml.replace_range(start..end, new_version);
-    fs::write(dry_run, new_version, path, &cargo_toml)?;
+/// The names of every crate in the workspace defined by `root` which inherits its version.
+fn inheriting_crates(root: &Cargo, root_path: &Path) -> Vec<String> {
+    let root_dir = root_path.parent().unwrap_or_else(|| Path::new(""));
+    let root_package = root
+        .package
+        .as_ref()
+        .filter(|package| matches!(package.version, Some(PackageVersion::Workspace)))
+        .and_then(|package| package.name.clone());
+    let members = root
+        .workspace
+        .iter()
+        .flat_map(|workspace| workspace.members.iter())
+        .flat_map(|member| member_dirs(root_dir, member))
+        .filter_map(|member_dir| {
+            let manifest = member_dir.join("Cargo.toml");
+            let content = std::fs::read_to_string(&manifest).ok()?;
+            let package = parse(&content, &manifest).ok()?.package?;
+            matches!(package.version, Some(PackageVersion::Workspace))
+                .then_some(package.name)
+                .flatten()
+        });
+    root_package.into_iter().chain(members).collect()
+}
 
-    Ok(cargo_toml)
+/// Resolve an entry of `workspace.members`. Only a trailing `/*` glob is supported.
+fn member_dirs(root_dir: &Path, member: &str) -> Vec<PathBuf> {
+    if let Some(parent) = member.strip_suffix("/*") {
+        std::fs::read_dir(root_dir.join(parent))
+            .into_iter()
+            .flatten()
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| path.is_dir())
+            .collect()
+    } else {
+        vec![root_dir.join(member)]
+    }
 }
 
 #[derive(Debug, Diagnostic, Error)]
@@ -47,7 +261,7 @@ pub(crate) enum Error {
     #[error("Error deserializing {path}: {source}")]
     #[diagnostic(
         code(cargo::deserialize),
-        help("knope expects the Cargo.toml file to have a `package.version` property. Workspace support is coming soon!"),
+        help("knope expects the Cargo.toml file to have a `package.version` or `workspace.package.version` property."),
         url("https://knope-dev.github.io/knope/config/packages.html#supported-formats-for-versioning")
     )]
     Deserialize {
@@ -55,6 +269,27 @@ pub(crate) enum Error {
         #[source]
         source: toml::de::Error,
     },
+    #[error("No version found in {0}")]
+    #[diagnostic(
+        code(cargo::no_version),
+        help("Set `package.version`, `workspace.package.version`, or `package.version.workspace = true`."),
+        url("https://knope-dev.github.io/knope/config/packages.html#cargo-workspaces")
+    )]
+    NoVersion(PathBuf),
+    #[error("{0} inherits its version from a workspace, but no workspace root was found")]
+    #[diagnostic(
+        code(cargo::no_workspace_root),
+        help("A `Cargo.toml` containing a `[workspace]` table must exist in a parent directory."),
+        url("https://knope-dev.github.io/knope/config/packages.html#cargo-workspaces")
+    )]
+    NoWorkspaceRoot(PathBuf),
+    #[error("The workspace root {0} does not define a version")]
+    #[diagnostic(
+        code(cargo::no_workspace_version),
+        help("Add a `version` to the `[workspace.package]` table."),
+        url("https://knope-dev.github.io/knope/config/packages.html#cargo-workspaces")
+    )]
+    NoWorkspaceVersion(PathBuf),
     #[error(transparent)]
     #[diagnostic(transparent)]
     Fs(#[from] fs::Error),
@@ -63,19 +298,327 @@ pub(crate) enum Error {
     Semver(#[from] super::semver::version::Error),
 }
 
-#[derive(Debug, Deserialize)]
+fn parse(content: &str, path: &Path) -> Result<Cargo, Error> {
+    toml::from_str(content).map_err(|source| Error::Deserialize {
+        path: path.into(),
+        source,
+    })
+}
+
+/// Where the version of a crate is actually defined.
+enum VersionSource {
+    /// In the same file, `span` includes the quotes around `version`.
+    ///
+    /// `is_workspace` is true if this is `[workspace.package]` rather than `[package]`.
+    Local {
+        span: Range<usize>,
+        version: String,
+        is_workspace: bool,
+    },
+    /// In the `[workspace.package]` table of the workspace root at this path.
+    Workspace(PathBuf),
+}
+
+#[derive(Debug, Default, Deserialize)]
 struct Cargo {
-    package: Package,
+    package: Option<Package>,
+    workspace: Option<Workspace>,
+    #[serde(default)]
+    dependencies: Dependencies,
+    #[serde(default, rename = "dev-dependencies")]
+    dev_dependencies: Dependencies,
+    #[serde(default, rename = "build-dependencies")]
+    build_dependencies: Dependencies,
+}
+
+impl Cargo {
+    /// `content` must be the same content `self` was parsed from.
+    fn version_source(&self, content: &str, path: &Path) -> Result<VersionSource, Error> {
+        let version = self
+            .package
+            .as_ref()
+            .and_then(|package| package.version.as_ref());
+        match version {
+            Some(PackageVersion::Literal) => {
+                // Spans aren't available through a custom `Deserialize`, so parse again
+                let version = toml::from_str::<LiteralCargo>(content)
+                    .map_err(|source| Error::Deserialize {
+                        path: path.into(),
+                        source,
+                    })?
+                    .package
+                    .version;
+                Ok(VersionSource::Local {
+                    span: version.span(),
+                    version: version.into_inner(),
+                    is_workspace: false,
+                })
+            }
+            Some(PackageVersion::Workspace) if self.workspace.is_none() => {
+                find_workspace_root(path)
+                    .map(VersionSource::Workspace)
+                    .ok_or_else(|| Error::NoWorkspaceRoot(path.into()))
+            }
+            // Either a workspace root without a root package, or a root package inheriting from
+            // its own workspace.
+            Some(PackageVersion::Workspace) | None => self
+                .workspace_version()
+                .map(|version| VersionSource::Local {
+                    span: version.span(),
+                    version: version.into_inner(),
+                    is_workspace: true,
+                })
+                .ok_or_else(|| Error::NoVersion(path.into())),
+        }
+    }
+
+    /// Every dependency with a version requirement, except for dev-dependencies.
+    ///
+    /// Yields the name of the required crate, the span of the requirement (including quotes),
+    /// and the requirement itself.
+    fn requirements(&self) -> impl Iterator<Item = (&str, (Range<usize>, &str))> {
+        self.workspace
+            .iter()
+            .flat_map(|workspace| workspace.dependencies.iter())
+            .chain(self.dependencies.iter())
+            .chain(self.build_dependencies.iter())
+            .filter_map(|(key, dependency)| {
+                let name = dependency.get_ref().package.as_deref().unwrap_or(key);
+                let requirement = match (
+                    &dependency.get_ref().version,
+                    &dependency.get_ref().requirement,
+                ) {
+                    (Some(version), _) => (version.span(), version.get_ref().as_str()),
+                    (None, Some(requirement)) => (dependency.span(), requirement.as_str()),
+                    (None, None) => return None,
+                };
+                Some((name, requirement))
+            })
+    }
+
+    fn workspace_version(&self) -> Option<Spanned<String>> {
+        self.workspace
+            .as_ref()
+            .and_then(|workspace| workspace.package.as_ref())
+            .and_then(|package| package.version.clone())
+    }
+
+    /// Find every intra-workspace dependency requirement which needs to change to `new_version`.
+    ///
+    /// Intra-workspace dependencies are those with a `path` pointing at a crate which inherits
+    /// its version from the workspace—these are always released together.
+    fn dependency_replacements(&self, path: &Path, new_version: &str) -> Vec<Replacement> {
+        let manifest_dir = path.parent().unwrap_or_else(|| Path::new(""));
+        self.workspace
+            .iter()
+            .flat_map(|workspace| workspace.dependencies.values())
+            .chain(self.dependencies.values())
+            .chain(self.dev_dependencies.values())
+            .chain(self.build_dependencies.values())
+            .map(Spanned::get_ref)
+            .filter_map(|dependency| {
+                let version = dependency.version.as_ref()?;
+                let dependency_path = manifest_dir.join(dependency.path.as_ref()?);
+                let dependency_manifest = dependency_path.join("Cargo.toml");
+                let content = std::fs::read_to_string(&dependency_manifest).ok()?;
+                let inherits = parse(&content, &dependency_manifest)
+                    .ok()?
+                    .package?
+                    .version
+                    .is_some_and(|version| matches!(version, PackageVersion::Workspace));
+                inherits.then(|| requirement_span(version, new_version))
+            })
+            .collect()
+    }
+}
+
+/// Look through every parent directory of `path` for a `Cargo.toml` which defines a workspace.
+fn find_workspace_root(path: &Path) -> Option<PathBuf> {
+    path.parent()?
+        .ancestors()
+        .skip(1)
+        .map(|dir| dir.join("Cargo.toml"))
+        .find(|candidate| {
+            std::fs::read_to_string(candidate)
+                .ok()
+                .and_then(|content| parse(&content, candidate).ok())
+                .is_some_and(|doc| doc.workspace.is_some())
+        })
 }
 
 #[derive(Debug, Deserialize)]
 struct Package {
+    name: Option<String>,
+    version: Option<PackageVersion>,
+}
+
+/// The `package.version` key, which is either a string or `{ workspace = true }`.
+#[derive(Debug)]
+enum PackageVersion {
+    Literal,
+    Workspace,
+}
+
+#[derive(Debug, Deserialize)]
+struct LiteralCargo {
+    package: LiteralPackage,
+}
+
+#[derive(Debug, Deserialize)]
+struct LiteralPackage {
     version: Spanned<String>,
 }
 
+impl<'de> Deserialize<'de> for PackageVersion {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct PackageVersionVisitor;
+
+        impl<'de> Visitor<'de> for PackageVersionVisitor {
+            type Value = PackageVersion;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a version string or `{ workspace = true }`")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, _: &str) -> Result<Self::Value, E> {
+                Ok(PackageVersion::Literal)
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut workspace = false;
+                while let Some(key) = map.next_key::<String>()? {
+                    if key == "workspace" {
+                        workspace = map.next_value()?;
+                    } else {
+                        map.next_value::<IgnoredAny>()?;
+                    }
+                }
+                if workspace {
+                    Ok(PackageVersion::Workspace)
+                } else {
+                    Err(serde::de::Error::custom(
+                        "expected `workspace = true` for an inherited version",
+                    ))
+                }
+            }
+        }
+
+        deserializer.deserialize_any(PackageVersionVisitor)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Workspace {
+    #[serde(default)]
+    members: Vec<String>,
+    package: Option<WorkspacePackage>,
+    #[serde(default)]
+    dependencies: Dependencies,
+}
+
+#[derive(Debug, Deserialize)]
+struct WorkspacePackage {
+    version: Option<Spanned<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Lock {
+    #[serde(default)]
+    package: Vec<LockPackage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LockPackage {
+    name: String,
+    version: Spanned<String>,
+    source: Option<String>,
+}
+
+type Dependencies = BTreeMap<String, Spanned<Dependency>>;
+
+/// A single dependency, only tracking the keys needed to update requirements.
+#[derive(Debug, Default)]
+struct Dependency {
+    path: Option<String>,
+    version: Option<Spanned<String>>,
+    /// The name of the crate, if it's different from the key
+    package: Option<String>,
+    /// A bare version requirement, like `serde = "1.0"`
+    requirement: Option<String>,
+}
+
+impl<'de> Deserialize<'de> for Dependency {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct DependencyVisitor;
+
+        impl<'de> Visitor<'de> for DependencyVisitor {
+            type Value = Dependency;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a version requirement or a dependency table")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, requirement: &str) -> Result<Self::Value, E> {
+                // A bare version requirement can't point at a path, so it's never intra-workspace
+                Ok(Dependency {
+                    requirement: Some(requirement.to_string()),
+                    ..Dependency::default()
+                })
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut dependency = Dependency::default();
+                while let Some(key) = map.next_key::<String>()? {
+                    match key.as_str() {
+                        "path" => dependency.path = Some(map.next_value()?),
+                        "version" => dependency.version = Some(map.next_value()?),
+                        "package" => dependency.package = Some(map.next_value()?),
+                        _ => {
+                            map.next_value::<IgnoredAny>()?;
+                        }
+                    }
+                }
+                Ok(dependency)
+            }
+        }
+
+        deserializer.deserialize_any(DependencyVisitor)
+    }
+}
+
+/// A range of the original content and what to replace it with.
+type Replacement = (Range<usize>, String);
+
+/// Replace the range within the quotes of a TOML string spanning `span`.
+fn quoted_span(span: Range<usize>, new_version: &str) -> Replacement {
+    // Account for quotes with +- 1
+    (span.start + 1..span.end - 1, new_version.to_string())
+}
+
+/// Replace the version in a requirement like `^1.2.3` or `=1.2.3`, keeping the operator.
+fn requirement_span(requirement: &Spanned<String>, new_version: &str) -> Replacement {
+    let (range, _) = quoted_span(requirement.span(), new_version);
+    let operator = requirement
+        .get_ref()
+        .chars()
+        .take_while(|c| matches!(c, '^' | '~' | '=' | '>' | '<' | ' '))
+        .collect::<String>();
+    (range, format!("{operator}{new_version}"))
+}
+
+/// Apply all `replacements` from last to first so that earlier spans stay valid.
+fn replace_all(content: &mut String, mut replacements: Vec<Replacement>) {
+    replacements.sort_by_key(|(range, _)| range.start);
+    for (range, replacement) in replacements.into_iter().rev() {
+        content.replace_range(range, &replacement);
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use std::path::Path;
+    use std::{fs::write, path::Path};
+
+    use pretty_assertions::assert_eq;
 
     use super::*;
     use crate::dry_run::fake_dry_run;
@@ -113,4 +656,176 @@ mod tests {
         let expected = content.replace("0.1.0-rc.0", "1.2.3-rc.4");
         assert_eq!(new, expected);
     }
+
+    const WORKSPACE_ROOT: &str = r#"[workspace]
+members = ["crates/*"]
+
+[workspace.package]
+version = "0.1.0"
+
+[workspace.dependencies]
+first = { path = "crates/first", version = "0.1.0" }
+serde = "1.0"
+"#;
+
+    const MEMBER: &str = r#"[package]
+name = "second"
+version.workspace = true
+
+[dependencies]
+first = { path = "../first", version = "=0.1.0" }
+other = { version = "0.1.0" }
+"#;
+
+    #[test]
+    fn workspace_root_version() {
+        assert_eq!(
+            get_version(WORKSPACE_ROOT, Path::new("Cargo.toml")).unwrap(),
+            Version::new(0, 1, 0, None)
+        );
+    }
+
+    #[test]
+    fn inherited_version() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let member_dir = temp_dir.path().join("crates").join("second");
+        std::fs::create_dir_all(&member_dir).unwrap();
+        write(temp_dir.path().join("Cargo.toml"), WORKSPACE_ROOT).unwrap();
+
+        assert_eq!(
+            get_version(MEMBER, &member_dir.join("Cargo.toml")).unwrap(),
+            Version::new(0, 1, 0, None)
+        );
+    }
+
+    #[test]
+    fn inherited_version_without_workspace() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let result = get_version(MEMBER, &temp_dir.path().join("Cargo.toml"));
+        assert!(
+            matches!(result, Err(Error::NoWorkspaceRoot(_))),
+            "Expected NoWorkspaceRoot, got {result:?}"
+        );
+    }
+
+    #[test]
+    fn set_inherited_version() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let crates = temp_dir.path().join("crates");
+        for member in ["first", "second"] {
+            std::fs::create_dir_all(crates.join(member)).unwrap();
+        }
+        let root_path = temp_dir.path().join("Cargo.toml");
+        write(&root_path, WORKSPACE_ROOT).unwrap();
+        write(
+            crates.join("first").join("Cargo.toml"),
+            "[package]\nname = \"first\"\nversion.workspace = true\n",
+        )
+        .unwrap();
+        let member_path = crates.join("second").join("Cargo.toml");
+        write(&member_path, MEMBER).unwrap();
+
+        let new_member = set_version(&mut None, MEMBER.to_string(), "0.2.0", &member_path).unwrap();
+
+        assert_eq!(
+            new_member,
+            MEMBER.replace("=0.1.0", "=0.2.0"),
+            "Only the path dependency should change in the member"
+        );
+        assert_eq!(
+            std::fs::read_to_string(&root_path).unwrap(),
+            WORKSPACE_ROOT
+                .replace("version = \"0.1.0\"\n\n", "version = \"0.2.0\"\n\n")
+                .replace(
+                    "path = \"crates/first\", version = \"0.1.0\"",
+                    "path = \"crates/first\", version = \"0.2.0\""
+                )
+        );
+        assert_eq!(related_paths(MEMBER, &member_path), vec![root_path]);
+    }
+
+    #[test]
+    fn set_lockfile_version() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manifest_path = temp_dir.path().join("Cargo.toml");
+        let lockfile = temp_dir.path().join("Cargo.lock");
+        let manifest = "[package]\nname = \"tester\"\nversion = \"0.1.0\"\n";
+        let lock = r#"version = 3
+
+[[package]]
+name = "tester"
+version = "0.1.0"
+dependencies = ["other"]
+
+[[package]]
+name = "other"
+version = "0.1.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+"#;
+        write(&lockfile, lock).unwrap();
+
+        let locked = locked_crates(manifest, &manifest_path).unwrap();
+        assert_eq!(
+            locked,
+            LockedCrates {
+                lockfile: lockfile.clone(),
+                names: vec![String::from("tester")],
+                version: String::from("0.1.0"),
+            }
+        );
+
+        set_lockfile_versions(
+            &mut None,
+            &lockfile,
+            &[(String::from("tester"), String::from("0.1.0"))],
+            "0.2.0",
+        )
+        .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&lockfile).unwrap(),
+            lock.replacen("0.1.0", "0.2.0", 1),
+            "Only the local crate should change"
+        );
+    }
+
+    #[test]
+    fn dependencies() {
+        let content = r#"[package]
+name = "app"
+version = "1.0.0"
+
+[dependencies]
+core = "^1.2.3"
+renamed = { package = "core-macros", version = "~1.2" }
+local = { path = "../local" }
+
+[dev-dependencies]
+test-helpers = "1.0.0"
+"#;
+
+        assert_eq!(package_name(content).as_deref(), Some("app"));
+        assert_eq!(super::dependencies(content), vec!["core", "core-macros"]);
+
+        let new = set_dependency_version(
+            &mut fake_dry_run(),
+            String::from(content),
+            "core",
+            "2.0.0",
+            Path::new(""),
+        )
+        .unwrap();
+        let new = set_dependency_version(
+            &mut fake_dry_run(),
+            new,
+            "core-macros",
+            "2.0.0",
+            Path::new(""),
+        )
+        .unwrap();
+
+        let expected = content
+            .replace("^1.2.3", "^2.0.0")
+            .replace("~1.2", "~2.0.0");
+        assert_eq!(new, expected);
+    }
 }
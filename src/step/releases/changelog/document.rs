@@ -0,0 +1,597 @@
+//! A changelog parsed into the entries for each version, tolerant of the common heading styles:
+//!
+//! - `## 1.2.3 (2023-01-01)`, which knope writes by default
+//! - `## [1.2.3] - 2023-01-01` from [Keep a Changelog](https://keepachangelog.com)
+//! - `## [1.2.3](https://github.com/...) (2023-01-01)` or `## v1.2.3`
+//! - Any heading level, like `# 1.2.3`, as long as every version uses the same one
+
+use std::{ops::Range, str::FromStr};
+
+use crate::step::releases::semver::Version;
+
+/// The parts of a changelog that knope reads and writes.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct Document<'content> {
+    /// The heading level of versions, like `2` for `## 1.2.3`
+    pub(crate) level: usize,
+    /// A Keep a Changelog style `## [Unreleased]` section
+    pub(crate) unreleased: Option<ReleaseEntry<'content>>,
+    /// The entry for every version, in the order they appear
+    pub(crate) releases: Vec<ReleaseEntry<'content>>,
+    /// Where the link reference definitions at the end of the file start
+    pub(crate) footer_start: usize,
+}
+
+/// The entry for one version (or the Unreleased section) in a changelog.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct ReleaseEntry<'content> {
+    /// `None` for the Unreleased section
+    pub(crate) version: Option<Version>,
+    /// The version as written in the heading, without any `v` prefix
+    pub(crate) version_text: Span<'content>,
+    /// The date after the version, like `2023-01-01` in `## 1.2.3 (2023-01-01)`
+    pub(crate) date: Option<Span<'content>>,
+    /// The heading line, without the line break
+    pub(crate) heading: Span<'content>,
+    /// Everything after the heading, up to the next version or the end of the changelog
+    pub(crate) body: Span<'content>,
+    pub(crate) sections: Vec<Section>,
+}
+
+/// Some text of a changelog, along with where it is.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct Span<'content> {
+    pub(crate) text: &'content str,
+    pub(crate) range: Range<usize>,
+}
+
+/// A group of changes in an entry, like `### Features`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct Section {
+    /// `None` for changes before the first section heading
+    pub(crate) title: Option<String>,
+    /// Every list item, or heading in knope's own format, with the text that follows it
+    pub(crate) entries: Vec<String>,
+}
+
+/// A line of a changelog, without its line break.
+struct Line<'content> {
+    text: &'content str,
+    start: usize,
+}
+
+impl<'content> Document<'content> {
+    pub(crate) fn parse(content: &'content str) -> Self {
+        let lines = lines(content);
+        let footer_start = footer_start(content, &lines);
+        let mut fences = Fences::default();
+        let is_code: Vec<bool> = lines.iter().map(|line| fences.is_code(line.text)).collect();
+        // Lines like `# Title` in code blocks are examples, not headings
+        let heading_at = |index: usize| {
+            lines
+                .get(index)
+                .filter(|_| !is_code.get(index).copied().unwrap_or_default())
+                .and_then(|line| heading(line.text))
+        };
+
+        let mut level = None;
+        let mut headings = Vec::new();
+        for (index, line) in lines.iter().enumerate() {
+            if line.start >= footer_start {
+                break;
+            }
+            let Some((heading_level, _)) = heading_at(index) else {
+                continue;
+            };
+            if level.map_or(heading_level > 3, |level| heading_level != level) {
+                continue;
+            }
+            let text = Span {
+                text: line.text,
+                range: line.start..line.start + line.text.len(),
+            }
+            .trim_start_with(|text| text.trim_start_matches('#'))
+            .trim();
+            if let Some(parts) = VersionHeading::parse(&text) {
+                level = Some(heading_level);
+                headings.push((index, parts));
+            }
+        }
+        let level = level.unwrap_or(2);
+
+        let mut unreleased = None;
+        let mut releases = Vec::new();
+        for (index, parts) in headings {
+            let Some(line) = lines.get(index) else {
+                continue;
+            };
+            let body_start = lines
+                .get(index + 1)
+                .map_or(content.len(), |next| next.start)
+                .min(footer_start);
+            let body_end = lines
+                .iter()
+                .enumerate()
+                .skip(index + 1)
+                .find(|(other, _)| heading_at(*other).map_or(false, |(other, _)| other <= level))
+                .map_or(footer_start, |(_, next)| next.start.min(footer_start));
+            let body = span(content, body_start..body_end);
+            let entry = ReleaseEntry {
+                version: parts.version,
+                version_text: parts.version_text,
+                date: parts.date,
+                heading: Span {
+                    text: line.text,
+                    range: line.start..line.start + line.text.len(),
+                },
+                sections: sections(body.text, level),
+                body,
+            };
+            if entry.version.is_some() {
+                releases.push(entry);
+            } else if unreleased.is_none() {
+                unreleased = Some(entry);
+            }
+        }
+
+        Self {
+            level,
+            unreleased,
+            releases,
+            footer_start,
+        }
+    }
+
+    /// The entry for `version`, if there is one.
+    pub(crate) fn release(&self, version: &Version) -> Option<&ReleaseEntry<'content>> {
+        self.releases
+            .iter()
+            .find(|release| release.version.as_ref() == Some(version))
+    }
+}
+
+impl<'content> Span<'content> {
+    /// The part of this span at `range`, relative to the start of the span.
+    fn slice(&self, range: Range<usize>) -> Option<Self> {
+        let text = self.text.get(range.clone())?;
+        let start = self.range.start + range.start;
+        Some(Self {
+            text,
+            range: start..start + text.len(),
+        })
+    }
+
+    /// The rest of this span after `index`, relative to the start of the span.
+    fn after(&self, index: usize) -> Self {
+        self.slice(index..self.text.len()).unwrap_or(Self {
+            text: "",
+            range: self.range.end..self.range.end,
+        })
+    }
+
+    /// This span without the start that `trim` removes from its text.
+    fn trim_start_with(&self, trim: impl FnOnce(&'content str) -> &'content str) -> Self {
+        let rest = trim(self.text);
+        self.after(self.text.len().saturating_sub(rest.len()))
+    }
+
+    /// This span without the end that `trim` removes from its text.
+    fn trim_end_with(&self, trim: impl FnOnce(&'content str) -> &'content str) -> Self {
+        let kept = trim(self.text);
+        self.slice(0..kept.len()).unwrap_or_else(|| self.clone())
+    }
+
+    fn trim(&self) -> Self {
+        self.trim_start_with(str::trim_start)
+            .trim_end_with(str::trim_end)
+    }
+}
+
+/// The parts of a heading for a version, like `[1.2.3] - 2023-01-01`.
+struct VersionHeading<'content> {
+    version: Option<Version>,
+    version_text: Span<'content>,
+    date: Option<Span<'content>>,
+}
+
+impl<'content> VersionHeading<'content> {
+    /// Parse the text of a heading, without the `#`s.
+    fn parse(text: &Span<'content>) -> Option<Self> {
+        let (version_text, rest) = if text.text.starts_with('[') {
+            let close = text.text.find(']')?;
+            let version_text = text.slice(1..close)?.trim();
+            let rest = text.after(close + 1);
+            // Skip the URL of an inline link, like `[1.2.3](https://...)`
+            let rest = if rest.text.starts_with('(') {
+                rest.after(rest.text.find(')').map_or(rest.text.len(), |end| end + 1))
+            } else {
+                rest
+            };
+            (version_text, rest)
+        } else {
+            match text.text.find(char::is_whitespace) {
+                Some(end) => (text.slice(0..end)?, text.after(end)),
+                None => (text.clone(), text.after(text.text.len())),
+            }
+        };
+        let date = rest
+            .trim()
+            .trim_start_with(|date| date.trim_start_matches(['-', '–', '—']))
+            .trim()
+            .trim_start_with(|date| date.trim_start_matches('('))
+            .trim_end_with(|date| date.trim_end_matches(')'))
+            .trim();
+        let date = Some(date).filter(|date| !date.text.is_empty());
+
+        if version_text.text.eq_ignore_ascii_case("unreleased") {
+            return Some(Self {
+                version: None,
+                version_text,
+                date,
+            });
+        }
+        let version_text =
+            version_text.trim_start_with(|version| version.strip_prefix('v').unwrap_or(version));
+        Version::from_str(version_text.text)
+            .ok()
+            .map(|version| Self {
+                version: Some(version),
+                version_text,
+                date,
+            })
+    }
+}
+
+/// The level and text of a Markdown heading, like `(2, "Title")` for `## Title`.
+pub(crate) fn heading(line: &str) -> Option<(usize, &str)> {
+    let text = line.trim_start_matches('#');
+    let level = line.len() - text.len();
+    if (1..=6).contains(&level) && (text.starts_with(' ') || text.is_empty()) {
+        Some((level, text.trim()))
+    } else {
+        None
+    }
+}
+
+/// Whether `line` is a Markdown link reference definition, like `[1.0.0]: https://...`.
+pub(crate) fn is_link_definition(line: &str) -> bool {
+    line.starts_with('[')
+        && line
+            .split_once("]:")
+            .map_or(false, |(label, _)| !label.contains(']'))
+}
+
+/// Tracks fenced code blocks (with backticks or `~~~`), where lines starting with `#` aren't headings.
+#[derive(Default)]
+struct Fences {
+    /// The character and length of the fence which opened the current code block
+    open: Option<(char, usize)>,
+}
+
+impl Fences {
+    /// Whether `line`, the next line of the document, is a fence or inside a code block.
+    fn is_code(&mut self, line: &str) -> bool {
+        let fence = fence(line);
+        match (self.open, fence) {
+            (Some((marker, length)), Some((other_marker, other_length, info)))
+                if other_marker == marker && other_length >= length && info.trim().is_empty() =>
+            {
+                self.open = None;
+                true
+            }
+            (Some(_), _) => true,
+            (None, Some((marker, length, _))) => {
+                self.open = Some((marker, length));
+                true
+            }
+            (None, None) => false,
+        }
+    }
+}
+
+/// The character, length, and info string of a code fence, like `('~', 3, "markdown")` for
+/// `~~~markdown`.
+fn fence(line: &str) -> Option<(char, usize, &str)> {
+    let trimmed = line.trim_start_matches(' ');
+    if line.len() - trimmed.len() > 3 {
+        return None;
+    }
+    let marker = trimmed
+        .chars()
+        .next()
+        .filter(|char| matches!(char, '`' | '~'))?;
+    let info = trimmed.trim_start_matches(marker);
+    let length = trimmed.len() - info.len();
+    (length >= 3).then_some((marker, length, info))
+}
+
+fn lines(content: &str) -> Vec<Line> {
+    let mut start = 0;
+    content
+        .split_inclusive('\n')
+        .map(|line| {
+            let parsed = Line {
+                text: line.trim_end_matches(['\n', '\r']),
+                start,
+            };
+            start += line.len();
+            parsed
+        })
+        .collect()
+}
+
+/// The start of the link reference definitions (and blank lines) at the end of the changelog.
+fn footer_start(content: &str, lines: &[Line]) -> usize {
+    let mut footer_start = content.len();
+    for line in lines.iter().rev() {
+        if is_link_definition(line.text) {
+            footer_start = line.start;
+        } else if !line.text.trim().is_empty() {
+            break;
+        }
+    }
+    footer_start
+}
+
+fn span(content: &str, range: Range<usize>) -> Span {
+    Span {
+        text: content.get(range.clone()).unwrap_or_default(),
+        range,
+    }
+}
+
+/// The changes in the body of an entry, grouped by their headings one level below the version.
+fn sections(body: &str, level: usize) -> Vec<Section> {
+    let mut sections: Vec<Section> = Vec::new();
+    let mut title = None;
+    let mut entry: Vec<&str> = Vec::new();
+    let mut fences = Fences::default();
+    for line in body.lines() {
+        if fences.is_code(line) {
+            entry.push(line);
+            continue;
+        }
+        if is_link_definition(line) {
+            continue;
+        }
+        let item = line
+            .strip_prefix("- ")
+            .or_else(|| line.strip_prefix("* "))
+            .map(str::trim);
+        let (section_title, is_entry_heading) = match heading(line) {
+            Some((heading_level, text)) if heading_level == level + 1 => (Some(text), false),
+            Some((heading_level, _)) => (None, heading_level > level + 1),
+            None => (None, false),
+        };
+        if section_title.is_some() || item.is_some() || is_entry_heading {
+            add_entry(&mut sections, title.as_deref(), &entry);
+            entry.clear();
+        }
+        if let Some(section_title) = section_title {
+            title = Some(section_title.to_string());
+        } else if let Some(item) = item {
+            entry.push(item);
+        } else if !entry.is_empty() || !line.trim().is_empty() {
+            entry.push(if is_entry_heading { line } else { line.trim() });
+        }
+    }
+    add_entry(&mut sections, title.as_deref(), &entry);
+    sections
+}
+
+fn add_entry(sections: &mut Vec<Section>, title: Option<&str>, entry: &[&str]) {
+    let entry = entry.join("\n").trim().to_string();
+    if entry.is_empty() {
+        return;
+    }
+    match sections
+        .iter_mut()
+        .find(|section| section.title.as_deref() == title)
+    {
+        Some(section) => section.entries.push(entry),
+        None => sections.push(Section {
+            title: title.map(String::from),
+            entries: vec![entry],
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn heading_styles() {
+        const CONTENT: &str = "\
+# Changelog
+
+Some details
+
+## [Unreleased]
+
+- Something new
+
+## 1.2.0 (2023-03-01)
+
+### Features
+
+#### A feature
+
+With details
+
+## [1.1.0] - 2023-02-01
+
+### Fixed
+
+- A fix
+  over two lines
+
+## [1.0.1](https://github.com/knope-dev/knope/compare/v1.0.0...v1.0.1) (2023-01-15)
+
+## v1.0.0
+
+Initial release
+
+[unreleased]: https://github.com/knope-dev/knope/compare/v1.2.0...HEAD
+[1.1.0]: https://github.com/knope-dev/knope/compare/v1.0.1...v1.1.0
+";
+        let document = Document::parse(CONTENT);
+        assert_eq!(document.level, 2);
+        assert_eq!(
+            document.footer_start,
+            CONTENT.find("[unreleased]:").unwrap()
+        );
+
+        let unreleased = document.unreleased.unwrap();
+        assert_eq!(unreleased.version, None);
+        assert_eq!(unreleased.heading.text, "## [Unreleased]");
+        assert_eq!(unreleased.body.text, "\n- Something new\n\n");
+
+        let versions = document
+            .releases
+            .iter()
+            .map(|release| {
+                (
+                    release.version.clone().unwrap().to_string(),
+                    release.date.as_ref().map(|date| date.text),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            versions,
+            vec![
+                (String::from("1.2.0"), Some("2023-03-01")),
+                (String::from("1.1.0"), Some("2023-02-01")),
+                (String::from("1.0.1"), Some("2023-01-15")),
+                (String::from("1.0.0"), None),
+            ]
+        );
+
+        let sections = document
+            .releases
+            .iter()
+            .map(|release| release.sections.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            sections,
+            vec![
+                vec![Section {
+                    title: Some(String::from("Features")),
+                    entries: vec![String::from("#### A feature\n\nWith details")],
+                }],
+                vec![Section {
+                    title: Some(String::from("Fixed")),
+                    entries: vec![String::from("A fix\nover two lines")],
+                }],
+                vec![],
+                vec![Section {
+                    title: None,
+                    entries: vec![String::from("Initial release")],
+                }],
+            ]
+        );
+        let last = document.releases.last().unwrap();
+        assert_eq!(last.body.text, "\nInitial release\n\n");
+        assert_eq!(
+            &CONTENT[last.version_text.range.clone()],
+            last.version_text.text
+        );
+        assert_eq!(last.version_text.text, "1.0.0");
+        for release in &document.releases {
+            assert_eq!(
+                &CONTENT[release.version_text.range.clone()],
+                release.version_text.text
+            );
+            if let Some(date) = &release.date {
+                assert_eq!(&CONTENT[date.range.clone()], date.text);
+            }
+        }
+    }
+
+    #[test]
+    fn other_heading_levels() {
+        const CONTENT: &str = "\
+# 1.1.0
+
+## Features
+
+### A feature
+
+# 1.0.0
+
+## Notes about 2.0.0
+";
+        let document = Document::parse(CONTENT);
+        assert_eq!(document.level, 1);
+        assert_eq!(document.releases.len(), 2);
+        assert_eq!(
+            document
+                .release(&Version::new(1, 1, 0, None))
+                .unwrap()
+                .sections,
+            vec![Section {
+                title: Some(String::from("Features")),
+                entries: vec![String::from("### A feature")],
+            }]
+        );
+        assert_eq!(
+            document
+                .release(&Version::new(1, 0, 0, None))
+                .unwrap()
+                .body
+                .text,
+            "\n## Notes about 2.0.0\n"
+        );
+    }
+
+    #[test]
+    fn code_blocks() {
+        const CONTENT: &str = "\
+## 1.1.0 (2023-02-01)
+
+### Features
+
+#### Show examples
+
+Before:
+
+```markdown
+## 1.0.0
+
+### Features
+```
+
+~~~
+# 0.1.0
+~~~
+
+## 1.0.0 (2023-01-01)
+";
+        let document = Document::parse(CONTENT);
+        let versions = document
+            .releases
+            .iter()
+            .map(|release| release.version.clone().unwrap().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(versions, vec!["1.1.0", "1.0.0"]);
+        assert_eq!(
+            document.releases[0].sections,
+            vec![Section {
+                title: Some(String::from("Features")),
+                entries: vec![String::from(
+                    "#### Show examples\n\nBefore:\n\n```markdown\n## 1.0.0\n\n### Features\n```\n\n~~~\n# 0.1.0\n~~~"
+                )],
+            }]
+        );
+    }
+
+    #[test]
+    fn no_versions() {
+        let document = Document::parse("# Changelog\n\n## Not a version\n");
+        assert_eq!(document.level, 2);
+        assert!(document.unreleased.is_none());
+        assert!(document.releases.is_empty());
+    }
+}
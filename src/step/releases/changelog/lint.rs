@@ -0,0 +1,363 @@
+//! Checks of a package's changelog for `knope --validate`.
+
+use std::ops::Range;
+
+use miette::{Diagnostic, NamedSource, SourceSpan};
+use thiserror::Error;
+use time::{macros::format_description, Date};
+
+use super::{
+    document::{Document, ReleaseEntry},
+    Changelog,
+};
+use crate::{
+    integrations::{git, git::get_tagged_versions},
+    step::releases::{semver::Version, DateFormat, Package},
+    workflow::Verbose,
+};
+
+/// A problem with a changelog, pointing at where it is.
+#[derive(Debug, Diagnostic, Error)]
+pub(crate) enum Problem {
+    #[error("Version {version} was released with the tag {tag}, but has no changelog entry")]
+    #[diagnostic(
+        code(changelog::missing_entry),
+        help("Add an entry for {version}, like `## {version}`, or remove the tag."),
+        url("https://knope-dev.github.io/knope/config/step/PrepareRelease.html#changelog-format")
+    )]
+    MissingEntry {
+        version: Version,
+        tag: String,
+        #[source_code]
+        source_code: NamedSource,
+        #[label("{version} belongs here")]
+        span: SourceSpan,
+    },
+    #[error("Version {version} appears more than once in the changelog")]
+    #[diagnostic(
+        code(changelog::duplicate_version),
+        help("Combine the entries for {version} into one."),
+        url("https://knope-dev.github.io/knope/config/step/PrepareRelease.html#changelog-format")
+    )]
+    DuplicateVersion {
+        version: Version,
+        #[source_code]
+        source_code: NamedSource,
+        #[label("first entry")]
+        first: SourceSpan,
+        #[label("duplicate")]
+        duplicate: SourceSpan,
+    },
+    #[error("Version {version} is listed after the older version {previous}")]
+    #[diagnostic(
+        code(changelog::out_of_order),
+        help("Versions should go from newest to oldest."),
+        url("https://knope-dev.github.io/knope/config/step/PrepareRelease.html#changelog-format")
+    )]
+    OutOfOrder {
+        version: Version,
+        previous: Version,
+        #[source_code]
+        source_code: NamedSource,
+        #[label("older version")]
+        previous_span: SourceSpan,
+        #[label("newer version")]
+        span: SourceSpan,
+    },
+    #[error("Invalid date {date}: {reason}")]
+    #[diagnostic(
+        code(changelog::invalid_date),
+        help("Dates should match the `date_format` of the package, which is {format}."),
+        url("https://knope-dev.github.io/knope/config/packages.html#date_format")
+    )]
+    InvalidDate {
+        date: String,
+        reason: String,
+        format: String,
+        #[source_code]
+        source_code: NamedSource,
+        #[label("invalid date")]
+        span: SourceSpan,
+    },
+    #[error("The heading for version {version} has a different style than the others")]
+    #[diagnostic(
+        code(changelog::inconsistent_heading),
+        help("Write every version heading like the first one, starting with `{expected_form}`."),
+        url("https://knope-dev.github.io/knope/config/step/PrepareRelease.html#changelog-format")
+    )]
+    InconsistentHeading {
+        version: Version,
+        expected_form: String,
+        #[source_code]
+        source_code: NamedSource,
+        #[label("expected a heading like this")]
+        expected: SourceSpan,
+        #[label("but found this")]
+        span: SourceSpan,
+    },
+}
+
+impl Package {
+    /// Check the changelog of this package, if it has one, against the tags of its releases.
+    pub(crate) fn lint_changelog(&self, verbose: Verbose) -> Result<Vec<Problem>, git::Error> {
+        let Some(changelog) = self
+            .changelog
+            .as_ref()
+            .filter(|changelog| !changelog.content.is_empty())
+        else {
+            return Ok(Vec::new());
+        };
+        let tags = get_tagged_versions(&self.tag_template(), self.uses_pep440(), verbose)?;
+        Ok(lint(changelog, &tags, self.date_format.as_ref()))
+    }
+}
+
+/// Every problem with `changelog`, given the `(tag, version)` of every release.
+pub(crate) fn lint(
+    changelog: &Changelog,
+    tags: &[(String, Version)],
+    date_format: Option<&DateFormat>,
+) -> Vec<Problem> {
+    let document = Document::parse(&changelog.content);
+    let source_code =
+        || NamedSource::new(changelog.path.to_string_lossy(), changelog.content.clone());
+    let mut problems = Vec::new();
+
+    for (tag, version) in tags {
+        if document.release(version).is_some() {
+            continue;
+        }
+        let position = document
+            .releases
+            .iter()
+            .find(|release| release.version.as_ref() < Some(version))
+            .map_or(document.footer_start, |release| release.heading.range.start);
+        problems.push(Problem::MissingEntry {
+            version: version.clone(),
+            tag: tag.clone(),
+            source_code: source_code(),
+            span: (position, 0).into(),
+        });
+    }
+
+    for (index, release) in document.releases.iter().enumerate() {
+        let Some(version) = &release.version else {
+            continue;
+        };
+        let earlier = document.releases.get(..index).unwrap_or_default();
+        if let Some(first) = earlier
+            .iter()
+            .find(|other| other.version.as_ref() == Some(version))
+        {
+            problems.push(Problem::DuplicateVersion {
+                version: version.clone(),
+                source_code: source_code(),
+                first: span(&first.heading.range),
+                duplicate: span(&release.heading.range),
+            });
+            continue;
+        }
+        if let Some(previous) = earlier.last() {
+            if let Some(previous_version) = previous
+                .version
+                .as_ref()
+                .filter(|previous_version| *previous_version < version)
+            {
+                problems.push(Problem::OutOfOrder {
+                    version: version.clone(),
+                    previous: previous_version.clone(),
+                    source_code: source_code(),
+                    previous_span: span(&previous.heading.range),
+                    span: span(&release.heading.range),
+                });
+            }
+        }
+    }
+
+    for date in document
+        .releases
+        .iter()
+        .filter_map(|release| release.date.as_ref())
+    {
+        let parsed = match date_format {
+            Some(date_format) => Date::parse(date.text, &date_format.items),
+            None => Date::parse(date.text, format_description!("[year]-[month]-[day]")),
+        };
+        if let Err(err) = parsed {
+            problems.push(Problem::InvalidDate {
+                date: date.text.to_string(),
+                reason: err.to_string(),
+                format: date_format
+                    .map_or_else(|| String::from("[year]-[month]-[day]"), ToString::to_string),
+                source_code: source_code(),
+                span: span(&date.range),
+            });
+        }
+    }
+
+    if let Some((expected, rest)) = document.releases.split_first() {
+        let expected_style = HeadingStyle::of(expected, &changelog.content);
+        for release in rest {
+            if HeadingStyle::of(release, &changelog.content) != expected_style {
+                if let Some(version) = &release.version {
+                    problems.push(Problem::InconsistentHeading {
+                        version: version.clone(),
+                        expected_form: expected_style.form(),
+                        source_code: source_code(),
+                        expected: span(&expected.heading.range),
+                        span: span(&release.heading.range),
+                    });
+                }
+            }
+        }
+    }
+
+    problems
+}
+
+fn span(range: &Range<usize>) -> SourceSpan {
+    (range.start, range.len()).into()
+}
+
+/// How a version heading starts, like `## [` in `## [1.2.3] - 2023-01-01`.
+///
+/// The text around the date isn't part of the style, so `## 1.2.3 (2023-01-01)` and
+/// `## 1.2.3 - 2023-01-01` are both accepted.
+#[derive(Debug, Eq, PartialEq)]
+struct HeadingStyle<'content> {
+    before_version: &'content str,
+}
+
+impl<'content> HeadingStyle<'content> {
+    fn of(release: &ReleaseEntry, content: &'content str) -> Self {
+        let before_version = content
+            .get(release.heading.range.start..release.version_text.range.start)
+            .unwrap_or_default();
+        Self { before_version }
+    }
+
+    /// The start of headings in this style, like `## [<version>]`.
+    fn form(&self) -> String {
+        let closing = if self.before_version.ends_with('[') {
+            "]"
+        } else {
+            ""
+        };
+        format!("{}<version>{closing}", self.before_version)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::PathBuf, str::FromStr};
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn changelog(content: &str) -> Changelog {
+        Changelog {
+            path: PathBuf::from("CHANGELOG.md"),
+            content: content.to_string(),
+        }
+    }
+
+    fn version(version: &str) -> Version {
+        Version::from_str(version).unwrap()
+    }
+
+    fn codes(problems: &[Problem]) -> Vec<String> {
+        problems
+            .iter()
+            .map(|problem| problem.code().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn valid() {
+        let changelog = changelog(
+            "# Changelog\n\n## [Unreleased]\n\n## [1.1.0] - 2023-02-01\n\n- A fix\n\n## [1.0.0](https://example.com) - 2023-01-01\n\n## [0.1.0]\n",
+        );
+        let tags = [
+            (String::from("v1.1.0"), version("1.1.0")),
+            (String::from("v1.0.0"), version("1.0.0")),
+        ];
+        assert!(lint(&changelog, &tags, None).is_empty());
+    }
+
+    #[test]
+    fn problems() {
+        let changelog = changelog(
+            "# Changelog\n\n## 1.0.0 (2023-01-01)\n\n## 1.1.0 (2023-02-30)\n\n## [0.1.0] - 2022-01-01\n\n## 0.1.0\n",
+        );
+        let tags = [
+            (String::from("v1.0.0"), version("1.0.0")),
+            (String::from("v0.2.0"), version("0.2.0")),
+        ];
+        let problems = lint(&changelog, &tags, None);
+        assert_eq!(
+            codes(&problems),
+            vec![
+                "changelog::missing_entry",
+                "changelog::out_of_order",
+                "changelog::duplicate_version",
+                "changelog::invalid_date",
+                "changelog::inconsistent_heading",
+            ]
+        );
+        let Problem::MissingEntry { span, .. } = &problems[0] else {
+            panic!("Expected a missing entry, got {:?}", problems[0]);
+        };
+        assert_eq!(span.offset(), changelog.content.find("## [0.1.0]").unwrap());
+    }
+
+    #[test]
+    fn heading_styles() {
+        let changelog = changelog(
+            "## 1.1.0 (2023-02-01)\n\n## 1.0.0 - 2023-01-01\n\n## [0.2.0] - 2022-01-01\n\n## v0.1.0\n",
+        );
+        let problems = lint(&changelog, &[], None);
+        let forms = problems
+            .iter()
+            .map(|problem| match problem {
+                Problem::InconsistentHeading {
+                    version,
+                    expected_form,
+                    ..
+                } => (version.to_string(), expected_form.as_str()),
+                _ => panic!("Expected an inconsistent heading, got {problem:?}"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            forms,
+            vec![
+                (String::from("0.2.0"), "## <version>"),
+                (String::from("0.1.0"), "## <version>"),
+            ]
+        );
+    }
+
+    #[test]
+    fn code_blocks() {
+        let changelog = changelog(
+            "## 1.1.0 (2023-02-01)\n\nThe heading used to be:\n\n```markdown\n## 1.0.0\n```\n\nAnd is now:\n\n```markdown\n## 1.0.0 (2023-01-01)\n```\n\n## 1.0.0 (2023-01-01)\n\n## 0.8.0 (2022-01-01)\n",
+        );
+        let tags = [
+            (String::from("v1.1.0"), version("1.1.0")),
+            (String::from("v1.0.0"), version("1.0.0")),
+            (String::from("v0.8.0"), version("0.8.0")),
+        ];
+        assert!(lint(&changelog, &tags, None).is_empty());
+    }
+
+    #[test]
+    fn custom_date_format() {
+        let changelog = changelog("## 1.0.0 (January 01, 2023)\n");
+        let date_format = DateFormat::from_str("[month repr:long] [day], [year]").unwrap();
+        assert!(lint(&changelog, &[], Some(&date_format)).is_empty());
+        assert_eq!(
+            codes(&lint(&changelog, &[], None)),
+            vec!["changelog::invalid_date"]
+        );
+    }
+}
@@ -0,0 +1,910 @@
+use std::{path::PathBuf, str::FromStr};
+
+use document::{heading, is_link_definition, Document};
+use indexmap::IndexMap;
+use itertools::Itertools;
+use miette::Diagnostic;
+use serde_json::json;
+use thiserror::Error;
+
+use super::{
+    semver::Version, template, template::Template, Change, ChangeType, Package, Release, TimeError,
+};
+use crate::{
+    config::{ChangeLogSectionName, GitHub},
+    dry_run::DryRun,
+    fs,
+};
+
+pub(crate) mod document;
+pub(crate) mod lint;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct Changelog {
+    pub(crate) path: PathBuf,
+    pub(crate) content: String,
+}
+
+impl TryFrom<PathBuf> for Changelog {
+    type Error = Error;
+
+    fn try_from(path: PathBuf) -> Result<Self, Self::Error> {
+        let content = if path.exists() {
+            fs::read_to_string(&path)?
+        } else {
+            String::new()
+        };
+        Ok(Self { path, content })
+    }
+}
+
+impl Changelog {
+    /// The entry for `version`, without its heading.
+    pub(crate) fn get_section(&self, version: &Version) -> Option<String> {
+        Document::parse(&self.content)
+            .release(version)
+            .map(|release| release.body.text.trim().to_string())
+            .filter(|section| !section.is_empty())
+    }
+
+    /// The hand-written changes in a [Keep a Changelog](https://keepachangelog.com) style
+    /// `## [Unreleased]` section, grouped by their `###` headings.
+    ///
+    /// Each list item (or `####` heading, like knope writes) is one change. Changes which aren't
+    /// under a `###` heading are in [`NOTES_SECTION`].
+    pub(crate) fn unreleased(&self) -> Vec<(String, Vec<Entry>)> {
+        Document::parse(&self.content)
+            .unreleased
+            .map(|unreleased| {
+                unreleased
+                    .sections
+                    .into_iter()
+                    .map(|section| {
+                        (
+                            section.title.unwrap_or_else(|| NOTES_SECTION.to_string()),
+                            section
+                                .entries
+                                .iter()
+                                .map(|entry| Entry::from_markdown(entry))
+                                .collect(),
+                        )
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// The section for notes which aren't changes of any type, like those directly under
+/// `## [Unreleased]` without a `###` heading.
+pub(crate) const NOTES_SECTION: &str = "Notes";
+
+/// One change in a section of a new changelog entry.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct Entry {
+    /// The first line, which is a `####` heading in the built-in format
+    pub(crate) summary: String,
+    /// Any details after the summary
+    pub(crate) body: String,
+}
+
+impl Entry {
+    /// A change whose first line is the summary, like a commit message with its body.
+    pub(crate) fn from_text(text: &str) -> Self {
+        let text = text.trim();
+        let (summary, body) = text.split_once('\n').unwrap_or((text, ""));
+        Self {
+            summary: summary.trim().to_string(),
+            body: body.trim().to_string(),
+        }
+    }
+
+    /// A change written in Markdown, like a changeset, where the summary may already be a heading.
+    pub(crate) fn from_markdown(markdown: &str) -> Self {
+        let mut entry = Self::from_text(markdown);
+        if let Some((_, summary)) = heading(&entry.summary) {
+            entry.summary = summary.to_string();
+        }
+        entry
+    }
+
+    /// The change in the built-in format.
+    fn render(&self) -> String {
+        if self.body.is_empty() {
+            format!("#### {}", self.summary)
+        } else {
+            format!("#### {}\n\n{}", self.summary, self.body)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_get_section {
+    use std::path::PathBuf;
+
+    use pretty_assertions::assert_eq;
+
+    use crate::step::releases::{changelog::Changelog, semver::Version};
+
+    const CONTENT: &str = r#"
+# Changelog
+
+Hey ya'll this is a changelog
+
+## 0.1.2 2023-05-02
+
+### Features
+#### Blah
+
+## 0.1.1 - 2023-03-02
+
+### Fixes
+
+#### it's fixex!
+
+## 0.0.1
+Initial release
+"#;
+
+    #[test]
+    fn first_section() {
+        let changelog = Changelog {
+            path: PathBuf::default(),
+            content: CONTENT.to_string(),
+        };
+
+        let section = changelog.get_section(&Version::new(0, 1, 2, None)).unwrap();
+        let expected = "### Features\n#### Blah";
+        assert_eq!(section, expected);
+    }
+
+    #[test]
+    fn middle_section() {
+        let changelog = Changelog {
+            path: PathBuf::default(),
+            content: CONTENT.to_string(),
+        };
+
+        let section = changelog.get_section(&Version::new(0, 1, 1, None)).unwrap();
+        let expected = "### Fixes\n\n#### it's fixex!";
+        assert_eq!(section, expected);
+    }
+
+    #[test]
+    fn no_section() {
+        let changelog = Changelog {
+            path: PathBuf::default(),
+            content: CONTENT.to_string(),
+        };
+
+        let section = changelog.get_section(&Version::new(0, 1, 0, None));
+        assert!(section.is_none());
+    }
+
+    #[test]
+    fn keep_a_changelog_sections() {
+        let changelog = Changelog {
+            path: PathBuf::default(),
+            content: String::from(
+                "# Changelog\n\n## [1.1.0] - 2023-02-01\n\n### Fixed\n\n- A fix\n\n## [1.0.0] - 2023-01-01\n\n- Initial release\n\n[1.1.0]: https://example.com\n",
+            ),
+        };
+
+        let section = changelog.get_section(&Version::new(1, 1, 0, None)).unwrap();
+        assert_eq!(section, "### Fixed\n\n- A fix");
+        let section = changelog.get_section(&Version::new(1, 0, 0, None)).unwrap();
+        assert_eq!(section, "- Initial release");
+    }
+
+    #[test]
+    fn linked_section() {
+        let changelog = Changelog {
+            path: PathBuf::default(),
+            content: String::from("## [1.0.0] (2023-01-01)\n\n### Features\n\n## 0.1.0\n"),
+        };
+
+        let section = changelog.get_section(&Version::new(1, 0, 0, None)).unwrap();
+        assert_eq!(section, "### Features");
+    }
+}
+
+#[derive(Debug, Diagnostic, Error)]
+pub(crate) enum Error {
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Fs(#[from] fs::Error),
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    TimeError(#[from] TimeError),
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Template(#[from] Box<template::Error>),
+}
+
+impl Package {
+    /// Adds content from `release` to `Self::changelog` if it exists.
+    ///
+    /// `previous_version` is only used by changelog templates and compare links, `github` only
+    /// by compare links.
+    pub(crate) fn write_changelog(
+        &mut self,
+        version: Version,
+        previous_version: Option<Version>,
+        github: Option<&GitHub>,
+        dry_run: DryRun,
+    ) -> Result<Release, Error> {
+        let mut fixes = Vec::new();
+        let mut features = Vec::new();
+        let mut breaking_changes = Vec::new();
+        let mut extra_sections: IndexMap<ChangeLogSectionName, Vec<Entry>> = IndexMap::new();
+
+        for change in &self.pending_changes {
+            if let Change::ReleasedWithGroup(_) = change {
+                extra_sections
+                    .entry(ChangeLogSectionName::from(NOTES_SECTION))
+                    .or_default()
+                    .push(change.entry());
+                continue;
+            }
+            match change.change_type() {
+                ChangeType::Fix => fixes.push(change.entry()),
+                ChangeType::Feature => features.push(change.entry()),
+                ChangeType::Breaking => breaking_changes.push(change.entry()),
+                ChangeType::Custom(source) => match self.changelog_section(&source) {
+                    None => {}
+                    // Custom commit types can add to the default sections
+                    Some(section) if section.as_ref() == FIXES_SECTION => {
+                        fixes.push(change.entry());
+                    }
+                    Some(section) if section.as_ref() == FEATURES_SECTION => {
+                        features.push(change.entry());
+                    }
+                    Some(section) if section.as_ref() == BREAKING_CHANGES_SECTION => {
+                        breaking_changes.push(change.entry());
+                    }
+                    Some(section) => extra_sections
+                        .entry(section)
+                        .or_default()
+                        .push(change.entry()),
+                },
+            }
+        }
+
+        let mut sections = sections(fixes, features, breaking_changes, extra_sections);
+        if let Some(changelog) = &self.changelog {
+            merge_sections(&mut sections, changelog.unreleased());
+        }
+        let tag_template = self.tag_template();
+        let tag = tag_template.tag_name(&version);
+        let repo = github
+            .filter(|_| self.compare_links)
+            .map(|GitHub { owner, repo }| format!("https://github.com/{owner}/{repo}"));
+        let link = repo.as_ref().map(|repo| match &previous_version {
+            Some(previous_version) => format!(
+                "{repo}/compare/{}...{tag}",
+                tag_template.tag_name(previous_version)
+            ),
+            None => format!("{repo}/releases/tag/{tag}"),
+        });
+        let mut release = Release::new(None, version)
+            .with_date_format(self.date_format.clone())
+            .with_link(link.clone());
+        if let Some(template) = &self.changelog_template {
+            let entry = self.render_template(template, &release, previous_version, sections)?;
+            release.new_changelog = Some(without_heading(&entry));
+            release.rendered_entry = Some(entry);
+        } else {
+            release.new_changelog = Some(render_sections(sections));
+        }
+        let new_changelog = release.changelog_entry()?;
+
+        if let (Some(changelog), Some(new_changes)) = (self.changelog.as_mut(), new_changelog) {
+            changelog.content = update_unreleased_link(
+                &add_version_to_changelog(&changelog.content, &new_changes),
+                &tag,
+            );
+            if let (Some(repo), Some(link)) = (repo, link) {
+                changelog.content = add_link_definition(
+                    &changelog.content,
+                    &release.new_version.to_string(),
+                    &link,
+                );
+                if Document::parse(&changelog.content).unreleased.is_some() {
+                    changelog.content = add_link_definition(
+                        &changelog.content,
+                        "unreleased",
+                        &format!("{repo}/compare/{tag}...HEAD"),
+                    );
+                }
+            }
+            fs::write(
+                dry_run,
+                &format!("\n{new_changes}"),
+                &changelog.path,
+                &changelog.content,
+            )?;
+        };
+
+        Ok(release)
+    }
+
+    fn render_template(
+        &self,
+        template: &Template,
+        release: &Release,
+        previous_version: Option<Version>,
+        sections: Vec<(String, Vec<Entry>)>,
+    ) -> Result<String, Error> {
+        let sections = sections
+            .into_iter()
+            .map(|(title, entries)| {
+                let entries = entries
+                    .into_iter()
+                    .map(|Entry { summary, body }| json!({"summary": summary, "body": body}))
+                    .collect::<Vec<_>>();
+                json!({"title": title, "entries": entries})
+            })
+            .collect::<Vec<_>>();
+        let context = json!({
+            "package": self.name.as_deref(),
+            "version": release.new_version.to_string(),
+            "previous_version": previous_version.map(|version| version.to_string()),
+            "date": release.date()?,
+            "title": release.title()?,
+            "link": release.link,
+            "sections": sections,
+        });
+        template
+            .render(&context)
+            .map_err(|err| Error::from(Box::new(err)))
+    }
+}
+
+/// The text of a changelog entry after its first line, if that line is a heading.
+fn without_heading(entry: &str) -> String {
+    let entry = entry.trim();
+    match entry.split_once('\n') {
+        Some((heading, rest)) if heading.starts_with('#') => rest.trim().to_string(),
+        None if entry.starts_with('#') => String::new(),
+        _ => entry.to_string(),
+    }
+}
+
+/// Take in some existing markdown in the expected changelog format, find the top entry, and
+/// put the new version above it.
+///
+/// If there is an `## [Unreleased]` section, the new version goes right after it and the section
+/// is emptied, since its changes are now part of the new version. If versions in the changelog
+/// use a different heading level than `##`, the headings of `new_changes` are changed to match.
+pub(crate) fn add_version_to_changelog(existing: &str, new_changes: &str) -> String {
+    let document = Document::parse(existing);
+    let new_changes = with_heading_level(new_changes, document.level);
+    let mut changelog = String::new();
+
+    let rest = if let Some(unreleased) = &document.unreleased {
+        changelog.push_str(
+            existing
+                .get(..unreleased.body.range.start)
+                .unwrap_or_default(),
+        );
+        changelog.push('\n');
+        changelog.push_str(&new_changes);
+        changelog.push('\n');
+        // Only the changes are moved, not any links between them
+        let link_definitions = unreleased
+            .body
+            .text
+            .lines()
+            .filter(|line| is_link_definition(line))
+            .collect_vec();
+        if !link_definitions.is_empty() {
+            push_link_definitions(&mut changelog, &link_definitions);
+            changelog.push('\n');
+        }
+        existing.get(unreleased.body.range.end..)
+    } else {
+        let insert_at = document
+            .releases
+            .first()
+            .map_or(document.footer_start, |release| release.heading.range.start);
+        changelog.push_str(existing.get(..insert_at).unwrap_or_default());
+        changelog.push_str(&new_changes);
+        if insert_at < existing.len() {
+            changelog.push('\n');
+        }
+        existing.get(insert_at..)
+    };
+    changelog.push_str(rest.unwrap_or_default());
+
+    if existing.ends_with('\n') && !changelog.ends_with('\n') {
+        // Preserve white space at end of file
+        changelog.push('\n');
+    }
+
+    changelog
+}
+
+/// Change every heading in `entry` so that its first one is at `level`.
+fn with_heading_level(entry: &str, level: usize) -> String {
+    if level == 2 {
+        return entry.to_string();
+    }
+    entry
+        .split_inclusive('\n')
+        .map(|line| match heading(line) {
+            Some((current, _)) => {
+                let new_level = (current + level).saturating_sub(2).max(1);
+                format!("{}{}", "#".repeat(new_level), line.trim_start_matches('#'))
+            }
+            None => line.to_string(),
+        })
+        .collect()
+}
+
+fn push_link_definitions(changelog: &mut String, link_definitions: &[&str]) {
+    for line in link_definitions {
+        changelog.push_str(line);
+        changelog.push('\n');
+    }
+}
+
+/// Point the `[Unreleased]` compare link, like
+/// `[Unreleased]: https://github.com/knope-dev/knope/compare/v1.0.0...HEAD`, at the new `tag`.
+///
+/// Links which don't compare against `HEAD` are left alone.
+fn update_unreleased_link(changelog: &str, tag: &str) -> String {
+    let mut updated = changelog
+        .lines()
+        .map(|line| {
+            let Some((label, url)) = line.split_once("]:") else {
+                return line.to_string();
+            };
+            let is_unreleased = label
+                .strip_prefix('[')
+                .map_or(false, |label| label.eq_ignore_ascii_case("unreleased"));
+            let base = url
+                .trim()
+                .strip_suffix("...HEAD")
+                .and_then(|url| url.rsplit_once("/compare/"))
+                .map(|(base, _)| base);
+            match base {
+                Some(base) if is_unreleased => {
+                    format!("{label}]: {base}/compare/{tag}...HEAD")
+                }
+                _ => line.to_string(),
+            }
+        })
+        .join("\n");
+    if changelog.ends_with('\n') {
+        updated.push('\n');
+    }
+    updated
+}
+
+/// Add `[label]: url` to the link reference definitions at the end of `changelog`, replacing any
+/// existing definition of `label`.
+///
+/// New definitions go after the one for `[Unreleased]` and before those of older versions, or at
+/// the end of the file if there are neither—other definitions are left where they are.
+fn add_link_definition(changelog: &str, label: &str, url: &str) -> String {
+    let definition = format!("[{label}]: {url}");
+    let mut lines = changelog.lines().collect_vec();
+    let label_of = |line: &str| {
+        if is_link_definition(line) {
+            line.strip_prefix('[')
+                .and_then(|line| line.split_once("]:"))
+                .map(|(label, _)| label.to_string())
+        } else {
+            None
+        }
+    };
+    let existing = lines.iter().position(|line| {
+        label_of(line).map_or(false, |existing| existing.eq_ignore_ascii_case(label))
+    });
+    let after_unreleased = lines
+        .iter()
+        .position(|line| {
+            label_of(line).map_or(false, |existing| {
+                existing.eq_ignore_ascii_case("unreleased")
+            })
+        })
+        .map(|index| index + 1);
+    let before_versions = lines.iter().position(|line| {
+        label_of(line).map_or(false, |existing| Version::from_str(&existing).is_ok())
+    });
+    if let Some(index) = existing {
+        if let Some(line) = lines.get_mut(index) {
+            *line = &definition;
+        }
+    } else if let Some(index) = after_unreleased.or(before_versions) {
+        lines.insert(index, &definition);
+    } else {
+        while lines.last().map_or(false, |line| line.trim().is_empty()) {
+            lines.pop();
+        }
+        if lines.last().map_or(false, |line| !is_link_definition(line)) {
+            lines.push("");
+        }
+        lines.push(&definition);
+    }
+    let mut updated = lines.join("\n");
+    updated.push('\n');
+    updated
+}
+
+/// Add the hand-written changes from an "Unreleased" section to `sections`, after any changes in
+/// a section with the same title.
+fn merge_sections(sections: &mut Vec<(String, Vec<Entry>)>, unreleased: Vec<(String, Vec<Entry>)>) {
+    for (title, entries) in unreleased {
+        match sections
+            .iter_mut()
+            .find(|(existing, _)| existing.eq_ignore_ascii_case(&title))
+        {
+            Some((_, existing)) => existing.extend(entries),
+            None => sections.push((title, entries)),
+        }
+    }
+}
+
+pub(crate) const BREAKING_CHANGES_SECTION: &str = "Breaking Changes";
+pub(crate) const FEATURES_SECTION: &str = "Features";
+pub(crate) const FIXES_SECTION: &str = "Fixes";
+
+#[cfg(test)]
+fn new_changelog(
+    fixes: Vec<String>,
+    features: Vec<String>,
+    breaking_changes: Vec<String>,
+    extra_sections: IndexMap<ChangeLogSectionName, Vec<String>>,
+) -> String {
+    let entries = |summaries: Vec<String>| {
+        summaries
+            .iter()
+            .map(|summary| Entry::from_text(summary))
+            .collect()
+    };
+    render_sections(sections(
+        entries(fixes),
+        entries(features),
+        entries(breaking_changes),
+        extra_sections
+            .into_iter()
+            .map(|(title, summaries)| (title, entries(summaries)))
+            .collect(),
+    ))
+}
+
+/// Every section with at least one entry, in the order they appear in the changelog.
+fn sections(
+    fixes: Vec<Entry>,
+    features: Vec<Entry>,
+    breaking_changes: Vec<Entry>,
+    extra_sections: IndexMap<ChangeLogSectionName, Vec<Entry>>,
+) -> Vec<(String, Vec<Entry>)> {
+    [
+        (BREAKING_CHANGES_SECTION.to_string(), breaking_changes),
+        (FEATURES_SECTION.to_string(), features),
+        (FIXES_SECTION.to_string(), fixes),
+    ]
+    .into_iter()
+    .filter(|(_, entries)| !entries.is_empty())
+    .chain(
+        extra_sections
+            .into_iter()
+            .map(|(title, entries)| (title.to_string(), entries)),
+    )
+    .collect()
+}
+
+/// The built-in format for a changelog entry: a `###` heading for each section, and a `####`
+/// heading for each change.
+fn render_sections(sections: Vec<(String, Vec<Entry>)>) -> String {
+    sections
+        .into_iter()
+        .flat_map(|(title, entries)| create_section(&title, &entries))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn create_section(title: &str, entries: &[Entry]) -> Vec<String> {
+    let mut blocks = Vec::with_capacity(entries.len() + 2);
+    blocks.push(format!("### {title}"));
+    blocks.extend(entries.iter().map(|entry| format!("\n{}", entry.render())));
+    blocks.push(String::new());
+    blocks
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn changelog_add_version() {
+        const MARKDOWN: &str = r##"# Changelog
+
+Some details about the keepachangelog format
+
+Sometimes a second paragraph
+
+## 0.1.0 - 2020-12-25
+### Features
+- Initial version
+
+[link]: some footer details
+"##;
+        const EXPECTED: &str = r##"# Changelog
+
+Some details about the keepachangelog format
+
+Sometimes a second paragraph
+
+### Breaking Changes
+
+#### Breaking change
+
+### Features
+
+#### New Feature
+
+#### Another feature
+
+### Fixes
+
+#### Fixed something
+
+### Notes
+
+#### Something
+
+### More stuff
+
+#### stuff
+
+## 0.1.0 - 2020-12-25
+### Features
+- Initial version
+
+[link]: some footer details
+"##;
+
+        let mut extra_sections = IndexMap::new();
+        extra_sections.insert(
+            ChangeLogSectionName::from("Notes"),
+            vec![String::from("Something")],
+        );
+        extra_sections.insert(
+            ChangeLogSectionName::from("More stuff"),
+            vec![String::from("stuff")],
+        );
+        let new_changes = new_changelog(
+            vec!["Fixed something".to_string()],
+            vec![String::from("New Feature"), String::from("Another feature")],
+            vec![String::from("Breaking change")],
+            extra_sections,
+        );
+        let changelog = add_version_to_changelog(MARKDOWN, &new_changes);
+        assert_eq!(changelog, EXPECTED);
+    }
+
+    #[test]
+    fn changelog_no_existing_version() {
+        const MARKDOWN: &str = r##"# Changelog
+
+Some details about the keepachangelog format
+
+Sometimes a second paragraph
+
+"##;
+        const EXPECTED: &str = r##"# Changelog
+
+Some details about the keepachangelog format
+
+Sometimes a second paragraph
+
+### Breaking Changes
+
+#### Breaking change
+
+### Features
+
+#### New Feature
+
+### Fixes
+
+#### Fixed something
+"##;
+
+        let new_changes = new_changelog(
+            vec!["Fixed something".to_string()],
+            vec![String::from("New Feature")],
+            vec![String::from("Breaking change")],
+            IndexMap::new(),
+        );
+        let changelog = add_version_to_changelog(MARKDOWN, &new_changes);
+        assert_eq!(changelog, EXPECTED);
+    }
+
+    const KEEP_A_CHANGELOG: &str = r##"# Changelog
+
+## [Unreleased]
+
+A note without a section
+
+### Added
+
+- Something new
+  with more details
+- Another thing
+
+### Fixed
+
+#### A fix in knope's format
+
+With a body
+
+## [1.0.0] - 2023-01-01
+
+- Initial release
+
+[unreleased]: https://github.com/knope-dev/knope/compare/v1.0.0...HEAD
+[1.0.0]: https://github.com/knope-dev/knope/releases/tag/v1.0.0
+"##;
+
+    #[test]
+    fn unreleased() {
+        let changelog = Changelog {
+            path: PathBuf::default(),
+            content: KEEP_A_CHANGELOG.to_string(),
+        };
+        assert_eq!(
+            changelog.unreleased(),
+            vec![
+                (
+                    String::from("Notes"),
+                    vec![Entry::from_text("A note without a section")]
+                ),
+                (
+                    String::from("Added"),
+                    vec![
+                        Entry {
+                            summary: String::from("Something new"),
+                            body: String::from("with more details"),
+                        },
+                        Entry::from_text("Another thing")
+                    ]
+                ),
+                (
+                    String::from("Fixed"),
+                    vec![Entry {
+                        summary: String::from("A fix in knope's format"),
+                        body: String::from("With a body"),
+                    }]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn add_version_after_unreleased() {
+        const EXPECTED: &str = r##"# Changelog
+
+## [Unreleased]
+
+## 1.1.0
+
+### Features
+
+#### New Feature
+
+## [1.0.0] - 2023-01-01
+
+- Initial release
+
+[unreleased]: https://github.com/knope-dev/knope/compare/v1.1.0...HEAD
+[1.0.0]: https://github.com/knope-dev/knope/releases/tag/v1.0.0
+"##;
+        let new_changes = format!(
+            "## 1.1.0\n\n{}",
+            new_changelog(
+                Vec::new(),
+                vec![String::from("New Feature")],
+                Vec::new(),
+                IndexMap::new()
+            )
+        );
+        let changelog = update_unreleased_link(
+            &add_version_to_changelog(KEEP_A_CHANGELOG, &new_changes),
+            "v1.1.0",
+        );
+        assert_eq!(changelog, EXPECTED);
+    }
+
+    #[test]
+    fn first_version_after_unreleased() {
+        const MARKDOWN: &str = "# Changelog\n\n## [Unreleased]\n\n- Something\n\n[unreleased]: https://github.com/knope-dev/knope/compare/v0.1.0...HEAD\n";
+        const EXPECTED: &str = "# Changelog\n\n## [Unreleased]\n\n## 1.0.0\n\n[unreleased]: https://github.com/knope-dev/knope/compare/v0.1.0...HEAD\n";
+        let changelog = add_version_to_changelog(MARKDOWN, "## 1.0.0\n");
+        assert_eq!(changelog, EXPECTED);
+    }
+
+    #[test]
+    fn add_link_definitions() {
+        const FOOTER: &str = "## 1.0.0\n\nSome changes\n\n[link]: some footer details\n";
+        const KEEP_A_CHANGELOG_FOOTER: &str =
+            "[unreleased]: https://example.com/unreleased\n[1.0.0]: https://example.com/1.0.0\n";
+        assert_eq!(
+            add_link_definition(FOOTER, "1.1.0", "https://example.com/1.1.0"),
+            "## 1.0.0\n\nSome changes\n\n[link]: some footer details\n[1.1.0]: https://example.com/1.1.0\n"
+        );
+        assert_eq!(
+            add_link_definition("## 1.0.0\n\nSome changes\n", "1.0.0", "https://example.com"),
+            "## 1.0.0\n\nSome changes\n\n[1.0.0]: https://example.com\n"
+        );
+        assert_eq!(
+            add_link_definition(KEEP_A_CHANGELOG_FOOTER, "1.1.0", "https://example.com/1.1.0"),
+            "[unreleased]: https://example.com/unreleased\n[1.1.0]: https://example.com/1.1.0\n[1.0.0]: https://example.com/1.0.0\n"
+        );
+        assert_eq!(
+            add_link_definition(
+                "[1.0.0]: https://example.com/1.0.0\n",
+                "unreleased",
+                "https://example.com/unreleased"
+            ),
+            "[unreleased]: https://example.com/unreleased\n[1.0.0]: https://example.com/1.0.0\n"
+        );
+        assert_eq!(
+            add_link_definition(KEEP_A_CHANGELOG_FOOTER, "1.0.0", "https://example.com/new"),
+            "[unreleased]: https://example.com/unreleased\n[1.0.0]: https://example.com/new\n"
+        );
+    }
+
+    #[test]
+    fn template_entries() {
+        let template = Template::parse(
+            PathBuf::from("changelog.md.tmpl"),
+            String::from(
+                "{% for section in sections %}{% for entry in section.entries %}\
+                 [{{ entry.summary }}|{{ entry.body }}]{% endfor %}{% endfor %}",
+            ),
+        )
+        .unwrap();
+        let sections = vec![(
+            String::from(FEATURES_SECTION),
+            vec![
+                Entry::from_text("#### Not a heading\n\nWith a body"),
+                Entry::from_markdown("#### A changeset\n\nWith details"),
+            ],
+        )];
+        let rendered = Package::default()
+            .render_template(
+                &template,
+                &Release::new(None, Version::new(1, 0, 0, None)),
+                None,
+                sections,
+            )
+            .unwrap();
+        assert_eq!(
+            rendered,
+            "[#### Not a heading|With a body][A changeset|With details]"
+        );
+    }
+
+    #[test]
+    fn add_version_with_other_heading_level() {
+        const MARKDOWN: &str = "# 1.0.0\n\n## Features\n\n### Initial release\n";
+        const EXPECTED: &str =
+            "# 1.1.0\n\n## Fixes\n\n### A fix\n\n# 1.0.0\n\n## Features\n\n### Initial release\n";
+        let new_changes = format!(
+            "## 1.1.0\n\n{}",
+            new_changelog(
+                vec![String::from("A fix")],
+                Vec::new(),
+                Vec::new(),
+                IndexMap::new()
+            )
+        );
+        assert_eq!(add_version_to_changelog(MARKDOWN, &new_changes), EXPECTED);
+    }
+}
@@ -1,14 +1,21 @@
-use std::fmt::Display;
+use std::{borrow::Cow, collections::HashSet, fmt::Display};
 
 use git_conventional::{Commit, Footer, Type};
+use itertools::Itertools;
 use log::debug;
 use miette::Diagnostic;
 
-use super::{package::ChangelogSectionSource, Change, ChangeType, Package};
+use super::{
+    package::{
+        ChangelogSectionSource, CommitDetails, CommitHash, CommitTypeBump, REVERT_COMMIT_TYPE,
+    },
+    references::Links,
+    Change, ChangeType, Package,
+};
 use crate::{
-    config::CommitFooter,
+    config::{CommitFooter, GitHub},
     integrations::git::{self, get_commit_messages_after_tag, get_current_versions_from_tags},
-    step::{releases, releases::git::tag_name},
+    step::releases,
     workflow::Verbose,
 };
 
@@ -20,81 +27,268 @@ pub(crate) struct ConventionalCommit {
 }
 
 impl ConventionalCommit {
-    fn from_commit_messages(
-        commit_messages: &[String],
+    fn from_git_commits(
+        git_commits: &[git::Commit],
         consider_scopes: bool,
         package: &Package,
+        links: Links,
     ) -> Vec<Self> {
-        let commits = commit_messages
+        let messages = cancel_reverts(git_commits)
+            .into_iter()
+            .map(|git_commit| (git_commit, normalize_revert(git_commit.message.trim())))
+            .collect_vec();
+        let commits = messages
             .iter()
-            .filter_map(|message| Commit::parse(message.trim()).ok())
-            .filter(|commit| {
+            .filter_map(|(git_commit, message)| {
+                Commit::parse(message)
+                    .ok()
+                    .map(|commit| (*git_commit, commit))
+            })
+            .filter(|(git_commit, commit)| {
+                let scope_matches = |scopes: &Vec<String>| {
+                    commit
+                        .scope()
+                        .is_some_and(|scope| scopes.contains(&scope.to_string()))
+                };
+                if package.paths.is_some() {
+                    // Paths are more reliable than scopes, but an explicit scope still counts
+                    return git_commit
+                        .changed_paths
+                        .iter()
+                        .any(|path| package.contains_path(path))
+                        || package.scopes.as_ref().is_some_and(scope_matches);
+                }
                 if !consider_scopes {
                     return true;
                 }
                 match (commit.scope(), &package.scopes) {
                     (None, _) => true,
                     (Some(_), None) => false,
-                    (Some(scope), Some(scopes)) => scopes.contains(&scope.to_string()),
+                    (Some(_), Some(scopes)) => scope_matches(scopes),
                 }
             })
-            .collect();
+            .collect_vec();
         debug!("Selected commits: {:?}", commits);
-        Self::from_commits(package, commits)
+        commits
+            .into_iter()
+            .flat_map(|(git_commit, commit)| {
+                Self::from_commit(package, &commit)
+                    .into_iter()
+                    .map(move |mut conventional_commit| {
+                        conventional_commit.add_details(
+                            git_commit,
+                            &commit,
+                            package.commit_details,
+                            links,
+                        );
+                        conventional_commit
+                    })
+                    .collect_vec()
+            })
+            .collect()
     }
 
+    #[cfg(test)]
     fn from_commits(package: &Package, commits: Vec<Commit>) -> Vec<Self> {
-        let mut conventional_commits = Vec::with_capacity(commits.len());
-
-        for commit in commits {
-            let commit_summary = format_commit_summary(&commit);
-            for footer in commit.footers() {
-                let source: ChangelogSectionSource = CommitFooter::from(footer.token()).into();
-                if package.extra_changelog_sections.contains_key(&source) {
-                    conventional_commits.push(Self {
-                        change_type: ChangeType::from(source),
-                        message: footer.value().to_string(),
-                        original_source: format_commit_footer(&commit_summary, footer),
-                    });
-                }
-            }
-            if let Some(breaking_message) = commit.breaking_description() {
-                let original_source = commit
-                    .footers()
-                    .iter()
-                    .find(|it| it.breaking())
-                    .map_or_else(
-                        || commit_summary.clone(),
-                        |breaking_footer| format_commit_footer(&commit_summary, breaking_footer),
-                    );
+        commits
+            .iter()
+            .flat_map(|commit| Self::from_commit(package, commit))
+            .collect()
+    }
+
+    fn from_commit(package: &Package, commit: &Commit) -> Vec<Self> {
+        let mut conventional_commits = Vec::new();
+        let commit_summary = format_commit_summary(commit);
+        for footer in commit.footers() {
+            let source: ChangelogSectionSource = CommitFooter::from(footer.token()).into();
+            if package.extra_changelog_sections.contains_key(&source) {
                 conventional_commits.push(Self {
-                    change_type: ChangeType::Breaking,
-                    message: breaking_message.to_string(),
-                    original_source,
+                    change_type: ChangeType::from(source),
+                    message: footer.value().to_string(),
+                    original_source: format_commit_footer(&commit_summary, footer),
                 });
-                if breaking_message == commit.description() {
-                    // There is no separate breaking change message, so the normal description is used.
-                    // Don't include the same message elsewhere.
-                    continue;
-                }
             }
+        }
+        if let Some(breaking_message) = commit.breaking_description() {
+            let original_source = commit
+                .footers()
+                .iter()
+                .find(|it| it.breaking())
+                .map_or_else(
+                    || commit_summary.clone(),
+                    |breaking_footer| format_commit_footer(&commit_summary, breaking_footer),
+                );
+            conventional_commits.push(Self {
+                change_type: ChangeType::Breaking,
+                message: breaking_message.to_string(),
+                original_source,
+            });
+            if breaking_message == commit.description() {
+                // There is no separate breaking change message, so the normal description is used.
+                // Don't include the same message elsewhere.
+                return conventional_commits;
+            }
+        }
 
-            if commit.type_() == Type::FEAT {
+        let commit_type = commit.type_().as_str();
+        if let Some(rule) = package.commit_type_rule(commit_type) {
+            // Commits which neither bump nor appear in the changelog are left out entirely
+            if rule.bump != CommitTypeBump::None || rule.section().is_some() {
                 conventional_commits.push(Self {
-                    change_type: ChangeType::Feature,
-                    message: commit.description().to_string(),
-                    original_source: commit_summary,
-                });
-            } else if commit.type_() == Type::FIX {
-                conventional_commits.push(Self {
-                    change_type: ChangeType::Fix,
+                    change_type: ChangeType::Custom(ChangelogSectionSource::CommitType(
+                        commit_type.to_string(),
+                    )),
                     message: commit.description().to_string(),
                     original_source: commit_summary,
                 });
             }
+        } else if commit.type_() == Type::FEAT {
+            conventional_commits.push(Self {
+                change_type: ChangeType::Feature,
+                message: commit.description().to_string(),
+                original_source: commit_summary,
+            });
+        } else if commit.type_() == Type::FIX {
+            conventional_commits.push(Self {
+                change_type: ChangeType::Fix,
+                message: commit.description().to_string(),
+                original_source: commit_summary,
+            });
         }
         conventional_commits
     }
+
+    /// Link the references to issues in the changelog message, then add the details of
+    /// `git_commit` which are enabled in `details`.
+    ///
+    /// The body of `commit` and the list of related issues are only added to the entry for its
+    /// description, not to entries from its footers.
+    fn add_details(
+        &mut self,
+        git_commit: &git::Commit,
+        commit: &Commit,
+        details: CommitDetails,
+        links: Links,
+    ) {
+        let is_description = self.message == commit.description();
+        // References already linked in the entry aren't repeated in the "Related" list
+        let mut shown = self.message.clone();
+        self.message = links.link(&self.message);
+        if !is_description {
+            return self.add_metadata(git_commit, details, links);
+        }
+        if details.body {
+            if let Some(body) = commit.body().map(str::trim).filter(|body| !body.is_empty()) {
+                self.message = format!("{}\n\n{}", self.message, links.link(body));
+                shown = format!("{shown}\n\n{body}");
+            }
+        }
+        let related = links.related(&git_commit.message, &shown);
+        if !related.is_empty() {
+            self.message = format!(
+                "{}\n\nRelated:\n\n{}",
+                self.message,
+                related.iter().map(|link| format!("- {link}")).join("\n")
+            );
+        }
+        self.add_metadata(git_commit, details, links);
+    }
+
+    /// Add a line with the ID and author of `git_commit`, if enabled in `details`.
+    fn add_metadata(&mut self, git_commit: &git::Commit, details: CommitDetails, links: Links) {
+        let short_id = git_commit.id.get(..SHORT_ID_LENGTH);
+        let hash = match (details.hash, short_id, links.github) {
+            (CommitHash::None, ..) | (_, None, _) => None,
+            (CommitHash::Link, Some(short_id), Some(GitHub { owner, repo })) => Some(format!(
+                "[`{short_id}`](https://github.com/{owner}/{repo}/commit/{})",
+                git_commit.id
+            )),
+            (CommitHash::Short | CommitHash::Link, Some(short_id), _) => {
+                Some(format!("`{short_id}`"))
+            }
+        };
+        let author =
+            Some(git_commit.author.as_str()).filter(|author| details.author && !author.is_empty());
+        let line = match (hash, author) {
+            (Some(hash), Some(author)) => format!("{hash} by {author}"),
+            (Some(hash), None) => hash,
+            (None, Some(author)) => format!("By {author}"),
+            (None, None) => return,
+        };
+        self.message = format!("{}\n\n{line}", self.message);
+    }
+}
+
+/// The number of characters of commit IDs shown in changelogs, matching `git log --oneline`.
+const SHORT_ID_LENGTH: usize = 7;
+
+/// Leave out every revert commit along with the commit it reverts, when both are in `commits`.
+///
+/// `commits` must be in order from oldest to newest. Starting from the newest commit means that
+/// reverting a revert brings back the original commit.
+fn cancel_reverts(commits: &[git::Commit]) -> Vec<&git::Commit> {
+    let mut cancelled = HashSet::new();
+    for (index, commit) in commits.iter().enumerate().rev() {
+        if cancelled.contains(&index) {
+            continue;
+        }
+        let Some(reverted_id) = reverted_id(&commit.message) else {
+            continue;
+        };
+        let reverted = commits
+            .get(..index)
+            .unwrap_or_default()
+            .iter()
+            .enumerate()
+            .rev()
+            .find(|(earlier, earlier_commit)| {
+                !cancelled.contains(earlier) && earlier_commit.id.starts_with(reverted_id)
+            });
+        if let Some((reverted, _)) = reverted {
+            debug!("Commit {} reverts {}", commit.id, reverted_id);
+            cancelled.insert(index);
+            cancelled.insert(reverted);
+        }
+    }
+    commits
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| !cancelled.contains(index))
+        .map(|(_, commit)| commit)
+        .collect()
+}
+
+/// The (possibly abbreviated) ID of the commit reverted by a commit with `message`, if any.
+///
+/// This is found in the `This reverts commit <id>.` line added by `git revert` or, for `revert:`
+/// commits, in a `Refs: <id>` footer.
+fn reverted_id(message: &str) -> Option<&str> {
+    let is_revert_type = message.starts_with(REVERT_COMMIT_TYPE);
+    message.lines().find_map(|line| {
+        let id = line
+            .trim()
+            .strip_prefix("This reverts commit ")
+            .or_else(|| {
+                is_revert_type
+                    .then(|| line.trim().strip_prefix("Refs: "))
+                    .flatten()
+            })?;
+        let id = id.trim_end_matches('.');
+        (id.len() >= 7 && id.chars().all(|c| c.is_ascii_hexdigit())).then_some(id)
+    })
+}
+
+/// Turn the message of a commit made by `git revert`, like `Revert "feat: A feature"`, into a
+/// conventional commit like `revert: feat: A feature`.
+fn normalize_revert(message: &str) -> Cow<str> {
+    let (subject, body) = message.split_once('\n').unwrap_or((message, ""));
+    match subject
+        .strip_prefix("Revert \"")
+        .and_then(|reverted| reverted.strip_suffix('"'))
+    {
+        Some(reverted) => Cow::Owned(format!("{REVERT_COMMIT_TYPE}: {reverted}\n{body}")),
+        None => Cow::Borrowed(message),
+    }
 }
 
 fn format_commit_summary(commit: &Commit) -> String {
@@ -136,10 +330,21 @@ impl Display for ConventionalCommit {
 
 #[cfg(test)]
 mod test_conventional_commits {
+    use glob::Pattern;
     use indexmap::IndexMap;
     use pretty_assertions::assert_eq;
 
     use super::*;
+    use crate::{config::ChangeLogSectionName, step::releases::package::CommitTypeRule};
+
+    fn commit(message: &str) -> git::Commit {
+        git::Commit {
+            id: String::new(),
+            author: String::new(),
+            message: message.to_string(),
+            changed_paths: Vec::new(),
+        }
+    }
 
     #[test]
     fn commit_types() {
@@ -227,14 +432,15 @@ mod test_conventional_commits {
             "feat(wrong_scope)!: Wrong scope breaking change!",
             "fix: No scope",
         ]
-        .map(String::from);
-        let conventional_commits = ConventionalCommit::from_commit_messages(
+        .map(commit);
+        let conventional_commits = ConventionalCommit::from_git_commits(
             &commits,
             false,
             &Package {
                 scopes: Some(vec![String::from("scope")]),
                 ..Package::default()
             },
+            Links::default(),
         );
         assert_eq!(
             conventional_commits,
@@ -261,9 +467,13 @@ mod test_conventional_commits {
             "feat(scope)!: Wrong scope breaking change!",
             "fix: No scope",
         ]
-        .map(String::from);
-        let conventional_commits =
-            ConventionalCommit::from_commit_messages(&commits, true, &Package::default());
+        .map(commit);
+        let conventional_commits = ConventionalCommit::from_git_commits(
+            &commits,
+            true,
+            &Package::default(),
+            Links::default(),
+        );
         assert_eq!(
             conventional_commits,
             vec![ConventionalCommit {
@@ -281,14 +491,15 @@ mod test_conventional_commits {
             "feat(scope): Right scope feature",
             "fix: No scope",
         ]
-        .map(String::from);
-        let conventional_commits = ConventionalCommit::from_commit_messages(
+        .map(commit);
+        let conventional_commits = ConventionalCommit::from_git_commits(
             &commits,
             true,
             &Package {
                 scopes: Some(vec![String::from("scope")]),
                 ..Package::default()
             },
+            Links::default(),
         );
         assert_eq!(
             conventional_commits,
@@ -307,9 +518,258 @@ mod test_conventional_commits {
         );
     }
 
+    #[test]
+    fn consider_paths() {
+        let commits = [
+            git::Commit {
+                message: String::from("feat: Changes core"),
+                changed_paths: vec![String::from("core/src/lib.rs")],
+                ..git::Commit::default()
+            },
+            git::Commit {
+                message: String::from("fix: Changes app"),
+                changed_paths: vec![String::from("app/src/main.rs")],
+                ..git::Commit::default()
+            },
+            git::Commit {
+                message: String::from("fix: Changes a file at the root of core"),
+                changed_paths: vec![String::from("core/Cargo.toml")],
+                ..git::Commit::default()
+            },
+            git::Commit {
+                message: String::from("fix(core): Scoped without changing core"),
+                changed_paths: vec![String::from("README.md")],
+                ..git::Commit::default()
+            },
+            git::Commit {
+                message: String::from("feat(app): Wrong scope, but changes core"),
+                changed_paths: vec![String::from("core/src/lib.rs")],
+                ..git::Commit::default()
+            },
+        ];
+        let conventional_commits = ConventionalCommit::from_git_commits(
+            &commits,
+            true,
+            &Package {
+                scopes: Some(vec![String::from("core")]),
+                paths: Some(vec![
+                    Pattern::new("core/src/**").unwrap(),
+                    Pattern::new("core/*.toml").unwrap(),
+                ]),
+                ..Package::default()
+            },
+            Links::default(),
+        );
+        assert_eq!(
+            conventional_commits
+                .into_iter()
+                .map(|commit| commit.original_source)
+                .collect_vec(),
+            vec![
+                "feat: Changes core",
+                "fix: Changes a file at the root of core",
+                "fix(core): Scoped without changing core",
+                "feat(app): Wrong scope, but changes core",
+            ]
+        );
+    }
+
+    #[test]
+    fn custom_commit_types() {
+        let commits = [
+            "perf: Faster",
+            "refactor: Cleaner",
+            "chore: Ignored",
+            "fix: Still a fix",
+            "deps!: Breaking dependency update",
+        ]
+        .map(commit);
+        let mut commit_types = IndexMap::new();
+        commit_types.insert(
+            String::from("perf"),
+            CommitTypeRule {
+                bump: CommitTypeBump::Patch,
+                section: Some(ChangeLogSectionName::from("Performance")),
+            },
+        );
+        commit_types.insert(
+            String::from("refactor"),
+            CommitTypeRule {
+                bump: CommitTypeBump::None,
+                section: None,
+            },
+        );
+        commit_types.insert(
+            String::from("deps"),
+            CommitTypeRule {
+                bump: CommitTypeBump::Patch,
+                section: None,
+            },
+        );
+        let conventional_commits = ConventionalCommit::from_git_commits(
+            &commits,
+            false,
+            &Package {
+                commit_types,
+                ..Package::default()
+            },
+            Links::default(),
+        );
+        assert_eq!(
+            conventional_commits,
+            vec![
+                ConventionalCommit {
+                    change_type: ChangeType::Custom(ChangelogSectionSource::CommitType(
+                        String::from("perf")
+                    )),
+                    message: String::from("Faster"),
+                    original_source: String::from("perf: Faster"),
+                },
+                ConventionalCommit {
+                    change_type: ChangeType::Fix,
+                    message: String::from("Still a fix"),
+                    original_source: String::from("fix: Still a fix"),
+                },
+                ConventionalCommit {
+                    change_type: ChangeType::Breaking,
+                    message: String::from("Breaking dependency update"),
+                    original_source: String::from("deps!: Breaking dependency update"),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn commit_details() {
+        let commits = [
+            git::Commit {
+                id: String::from("0123456789abcdef0123456789abcdef01234567"),
+                author: String::from("Jane Doe"),
+                message: String::from(
+                    "feat: A feature\n\nAll about the feature.\n\nBREAKING CHANGE: Something broke",
+                ),
+                ..git::Commit::default()
+            },
+            git::Commit {
+                id: String::from("89abcdef0123456789abcdef0123456789abcdef"),
+                author: String::from("John Doe"),
+                message: String::from("fix: A fix"),
+                ..git::Commit::default()
+            },
+        ];
+        let github = GitHub {
+            owner: String::from("knope-dev"),
+            repo: String::from("knope"),
+        };
+        let package = |commit_details| Package {
+            commit_details,
+            ..Package::default()
+        };
+        let messages = |package: &Package, github: Option<&GitHub>| {
+            let links = Links { github, jira: None };
+            ConventionalCommit::from_git_commits(&commits, false, package, links)
+                .into_iter()
+                .map(|commit| commit.message)
+                .collect_vec()
+        };
+
+        assert_eq!(
+            messages(&package(CommitDetails::default()), Some(&github)),
+            vec!["Something broke", "A feature", "A fix"]
+        );
+        let all_details = package(CommitDetails {
+            body: true,
+            author: true,
+            hash: CommitHash::Link,
+        });
+        assert_eq!(
+            messages(&all_details, Some(&github)),
+            vec![
+                "Something broke\n\n[`0123456`](https://github.com/knope-dev/knope/commit/0123456789abcdef0123456789abcdef01234567) by Jane Doe",
+                "A feature\n\nAll about the feature.\n\n[`0123456`](https://github.com/knope-dev/knope/commit/0123456789abcdef0123456789abcdef01234567) by Jane Doe",
+                "A fix\n\n[`89abcde`](https://github.com/knope-dev/knope/commit/89abcdef0123456789abcdef0123456789abcdef) by John Doe",
+            ]
+        );
+        // Without a forge to link to, only the short ID is added
+        assert_eq!(
+            messages(&all_details, None),
+            vec![
+                "Something broke\n\n`0123456` by Jane Doe",
+                "A feature\n\nAll about the feature.\n\n`0123456` by Jane Doe",
+                "A fix\n\n`89abcde` by John Doe",
+            ]
+        );
+        let author_only = package(CommitDetails {
+            author: true,
+            ..CommitDetails::default()
+        });
+        assert_eq!(
+            messages(&author_only, None),
+            vec![
+                "Something broke\n\nBy Jane Doe",
+                "A feature\n\nBy Jane Doe",
+                "A fix\n\nBy John Doe",
+            ]
+        );
+    }
+
+    #[test]
+    fn reverts() {
+        let commits = [
+            ("1111111111", "feat: A feature which is reverted"),
+            ("2222222222", "feat: A feature which stays"),
+            (
+                "3333333333",
+                "Revert \"feat: A feature which is reverted\"\n\nThis reverts commit 1111111111.",
+            ),
+            (
+                "4444444444",
+                "revert: fix: A fix from an earlier release\n\nRefs: 0000000",
+            ),
+            ("5555555555", "fix: A fix which is reverted twice"),
+            ("6666666666", "revert: Once\n\nRefs: 5555555"),
+            (
+                "7777777777",
+                "Revert \"revert: Once\"\n\nThis reverts commit 6666666666.",
+            ),
+        ]
+        .map(|(id, message)| git::Commit {
+            id: id.to_string(),
+            ..commit(message)
+        });
+        let conventional_commits = ConventionalCommit::from_git_commits(
+            &commits,
+            false,
+            &Package::default(),
+            Links::default(),
+        );
+        assert_eq!(
+            conventional_commits,
+            vec![
+                ConventionalCommit {
+                    change_type: ChangeType::Feature,
+                    message: String::from("A feature which stays"),
+                    original_source: String::from("feat: A feature which stays"),
+                },
+                ConventionalCommit {
+                    change_type: ChangeType::Custom(ChangelogSectionSource::CommitType(
+                        String::from("revert")
+                    )),
+                    message: String::from("fix: A fix from an earlier release"),
+                    original_source: String::from("revert: fix: A fix from an earlier release"),
+                },
+                ConventionalCommit {
+                    change_type: ChangeType::Fix,
+                    message: String::from("A fix which is reverted twice"),
+                    original_source: String::from("fix: A fix which is reverted twice"),
+                },
+            ]
+        );
+    }
+
     #[test]
     fn custom_footers() {
-        let commits = [String::from(
+        let commits = [commit(
             "chore: ignored type\n\nignored-footer: ignored\ncustom-footer: hello",
         )];
         let mut extra_changelog_sections = IndexMap::new();
@@ -317,13 +777,14 @@ mod test_conventional_commits {
             CommitFooter::from("custom-footer").into(),
             "custom section".into(),
         );
-        let conventional_commits = ConventionalCommit::from_commit_messages(
+        let conventional_commits = ConventionalCommit::from_git_commits(
             &commits,
             false,
             &Package {
                 extra_changelog_sections,
                 ..Package::default()
             },
+            Links::default(),
         );
         assert_eq!(
             conventional_commits,
@@ -343,15 +804,21 @@ mod test_conventional_commits {
 fn get_conventional_commits_after_last_stable_version(
     package: &Package,
     consider_scopes: bool,
+    links: Links,
     verbose: Verbose,
 ) -> Result<Vec<ConventionalCommit>, Error> {
-    let target_version = get_current_versions_from_tags(package.name.as_deref(), verbose)?.stable;
-    let tag = target_version.map(|version| tag_name(&version.into(), package.name.as_ref()));
-    let commit_messages = get_commit_messages_after_tag(tag, verbose).map_err(git::Error::from)?;
-    Ok(ConventionalCommit::from_commit_messages(
-        &commit_messages,
+    let tag_template = package.tag_template();
+    let target_version =
+        get_current_versions_from_tags(&tag_template, package.uses_pep440(), verbose)?
+            .latest_stable();
+    let tag = target_version.map(|version| tag_template.tag_name(&version));
+    let git_commits = get_commit_messages_after_tag(tag, package.paths.is_some(), verbose)
+        .map_err(git::Error::from)?;
+    Ok(ConventionalCommit::from_git_commits(
+        &git_commits,
         consider_scopes,
         package,
+        links,
     ))
 }
 
@@ -365,24 +832,30 @@ pub(crate) enum Error {
     GitRelease(#[from] releases::git::Error),
 }
 
+/// Add the changes from conventional commits since the last release to each package.
+///
+/// `links` are used for references to issues in commit messages, and to link to the commits
+/// themselves for packages which enable that in `commit_details`.
 pub(crate) fn add_releases_from_conventional_commits(
     packages: Vec<Package>,
+    links: Links,
     verbose: Verbose,
 ) -> Result<Vec<Package>, Error> {
     let consider_scopes = packages.iter().any(|package| package.scopes.is_some());
     packages
         .into_iter()
-        .map(|package| add_release_for_package(package, consider_scopes, verbose))
+        .map(|package| add_release_for_package(package, consider_scopes, links, verbose))
         .collect()
 }
 
 fn add_release_for_package(
     mut package: Package,
     consider_scopes: bool,
+    links: Links,
     verbose: Verbose,
 ) -> Result<Package, Error> {
-    get_conventional_commits_after_last_stable_version(&package, consider_scopes, verbose).map(
-        |commits| {
+    get_conventional_commits_after_last_stable_version(&package, consider_scopes, links, verbose)
+        .map(|commits| {
             if commits.is_empty() {
                 package
             } else {
@@ -392,6 +865,5 @@ fn add_release_for_package(
                     .collect();
                 package
             }
-        },
-    )
+        })
 }
@@ -0,0 +1,150 @@
+use std::collections::BTreeSet;
+
+use itertools::Itertools;
+
+use super::{versioned_file::VersionedFile, Package};
+
+/// Which packages depend on which, based on the dependencies listed in their versioned files.
+pub(crate) struct DependencyGraph {
+    /// For each package, the names its versioned files define
+    names: Vec<Vec<String>>,
+    /// For each package, the indices of the packages it depends on
+    dependencies: Vec<BTreeSet<usize>>,
+    /// Every package index, with dependencies before the packages which depend on them
+    order: Vec<usize>,
+}
+
+impl DependencyGraph {
+    pub(crate) fn new(packages: &[Package]) -> Self {
+        let names = packages
+            .iter()
+            .map(|package| {
+                package
+                    .versioned_files
+                    .iter()
+                    .filter_map(VersionedFile::package_name)
+                    .unique()
+                    .collect_vec()
+            })
+            .collect_vec();
+        let dependencies = packages
+            .iter()
+            .enumerate()
+            .map(|(index, package)| {
+                let required = package
+                    .versioned_files
+                    .iter()
+                    .flat_map(VersionedFile::dependencies)
+                    .collect_vec();
+                names
+                    .iter()
+                    .positions(|names| names.iter().any(|name| required.contains(name)))
+                    .filter(|dependency| *dependency != index)
+                    .collect()
+            })
+            .collect_vec();
+        let order = topological_order(&dependencies);
+        Self {
+            names,
+            dependencies,
+            order,
+        }
+    }
+
+    /// The names that other packages can use to depend on the package at `index`.
+    pub(crate) fn names_of(&self, index: usize) -> &[String] {
+        self.names.get(index).map_or(&[], Vec::as_slice)
+    }
+
+    /// The indices of the packages that the package at `index` depends on.
+    pub(crate) fn dependencies_of(&self, index: usize) -> impl Iterator<Item = usize> + '_ {
+        self.dependencies
+            .get(index)
+            .into_iter()
+            .flat_map(|dependencies| dependencies.iter().copied())
+    }
+
+    /// Every package index, with each package after the packages it depends on.
+    pub(crate) fn order(&self) -> &[usize] {
+        &self.order
+    }
+}
+
+/// Sort packages so that dependencies come first. Packages in a cycle keep their original order.
+fn topological_order(dependencies: &[BTreeSet<usize>]) -> Vec<usize> {
+    let mut order = Vec::with_capacity(dependencies.len());
+    while order.len() < dependencies.len() {
+        let next = (0..dependencies.len())
+            .filter(|index| !order.contains(index))
+            .find(|index| {
+                dependencies.get(*index).map_or(true, |dependencies| {
+                    dependencies
+                        .iter()
+                        .all(|dependency| order.contains(dependency))
+                })
+            });
+        // In a cycle, no package is ready, so fall back to the first remaining one
+        let next = next.or_else(|| (0..dependencies.len()).find(|index| !order.contains(index)));
+        order.extend(next);
+    }
+    order
+}
+
+/// Replace the version in a requirement like `^1.2.3` or `>=1.2` with `new_version`, keeping the
+/// operator.
+///
+/// Returns `None` for requirements which aren't a single version, like `*`, `>=1,<2`, or
+/// `workspace:^1.0.0`, since there's no way to know what the new requirement should be.
+pub(crate) fn update_requirement(requirement: &str, new_version: &str) -> Option<String> {
+    let version_start = requirement.find(|c: char| c.is_ascii_digit())?;
+    let (operator, version) = requirement.split_at(version_start);
+    let single_version = matches!(operator.trim(), "" | "^" | "~" | "~=" | "=" | "==" | ">=")
+        && !version.contains([',', ' ', '|', '*']);
+    single_version.then(|| format!("{operator}{new_version}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn requirements() {
+        for (requirement, expected) in [
+            ("1.2.3", Some("2.0.0")),
+            ("^1.2", Some("^2.0.0")),
+            ("~1.2.3", Some("~2.0.0")),
+            ("= 1.2.3", Some("= 2.0.0")),
+            (">=1.2.3", Some(">=2.0.0")),
+            ("~=1.2", Some("~=2.0.0")),
+            ("==1.2.3", Some("==2.0.0")),
+            ("!=1.2.3", None),
+            ("<2.0.0", None),
+            ("<=1.2.3", None),
+            (">1.2.3", None),
+            ("*", None),
+            (">=1.2, <2", None),
+            ("1.x || 2.x", None),
+            ("workspace:^1.2.3", None),
+        ] {
+            assert_eq!(
+                update_requirement(requirement, "2.0.0").as_deref(),
+                expected,
+                "updating {requirement}"
+            );
+        }
+    }
+
+    #[test]
+    fn order() {
+        let dependencies = [
+            BTreeSet::from([2]),
+            BTreeSet::new(),
+            BTreeSet::from([1]),
+            BTreeSet::from([4]),
+            BTreeSet::from([3]),
+        ];
+        assert_eq!(topological_order(&dependencies), vec![1, 2, 0, 3, 4]);
+    }
+}
@@ -1,3 +1,6 @@
+use std::{fmt, fmt::Display, str::FromStr};
+
+use itertools::Itertools;
 use log::error;
 use miette::Diagnostic;
 use thiserror::Error;
@@ -9,19 +12,113 @@ use crate::{
     integrations::git::{self, create_tag},
 };
 
-pub(crate) fn tag_name(version: &Version, package_name: Option<&PackageName>) -> String {
-    let prefix = package_name
-        .as_ref()
-        .map_or_else(|| "v".to_string(), |name| format!("{name}/v"));
-    format!("{prefix}{version}")
+const VERSION_PLACEHOLDER: &str = "{version}";
+const PACKAGE_PLACEHOLDER: &str = "{package}";
+
+/// How the Git tags for releases of a package are named, like `v{version}` or
+/// `{package}@{version}`.
+///
+/// The same template is used to create tags and to find the current version from existing tags,
+/// so everything around `{version}` is fixed when the package is loaded.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct TagTemplate {
+    template: String,
+    prefix: String,
+    suffix: String,
+}
+
+impl TagTemplate {
+    /// Parse a template for the package named `package_name`, making sure that every tag it
+    /// creates is a valid Git tag which can be read back as the same version.
+    pub(crate) fn new(template: &str, package_name: Option<&PackageName>) -> Result<Self, Error> {
+        let (prefix, suffix) = match template.split(VERSION_PLACEHOLDER).collect_vec().as_slice() {
+            [prefix, suffix] => (*prefix, *suffix),
+            _ => return Err(Error::TagTemplateVersion(template.to_string())),
+        };
+        let resolve = |part: &str| -> Result<String, Error> {
+            let resolved = match package_name {
+                Some(package_name) => part.replace(PACKAGE_PLACEHOLDER, package_name),
+                None if part.contains(PACKAGE_PLACEHOLDER) => {
+                    return Err(Error::TagTemplatePackage(template.to_string()))
+                }
+                None => part.to_string(),
+            };
+            if resolved.contains('{') || resolved.contains('}') {
+                return Err(Error::TagTemplatePlaceholder(template.to_string()));
+            }
+            Ok(resolved)
+        };
+        let tag_template = Self {
+            template: template.to_string(),
+            prefix: resolve(prefix)?,
+            suffix: resolve(suffix)?,
+        };
+        tag_template.check_round_trip()?;
+        Ok(tag_template)
+    }
+
+    /// The template used when a package doesn't set one: `v{version}`, or
+    /// `{package}/v{version}` for packages with a name.
+    pub(crate) fn default_for(package_name: Option<&PackageName>) -> Self {
+        let prefix = package_name.map_or_else(|| "v".to_string(), |name| format!("{name}/v"));
+        Self {
+            template: package_name.map_or_else(
+                || format!("v{VERSION_PLACEHOLDER}"),
+                |_| format!("{PACKAGE_PLACEHOLDER}/v{VERSION_PLACEHOLDER}"),
+            ),
+            prefix,
+            suffix: String::new(),
+        }
+    }
+
+    /// The name of the tag for `version`.
+    pub(crate) fn tag_name(&self, version: &Version) -> String {
+        format!("{}{version}{}", self.prefix, self.suffix)
+    }
+
+    /// The version part of `tag`, if the tag was created by this template.
+    pub(crate) fn version_from_tag<'tag>(&self, tag: &'tag str) -> Option<&'tag str> {
+        tag.strip_prefix(&self.prefix)?
+            .strip_suffix(&self.suffix)
+            .filter(|version| !version.is_empty())
+    }
+
+    fn check_round_trip(&self) -> Result<(), Error> {
+        for sample in ["1.2.3", "1.2.3-rc.4", "1.2.3+build.5"] {
+            let version = Version::from_str(sample).map_err(|_| self.round_trip_error(sample))?;
+            let tag = self.tag_name(&version);
+            let read_back = gix::refs::FullName::try_from(format!("refs/tags/{tag}"))
+                .ok()
+                .and_then(|_| self.version_from_tag(&tag))
+                .and_then(|version| Version::from_str(version).ok());
+            // Compare the text, since equality of versions ignores build metadata
+            if read_back.map(|version| version.to_string()) != Some(version.to_string()) {
+                return Err(self.round_trip_error(&tag));
+            }
+        }
+        Ok(())
+    }
+
+    fn round_trip_error(&self, tag: &str) -> Error {
+        Error::TagTemplateRoundTrip {
+            template: self.template.clone(),
+            tag: tag.to_string(),
+        }
+    }
+}
+
+impl Display for TagTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.template)
+    }
 }
 
 pub(crate) fn release(
     dry_run_stdout: DryRun,
     version: &Version,
-    package_name: Option<&PackageName>,
+    tag_template: &TagTemplate,
 ) -> Result<(), Error> {
-    let tag = tag_name(version, package_name);
+    let tag = tag_template.tag_name(version);
 
     create_tag(dry_run_stdout, tag).map_err(Error::from)
 }
@@ -34,4 +131,113 @@ pub(crate) enum Error {
     #[error(transparent)]
     #[diagnostic(transparent)]
     Git(#[from] git::Error),
+    #[error("Tag template {0} must contain {{version}} exactly once")]
+    #[diagnostic(
+        code(git::tag_template_version),
+        help("A tag template looks like `v{{version}}` or `{{package}}@{{version}}`."),
+        url("https://knope-dev.github.io/knope/config/packages.html#tag_template")
+    )]
+    TagTemplateVersion(String),
+    #[error("Tag template {0} uses {{package}}, but the package has no name")]
+    #[diagnostic(
+        code(git::tag_template_package),
+        help("`{{package}}` can only be used in `[packages]`, use the name directly instead."),
+        url("https://knope-dev.github.io/knope/config/packages.html#tag_template")
+    )]
+    TagTemplatePackage(String),
+    #[error("Tag template {0} contains an unknown placeholder")]
+    #[diagnostic(
+        code(git::tag_template_placeholder),
+        help("Only `{{version}}` and `{{package}}` can be used in a tag template."),
+        url("https://knope-dev.github.io/knope/config/packages.html#tag_template")
+    )]
+    TagTemplatePlaceholder(String),
+    #[error(
+        "Tag template {template} creates the tag {tag}, which can't be read back as a version"
+    )]
+    #[diagnostic(
+        code(git::tag_template_round_trip),
+        help(
+            "Tags must be valid Git tag names (no spaces, `~`, `^`, `:`, or `..`), and the text \
+            around `{{version}}` must not look like part of the version."
+        ),
+        url("https://knope-dev.github.io/knope/config/packages.html#tag_template")
+    )]
+    TagTemplateRoundTrip { template: String, tag: String },
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn default_templates() {
+        let version = Version::from_str("1.2.3-rc.0").unwrap();
+        let template = TagTemplate::default_for(None);
+        assert_eq!(template.tag_name(&version), "v1.2.3-rc.0");
+        assert_eq!(template.to_string(), "v{version}");
+        let name = PackageName::from("first");
+        let template = TagTemplate::default_for(Some(&name));
+        assert_eq!(template.tag_name(&version), "first/v1.2.3-rc.0");
+        assert_eq!(template.to_string(), "{package}/v{version}");
+        assert_eq!(
+            TagTemplate::new("{package}/v{version}", Some(&name)).unwrap(),
+            template
+        );
+    }
+
+    #[test]
+    fn custom_templates() {
+        let version = Version::new(1, 2, 3, None);
+        let template = TagTemplate::new("release-{version}", None).unwrap();
+        assert_eq!(template.tag_name(&version), "release-1.2.3");
+        assert_eq!(template.version_from_tag("release-1.2.3"), Some("1.2.3"));
+        assert_eq!(template.version_from_tag("v1.2.3"), None);
+
+        let name = PackageName::from("@scope/pkg");
+        let template = TagTemplate::new("{package}@{version}", Some(&name)).unwrap();
+        assert_eq!(template.tag_name(&version), "@scope/pkg@1.2.3");
+        assert_eq!(template.version_from_tag("@scope/pkg@1.2.3"), Some("1.2.3"));
+        assert_eq!(template.version_from_tag("@scope/other@1.2.3"), None);
+
+        let template = TagTemplate::new("v{version}-final", None).unwrap();
+        assert_eq!(
+            template.version_from_tag("v1.2.3-rc.1-final"),
+            Some("1.2.3-rc.1")
+        );
+        assert_eq!(template.version_from_tag("v-final"), None);
+    }
+
+    #[test]
+    fn invalid_templates() {
+        for template in ["v", "{version}-{version}"] {
+            let result = TagTemplate::new(template, None);
+            assert!(
+                matches!(result, Err(Error::TagTemplateVersion(_))),
+                "Expected TagTemplateVersion for {template}, got {result:?}"
+            );
+        }
+        assert!(matches!(
+            TagTemplate::new("{package}@{version}", None),
+            Err(Error::TagTemplatePackage(_))
+        ));
+        assert!(matches!(
+            TagTemplate::new("{name}@{version}", Some(&PackageName::from("first"))),
+            Err(Error::TagTemplatePlaceholder(_))
+        ));
+        for template in [
+            "release {version}",
+            "v{version}..",
+            "~{version}",
+            "{version}.lock",
+        ] {
+            let result = TagTemplate::new(template, None);
+            assert!(
+                matches!(result, Err(Error::TagTemplateRoundTrip { .. })),
+                "Expected TagTemplateRoundTrip for {template}, got {result:?}"
+            );
+        }
+    }
 }
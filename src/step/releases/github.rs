@@ -1,10 +1,11 @@
 use miette::{diagnostic, Diagnostic};
 
-use super::{git::tag_name, package::Asset, PackageName, Release, TimeError};
+use super::{git::TagTemplate, package::Asset, PackageName, Release, TimeError};
 use crate::{config::GitHub, dry_run::DryRun, integrations::github as api, state};
 
 pub(crate) fn release(
     package_name: Option<&PackageName>,
+    tag_template: &TagTemplate,
     release: &Release,
     github_state: state::GitHub,
     github_config: &GitHub,
@@ -14,7 +15,7 @@ pub(crate) fn release(
     let version = &release.new_version;
     let release_title = release.title()?;
 
-    let tag_name = tag_name(version, package_name);
+    let tag_name = tag_template.tag_name(version);
     let name = if let Some(package_name) = package_name {
         format!("{package_name} {release_title}")
     } else {
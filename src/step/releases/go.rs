@@ -3,7 +3,7 @@ use std::{fmt::Display, path::Path, str::FromStr};
 use miette::Diagnostic;
 use thiserror::Error;
 
-use super::{semver::Version, tag_name, versioned_file::VersionFromSource, PackageName};
+use super::{git::TagTemplate, semver::Version, versioned_file::VersionFromSource, PackageName};
 use crate::{
     dry_run::DryRun,
     fs,
@@ -293,15 +293,14 @@ pub(crate) fn get_version(
         });
     }
 
-    if let Some(version_from_tag) = get_current_versions_from_tags(prefix.as_deref(), verbose)
+    // Go requires tags like `{dir}/v{version}`, regardless of the package's `tag_template`
+    let tag_template = TagTemplate::default_for(prefix.map(PackageName::from).as_ref());
+    if let Some(version_from_tag) = get_current_versions_from_tags(&tag_template, false, verbose)
         .map(|current_versions| {
             current_versions
                 .into_latest()
                 .map(|version| VersionFromSource {
-                    source: format!(
-                        "Git tag {tag}",
-                        tag = tag_name(&version, prefix.map(PackageName::from).as_ref())
-                    ),
+                    source: format!("Git tag {tag}", tag = tag_template.tag_name(&version)),
                     version,
                 })
         })
@@ -0,0 +1,393 @@
+use std::{
+    ops::Range,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use miette::Diagnostic;
+use thiserror::Error;
+
+use super::semver::Version;
+use crate::{dry_run::DryRun, fs};
+
+/// Get the version from a `build.gradle` or `build.gradle.kts` file.
+///
+/// This is either a top-level `version = "1.2.3"` or, for Android apps, the `versionName`.
+/// `path` is used for error reporting.
+pub(crate) fn get_version(content: &str, path: &Path) -> Result<Version, Error> {
+    let script = BuildScript::parse(content);
+    let version = script.version(content, path)?;
+    Version::from_str(version).map_err(Error::from)
+}
+
+/// Replace the `version` and `versionName` in a Gradle build script with `new_version`.
+///
+/// If `increment_version_code` is set, the Android `versionCode` is also increased by one.
+/// `path` is used for error reporting.
+pub(crate) fn set_version(
+    dry_run: DryRun,
+    mut content: String,
+    new_version: &str,
+    path: &Path,
+    increment_version_code: bool,
+) -> Result<String, Error> {
+    let script = BuildScript::parse(&content);
+    script.version(&content, path)?;
+
+    let mut replacements: Vec<(Range<usize>, String)> = [script.version, script.version_name]
+        .into_iter()
+        .flatten()
+        .map(|range| (range, new_version.to_string()))
+        .collect();
+    if increment_version_code {
+        let range = script
+            .version_code
+            .ok_or_else(|| Error::NoVersionCode(path.into()))?;
+        let version_code = content
+            .get(range.clone())
+            .unwrap_or_default()
+            .parse::<u64>()
+            .map_err(|_| Error::NoVersionCode(path.into()))?;
+        replacements.push((range, (version_code + 1).to_string()));
+    }
+
+    replacements.sort_by_key(|(range, _)| range.start);
+    for (range, replacement) in replacements.into_iter().rev() {
+        content.replace_range(range, &replacement);
+    }
+    fs::write(dry_run, new_version, path, &content)?;
+    Ok(content)
+}
+
+/// Get the `version` property from a `gradle.properties` file.
+///
+/// `path` is used for error reporting.
+pub(crate) fn get_properties_version(content: &str, path: &Path) -> Result<Version, Error> {
+    let range = find_property(content, "version").ok_or_else(|| Error::NoVersion(path.into()))?;
+    Version::from_str(content.get(range).unwrap_or_default()).map_err(Error::from)
+}
+
+/// Replace the `version` property in a `gradle.properties` file with `new_version`.
+///
+/// `path` is used for error reporting.
+pub(crate) fn set_properties_version(
+    dry_run: DryRun,
+    mut content: String,
+    new_version: &str,
+    path: &Path,
+) -> Result<String, Error> {
+    let range = find_property(&content, "version").ok_or_else(|| Error::NoVersion(path.into()))?;
+    content.replace_range(range, new_version);
+    fs::write(dry_run, new_version, path, &content)?;
+    Ok(content)
+}
+
+#[derive(Debug, Diagnostic, Error)]
+pub(crate) enum Error {
+    #[error("No version found in {0}")]
+    #[diagnostic(
+        code(gradle::no_version),
+        help(
+            "knope expects a Gradle build script to contain a top-level `version = \"1.2.3\"` or an \
+            Android `versionName \"1.2.3\"`, and `gradle.properties` to contain `version=1.2.3`."
+        ),
+        url("https://knope-dev.github.io/knope/config/packages.html#gradle")
+    )]
+    NoVersion(PathBuf),
+    #[error("Found conflicting versions {version} and {version_name} in {path}")]
+    #[diagnostic(
+        code(gradle::inconsistent),
+        help("Make sure `version` and `versionName` are the same."),
+        url("https://knope-dev.github.io/knope/config/packages.html#gradle")
+    )]
+    InconsistentVersions {
+        version: String,
+        version_name: String,
+        path: PathBuf,
+    },
+    #[error("No numeric versionCode found in {0}")]
+    #[diagnostic(
+        code(gradle::no_version_code),
+        help(
+            "`increment_version_code` requires an Android `versionCode` set to a number literal, \
+            like `versionCode = 1`."
+        ),
+        url("https://knope-dev.github.io/knope/config/packages.html#gradle")
+    )]
+    NoVersionCode(PathBuf),
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Fs(#[from] fs::Error),
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Semver(#[from] super::semver::version::Error),
+}
+
+/// The locations of the values knope cares about in a Gradle build script.
+#[derive(Debug, Default)]
+struct BuildScript {
+    /// The inside of the quotes of a top-level `version`
+    version: Option<Range<usize>>,
+    /// The inside of the quotes of `versionName`, anywhere in the file
+    version_name: Option<Range<usize>>,
+    /// The digits of `versionCode`, anywhere in the file
+    version_code: Option<Range<usize>>,
+}
+
+impl BuildScript {
+    /// Scan the script line by line.
+    ///
+    /// This isn't a real Groovy or Kotlin parser, it only understands simple assignments like
+    /// `version = "1.2.3"` (Kotlin & Groovy) or `versionName '1.2.3'` (Groovy).
+    fn parse(content: &str) -> Self {
+        let mut script = Self::default();
+        let mut depth: usize = 0;
+        let mut offset = 0;
+        for line in content.split_inclusive('\n') {
+            let start = offset;
+            offset += line.len();
+            let trimmed = line.trim_start();
+            if trimmed.starts_with("//") || trimmed.starts_with('*') || trimmed.starts_with("/*") {
+                continue;
+            }
+            let indent = line.len() - trimmed.len();
+            if depth == 0 && script.version.is_none() {
+                script.version =
+                    assigned_string(trimmed, "version").map(|range| shift(range, start + indent));
+            }
+            if script.version_name.is_none() {
+                script.version_name = assigned_string(trimmed, "versionName")
+                    .map(|range| shift(range, start + indent));
+            }
+            if script.version_code.is_none() {
+                script.version_code = assigned_number(trimmed, "versionCode")
+                    .map(|range| shift(range, start + indent));
+            }
+            let opened = line.matches('{').count();
+            let closed = line.matches('}').count();
+            depth = (depth + opened).saturating_sub(closed);
+        }
+        script
+    }
+
+    /// The consistent version from `version` and `versionName`, or an error.
+    fn version<'content>(
+        &self,
+        content: &'content str,
+        path: &Path,
+    ) -> Result<&'content str, Error> {
+        let text = |range: &Range<usize>| content.get(range.clone()).unwrap_or_default();
+        match (&self.version, &self.version_name) {
+            (Some(version), Some(version_name)) if text(version) != text(version_name) => {
+                Err(Error::InconsistentVersions {
+                    version: text(version).to_string(),
+                    version_name: text(version_name).to_string(),
+                    path: path.into(),
+                })
+            }
+            (Some(range), _) | (None, Some(range)) => Ok(text(range)),
+            (None, None) => Err(Error::NoVersion(path.into())),
+        }
+    }
+}
+
+/// If `line` assigns a quoted string to `key`, return the range of the string's content.
+fn assigned_string(line: &str, key: &str) -> Option<Range<usize>> {
+    let value_start = value_start(line, key)?;
+    let value = line.get(value_start..)?;
+    let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    let len = value.get(1..)?.find(quote)?;
+    Some(value_start + 1..value_start + 1 + len)
+}
+
+/// If `line` assigns a number literal to `key`, return the range of the digits.
+fn assigned_number(line: &str, key: &str) -> Option<Range<usize>> {
+    let value_start = value_start(line, key)?;
+    let len = line
+        .get(value_start..)?
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(line.len() - value_start);
+    (len > 0).then_some(value_start..value_start + len)
+}
+
+/// Find where the value starts in a line like `key = value` or `key value`.
+fn value_start(line: &str, key: &str) -> Option<usize> {
+    let rest = line.strip_prefix(key)?;
+    let after_key = rest.trim_start();
+    let after_equals = after_key.strip_prefix('=');
+    if after_equals.is_none() && after_key.len() == rest.len() {
+        // Something like `versionNameSuffix`, not this key
+        return None;
+    }
+    let value = after_equals.unwrap_or(after_key).trim_start();
+    Some(line.len() - value.len())
+}
+
+/// Find the range of the value of `key` in a Java properties file.
+fn find_property(content: &str, key: &str) -> Option<Range<usize>> {
+    let mut offset = 0;
+    for line in content.split_inclusive('\n') {
+        let start = offset;
+        offset += line.len();
+        let trimmed = line.trim_start();
+        if trimmed.starts_with('#') || trimmed.starts_with('!') {
+            continue;
+        }
+        let Some(rest) = trimmed.strip_prefix(key) else {
+            continue;
+        };
+        let after_key = rest.trim_start();
+        let Some(value) = after_key
+            .strip_prefix('=')
+            .or_else(|| after_key.strip_prefix(':'))
+        else {
+            continue;
+        };
+        let value = value.trim();
+        let value_start = start + line.len() - value.len() - (line.len() - line.trim_end().len());
+        return Some(value_start..value_start + value.len());
+    }
+    None
+}
+
+fn shift(range: Range<usize>, offset: usize) -> Range<usize> {
+    range.start + offset..range.end + offset
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::dry_run::fake_dry_run;
+
+    const KOTLIN: &str = r#"plugins {
+    id("com.android.application")
+    kotlin("jvm") version "1.9.10"
+}
+
+group = "org.example"
+version = "1.2.3"
+
+android {
+    defaultConfig {
+        versionCode = 41
+        versionName = "1.2.3"
+        versionNameSuffix = "-dev"
+    }
+}
+
+dependencies {
+    constraints {
+        implementation("org.example:lib") {
+            version = "9.9.9"
+        }
+    }
+}
+"#;
+
+    #[test]
+    fn test_get_version() {
+        assert_eq!(
+            get_version(KOTLIN, Path::new("")).unwrap(),
+            Version::new(1, 2, 3, None)
+        );
+    }
+
+    #[test]
+    fn test_set_version() {
+        let new = set_version(
+            &mut fake_dry_run(),
+            KOTLIN.to_string(),
+            "1.3.0",
+            Path::new(""),
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            new,
+            KOTLIN
+                .replace("version = \"1.2.3\"", "version = \"1.3.0\"")
+                .replace("versionName = \"1.2.3\"", "versionName = \"1.3.0\"")
+        );
+    }
+
+    #[test]
+    fn increment_version_code() {
+        let new = set_version(
+            &mut fake_dry_run(),
+            KOTLIN.to_string(),
+            "1.3.0",
+            Path::new(""),
+            true,
+        )
+        .unwrap();
+        assert!(new.contains("versionCode = 42\n"), "{new}");
+        assert!(new.contains("versionName = \"1.3.0\"\n"), "{new}");
+    }
+
+    #[test]
+    fn groovy_android() {
+        let groovy = "android {\n    defaultConfig {\n        versionCode 9\n        versionName '0.1.0'\n    }\n}\n";
+        assert_eq!(
+            get_version(groovy, Path::new("")).unwrap(),
+            Version::new(0, 1, 0, None)
+        );
+        let new = set_version(
+            &mut fake_dry_run(),
+            groovy.to_string(),
+            "0.2.0",
+            Path::new(""),
+            true,
+        )
+        .unwrap();
+        assert_eq!(
+            new,
+            groovy
+                .replace("versionCode 9", "versionCode 10")
+                .replace("'0.1.0'", "'0.2.0'")
+        );
+    }
+
+    #[test]
+    fn inconsistent_versions() {
+        let script = "version = '1.0.0'\nandroid {\n  versionName = '2.0.0'\n}\n";
+        let result = get_version(script, Path::new(""));
+        assert!(
+            matches!(result, Err(Error::InconsistentVersions { .. })),
+            "Expected InconsistentVersions, got {result:?}"
+        );
+    }
+
+    #[test]
+    fn missing_version_code() {
+        let result = set_version(
+            &mut fake_dry_run(),
+            "version = \"1.0.0\"\n".to_string(),
+            "1.1.0",
+            Path::new(""),
+            true,
+        );
+        assert!(
+            matches!(result, Err(Error::NoVersionCode(_))),
+            "Expected NoVersionCode, got {result:?}"
+        );
+    }
+
+    #[test]
+    fn properties() {
+        let properties = "# The version\n#version=0.0.0\norg.gradle.jvmargs=-Xmx2g\nversion = 1.2.3  \nkotlin.code.style=official\n";
+        assert_eq!(
+            get_properties_version(properties, Path::new("")).unwrap(),
+            Version::new(1, 2, 3, None)
+        );
+        let new = set_properties_version(
+            &mut fake_dry_run(),
+            properties.to_string(),
+            "2.0.0",
+            Path::new(""),
+        )
+        .unwrap();
+        assert_eq!(new, properties.replace("= 1.2.3", "= 2.0.0"));
+    }
+}
@@ -0,0 +1,318 @@
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    dependencies::DependencyGraph,
+    package,
+    semver::{ConventionalRule, Label, Version},
+    Change, Package, PackageName, Rule,
+};
+use crate::{config::GitHub, dry_run::DryRun, workflow::Verbose};
+
+/// A set of packages which are released together, from `[[package_groups]]` in `knope.toml`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub(crate) struct PackageGroup {
+    #[serde(rename = "type")]
+    pub(crate) kind: GroupKind,
+    pub(crate) packages: Vec<PackageName>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum GroupKind {
+    /// Every member is released whenever any of them changes, all with the same version
+    Fixed,
+    /// Members with changes are released with the same bump—the largest needed by any of them
+    Linked,
+}
+
+/// What to do with a package when preparing a release.
+enum Plan {
+    Skip,
+    /// Bump with the given rule, or the one implied by the package's own changes
+    Bump(Option<ConventionalRule>),
+    /// Use the version of a fixed group, which has these members
+    Version {
+        version: Version,
+        group: Vec<PackageName>,
+    },
+}
+
+/// Write a release for every package with pending changes, keeping the members of `groups`
+/// together.
+///
+/// Packages which depend on a released package are released too (at least as a patch), with their
+/// requirement on the dependency updated to its new version.
+pub(crate) fn write_releases(
+    packages: Vec<Package>,
+    groups: &[PackageGroup],
+    prerelease_label: &Option<Label>,
+    github: Option<&GitHub>,
+    dry_run: DryRun,
+    verbose: Verbose,
+) -> Result<Vec<Package>, package::Error> {
+    let mut plans = packages
+        .iter()
+        .map(|package| {
+            if package.has_releasable_changes() {
+                Plan::Bump(None)
+            } else {
+                Plan::Skip
+            }
+        })
+        .collect_vec();
+    let graph = DependencyGraph::new(&packages);
+    propagate_releases(&packages, &graph, groups, &mut plans, verbose);
+
+    plan_groups(&packages, groups, &mut plans, prerelease_label, verbose)?;
+
+    // Release dependencies first, so their new versions are known by the packages using them
+    let mut new_versions: Vec<Option<Version>> = vec![None; packages.len()];
+    let mut released = packages.into_iter().map(Some).collect_vec();
+    for index in graph.order() {
+        let (Some(mut package), Some(plan)) = (
+            released.get_mut(*index).and_then(Option::take),
+            plans.get_mut(*index),
+        ) else {
+            continue;
+        };
+        let (new_version, group) = match std::mem::replace(plan, Plan::Skip) {
+            Plan::Skip => (None, Vec::new()),
+            Plan::Bump(bump_rule) => (
+                Some(package.next_release_version(prerelease_label, bump_rule, verbose)?),
+                Vec::new(),
+            ),
+            Plan::Version { version, group } => (Some(version), group),
+        };
+        let package = match new_version {
+            Some(new_version) => {
+                let updated_dependencies = graph
+                    .dependencies_of(*index)
+                    .filter_map(|dependency| {
+                        let version = new_versions.get(dependency)?.as_ref()?;
+                        Some(
+                            graph
+                                .names_of(dependency)
+                                .iter()
+                                .map(|name| (name.clone(), version.clone())),
+                        )
+                    })
+                    .flatten()
+                    .collect_vec();
+                if let Some(slot) = new_versions.get_mut(*index) {
+                    *slot = Some(new_version.clone());
+                }
+                write_release(
+                    package,
+                    new_version,
+                    updated_dependencies,
+                    &group,
+                    github,
+                    dry_run,
+                )?
+            }
+            None => package,
+        };
+        if let Some(slot) = released.get_mut(*index) {
+            *slot = Some(package);
+        }
+    }
+    Ok(released.into_iter().flatten().collect())
+}
+
+/// Write the release of `package`, first updating its requirements on `updated_dependencies`.
+/// Write the release of `package`, which is in a fixed `group` (or none, if empty).
+///
+/// Members of a fixed group which have no changes of their own say which group they were released
+/// with, so that their changelog entry isn't empty.
+fn write_release(
+    mut package: Package,
+    new_version: Version,
+    updated_dependencies: Vec<(String, Version)>,
+    group: &[PackageName],
+    github: Option<&GitHub>,
+    dry_run: DryRun,
+) -> Result<Package, package::Error> {
+    if !group.is_empty() && !package.has_releasable_changes() && updated_dependencies.is_empty() {
+        let others = group
+            .iter()
+            .filter(|member| package.name.as_ref() != Some(*member))
+            .map(ToString::to_string)
+            .collect();
+        package
+            .pending_changes
+            .push(Change::ReleasedWithGroup(others));
+    }
+    if !updated_dependencies.is_empty() {
+        for versioned_file in &mut package.versioned_files {
+            for (name, version) in &updated_dependencies {
+                versioned_file.set_dependency_version(dry_run, name, version)?;
+            }
+        }
+        package
+            .pending_changes
+            .push(Change::DependenciesUpdated(updated_dependencies));
+    }
+    package.write_release(new_version, github, dry_run)
+}
+
+/// Use the same bump for the released members of linked groups, and the same version for every
+/// member of fixed groups.
+fn plan_groups(
+    packages: &[Package],
+    groups: &[PackageGroup],
+    plans: &mut [Plan],
+    prerelease_label: &Option<Label>,
+    verbose: Verbose,
+) -> Result<(), package::Error> {
+    for group in groups {
+        let members = packages
+            .iter()
+            .positions(|package| {
+                package
+                    .name
+                    .as_ref()
+                    .is_some_and(|name| group.packages.contains(name))
+            })
+            .collect_vec();
+        let Some(rule) = members
+            .iter()
+            .filter_map(|index| match (plans.get(*index), packages.get(*index)) {
+                (Some(Plan::Bump(_)), Some(package)) => Some(package.bump_rule(verbose)),
+                _ => None,
+            })
+            .max()
+        else {
+            continue;
+        };
+        match group.kind {
+            GroupKind::Linked => {
+                if let Verbose::Yes = verbose {
+                    println!(
+                        "Using rule {rule} for linked packages {}",
+                        group.packages.iter().join(", ")
+                    );
+                }
+                for index in &members {
+                    if let Some(plan @ Plan::Bump(_)) = plans.get_mut(*index) {
+                        *plan = Plan::Bump(Some(rule));
+                    }
+                }
+            }
+            GroupKind::Fixed => {
+                let version = fixed_version(packages, &members, prerelease_label, rule, verbose)?;
+                if let Verbose::Yes = verbose {
+                    println!(
+                        "Using version {version} for fixed packages {}",
+                        group.packages.iter().join(", ")
+                    );
+                }
+                for index in &members {
+                    if let Some(plan) = plans.get_mut(*index) {
+                        *plan = Plan::Version {
+                            version: version.clone(),
+                            group: group.packages.clone(),
+                        };
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Release every package which depends on a released package, along with the rest of its fixed
+/// group, until nothing else needs to be released.
+fn propagate_releases(
+    packages: &[Package],
+    graph: &DependencyGraph,
+    groups: &[PackageGroup],
+    plans: &mut [Plan],
+    verbose: Verbose,
+) {
+    let is_released = |plans: &[Plan], index: usize| !matches!(plans.get(index), Some(Plan::Skip));
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for index in graph.order() {
+            if is_released(plans, *index)
+                || !graph
+                    .dependencies_of(*index)
+                    .any(|dependency| is_released(plans, dependency))
+            {
+                continue;
+            }
+            if let (Verbose::Yes, Some(Some(name))) =
+                (verbose, packages.get(*index).map(|package| &package.name))
+            {
+                println!("Releasing {name} because its dependencies are being released");
+            }
+            if let Some(plan) = plans.get_mut(*index) {
+                *plan = Plan::Bump(None);
+                changed = true;
+            }
+        }
+        for group in groups.iter().filter(|group| group.kind == GroupKind::Fixed) {
+            let members = packages
+                .iter()
+                .positions(|package| {
+                    package
+                        .name
+                        .as_ref()
+                        .is_some_and(|name| group.packages.contains(name))
+                })
+                .collect_vec();
+            if !members.iter().any(|member| is_released(plans, *member)) {
+                continue;
+            }
+            for member in members {
+                if let Some(plan @ Plan::Skip) = plans.get_mut(member) {
+                    *plan = Plan::Bump(None);
+                    changed = true;
+                }
+            }
+        }
+    }
+}
+
+/// The next version for every member of a fixed group, bumped from the newest version of any
+/// member.
+fn fixed_version(
+    packages: &[Package],
+    members: &[usize],
+    prerelease_label: &Option<Label>,
+    rule: ConventionalRule,
+    verbose: Verbose,
+) -> Result<Version, package::Error> {
+    let members = members
+        .iter()
+        .filter_map(|index| packages.get(*index))
+        .collect_vec();
+    if let Some(override_version) = members
+        .iter()
+        .find_map(|member| member.override_version.clone())
+    {
+        return Ok(override_version);
+    }
+    let member_versions = members
+        .into_iter()
+        .map(|member| {
+            member
+                .get_version(verbose)
+                .map(|versions| (member, versions))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let Some((leader, versions)) = member_versions
+        .into_iter()
+        .max_by_key(|(_, versions)| versions.clone().into_latest())
+    else {
+        return Ok(Version::default());
+    };
+    leader
+        .next_version(
+            versions,
+            &Rule::from_changes(rule, prerelease_label),
+            verbose,
+        )
+        .map_err(package::Error::from)
+}
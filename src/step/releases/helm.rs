@@ -0,0 +1,214 @@
+use std::{
+    ops::Range,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use miette::Diagnostic;
+use thiserror::Error;
+
+use super::{semver::Version, yaml};
+use crate::{dry_run::DryRun, fs};
+
+/// Get the chart `version` from a Helm `Chart.yaml`.
+///
+/// If `app_version` is set, the `appVersion` must be the same as the `version`.
+/// `path` is used for error reporting.
+pub(crate) fn get_version(content: &str, path: &Path, app_version: bool) -> Result<Version, Error> {
+    let chart = Chart::parse(content);
+    let version = chart
+        .version
+        .map(|range| text(content, range))
+        .ok_or_else(|| Error::NoVersion(path.into()))?;
+    if app_version {
+        let app_version = chart
+            .app_version
+            .map(|range| text(content, range))
+            .ok_or_else(|| Error::NoAppVersion(path.into()))?;
+        if app_version != version {
+            return Err(Error::InconsistentVersions {
+                version: version.to_string(),
+                app_version: app_version.to_string(),
+                path: path.into(),
+            });
+        }
+    }
+    Version::from_str(version).map_err(Error::from)
+}
+
+/// Replace the `version` (and optionally `appVersion`) in a `Chart.yaml` with `new_version`.
+///
+/// Only the values themselves are replaced, so comments, quotes, and ordering are kept.
+/// `path` is used for error reporting.
+pub(crate) fn set_version(
+    dry_run: DryRun,
+    mut content: String,
+    new_version: &str,
+    path: &Path,
+    app_version: bool,
+) -> Result<String, Error> {
+    let chart = Chart::parse(&content);
+    let mut ranges = vec![chart.version.ok_or_else(|| Error::NoVersion(path.into()))?];
+    if app_version {
+        ranges.push(
+            chart
+                .app_version
+                .ok_or_else(|| Error::NoAppVersion(path.into()))?,
+        );
+    }
+    ranges.sort_by_key(|range| range.start);
+    for range in ranges.into_iter().rev() {
+        content.replace_range(range, new_version);
+    }
+    fs::write(dry_run, new_version, path, &content)?;
+    Ok(content)
+}
+
+#[derive(Debug, Diagnostic, Error)]
+pub(crate) enum Error {
+    #[error("No version found in {0}")]
+    #[diagnostic(
+        code(helm::no_version),
+        help("knope expects the Chart.yaml file to have a top-level `version`."),
+        url("https://knope-dev.github.io/knope/config/packages.html#helm-chartyaml")
+    )]
+    NoVersion(PathBuf),
+    #[error("No appVersion found in {0}")]
+    #[diagnostic(
+        code(helm::no_app_version),
+        help(
+            "Add a top-level `appVersion` to the Chart.yaml file, or remove `app_version = true`."
+        ),
+        url("https://knope-dev.github.io/knope/config/packages.html#helm-chartyaml")
+    )]
+    NoAppVersion(PathBuf),
+    #[error("Found conflicting versions {version} and {app_version} in {path}")]
+    #[diagnostic(
+        code(helm::inconsistent),
+        help("Make sure `version` and `appVersion` are the same."),
+        url("https://knope-dev.github.io/knope/config/packages.html#helm-chartyaml")
+    )]
+    InconsistentVersions {
+        version: String,
+        app_version: String,
+        path: PathBuf,
+    },
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Fs(#[from] fs::Error),
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Semver(#[from] super::semver::version::Error),
+}
+
+/// The locations of the values knope cares about in a `Chart.yaml`.
+#[derive(Debug, Default)]
+struct Chart {
+    /// The value of the top-level `version`, without quotes
+    version: Option<Range<usize>>,
+    /// The value of the top-level `appVersion`, without quotes
+    app_version: Option<Range<usize>>,
+}
+
+impl Chart {
+    fn parse(content: &str) -> Self {
+        Self {
+            version: yaml::top_level_scalar(content, "version"),
+            app_version: yaml::top_level_scalar(content, "appVersion"),
+        }
+    }
+}
+
+fn text(content: &str, range: Range<usize>) -> &str {
+    content.get(range).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::dry_run::fake_dry_run;
+
+    const CHART: &str = r#"apiVersion: v2
+name: my-service
+description: A Helm chart for my-service
+type: application
+
+# The chart version, bumped by knope
+version: 0.1.0 # keep in sync with the service
+
+appVersion: "0.1.0"
+
+dependencies:
+  - name: postgresql
+    version: 12.1.2
+    repository: https://charts.bitnami.com/bitnami
+"#;
+
+    #[test]
+    fn test_get_version() {
+        assert_eq!(
+            get_version(CHART, Path::new(""), false).unwrap(),
+            Version::new(0, 1, 0, None)
+        );
+        assert_eq!(
+            get_version(CHART, Path::new(""), true).unwrap(),
+            Version::new(0, 1, 0, None)
+        );
+    }
+
+    #[test]
+    fn test_set_version() {
+        let new = set_version(
+            &mut fake_dry_run(),
+            CHART.to_string(),
+            "0.2.0",
+            Path::new(""),
+            false,
+        )
+        .unwrap();
+        assert_eq!(new, CHART.replace("version: 0.1.0 #", "version: 0.2.0 #"));
+    }
+
+    #[test]
+    fn set_app_version() {
+        let new = set_version(
+            &mut fake_dry_run(),
+            CHART.to_string(),
+            "0.2.0",
+            Path::new(""),
+            true,
+        )
+        .unwrap();
+        assert_eq!(
+            new,
+            CHART
+                .replace("version: 0.1.0 #", "version: 0.2.0 #")
+                .replace("appVersion: \"0.1.0\"", "appVersion: \"0.2.0\"")
+        );
+    }
+
+    #[test]
+    fn inconsistent_app_version() {
+        let chart = "version: 1.0.0\nappVersion: '2.0.0'\n";
+        assert_eq!(
+            get_version(chart, Path::new(""), false).unwrap(),
+            Version::new(1, 0, 0, None)
+        );
+        let result = get_version(chart, Path::new(""), true);
+        assert!(
+            matches!(result, Err(Error::InconsistentVersions { .. })),
+            "Expected InconsistentVersions, got {result:?}"
+        );
+    }
+
+    #[test]
+    fn missing_app_version() {
+        let result = get_version("version: 1.0.0\n", Path::new(""), true);
+        assert!(
+            matches!(result, Err(Error::NoAppVersion(_))),
+            "Expected NoAppVersion, got {result:?}"
+        );
+    }
+}
@@ -0,0 +1,305 @@
+use std::{
+    ops::Range,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use miette::Diagnostic;
+use quick_xml::{events::Event, Reader};
+use thiserror::Error;
+
+use super::semver::Version;
+use crate::{dry_run::DryRun, fs};
+
+/// Get the project version from a `pom.xml`, falling back to the `<parent>` version for modules
+/// which inherit it from a local parent.
+///
+/// `path` is used for error reporting.
+pub(crate) fn get_version(content: &str, path: &Path) -> Result<Version, Error> {
+    let pom = Pom::parse(content, path)?;
+    let parent_version = pom
+        .parent_version
+        .as_ref()
+        .filter(|_| pom.has_local_parent(content, path));
+    let version = pom
+        .version
+        .as_ref()
+        .or(parent_version)
+        .ok_or_else(|| Error::NoVersion(path.into()))?;
+    Version::from_str(version.text(content)).map_err(Error::from)
+}
+
+/// Replace the project-level `<version>` in a `pom.xml` with `new_version`.
+///
+/// If the `<parent>` is a local module (found via `<relativePath>`, which defaults to
+/// `../pom.xml`), its `<version>` is updated as well, since the parent is released along with
+/// its modules. Dependency and external parent versions are never changed, so a module which
+/// inherits its version from an external parent can't be updated.
+pub(crate) fn set_version(
+    dry_run: DryRun,
+    mut pom_xml: String,
+    new_version: &str,
+    path: &Path,
+) -> Result<String, Error> {
+    let pom = Pom::parse(&pom_xml, path)?;
+    let mut ranges = Vec::with_capacity(2);
+    if let Some(version) = &pom.version {
+        ranges.push(version.range.clone());
+    }
+    if let Some(parent_version) = &pom.parent_version {
+        if pom.has_local_parent(&pom_xml, path) {
+            ranges.push(parent_version.range.clone());
+        }
+    }
+    if ranges.is_empty() {
+        return Err(Error::NoVersion(path.into()));
+    }
+
+    ranges.sort_by_key(|range| range.start);
+    for range in ranges.into_iter().rev() {
+        pom_xml.replace_range(range, new_version);
+    }
+    fs::write(dry_run, new_version, path, &pom_xml)?;
+    Ok(pom_xml)
+}
+
+#[derive(Debug, Diagnostic, Error)]
+pub(crate) enum Error {
+    #[error("Could not parse {path} as XML: {source}")]
+    #[diagnostic(
+        code(maven::xml),
+        help("knope expects the pom.xml file to be valid XML."),
+        url("https://knope-dev.github.io/knope/config/packages.html#supported-formats-for-versioning")
+    )]
+    Xml {
+        path: PathBuf,
+        #[source]
+        source: quick_xml::Error,
+    },
+    #[error("No version found in {0}")]
+    #[diagnostic(
+        code(maven::no_version),
+        help(
+            "knope expects the pom.xml file to have a `<version>` in `<project>`, \
+            or a `<version>` in a local `<parent>` to inherit."
+        ),
+        url("https://knope-dev.github.io/knope/config/packages.html#supported-formats-for-versioning")
+    )]
+    NoVersion(PathBuf),
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Fs(#[from] fs::Error),
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Semver(#[from] super::semver::version::Error),
+}
+
+/// The text content of an element, located by its byte range in the original file.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct ElementText {
+    range: Range<usize>,
+}
+
+impl ElementText {
+    fn text<'content>(&self, content: &'content str) -> &'content str {
+        content.get(self.range.clone()).unwrap_or_default()
+    }
+}
+
+/// The parts of a `pom.xml` that knope cares about.
+#[derive(Debug, Default)]
+struct Pom {
+    /// `/project/version`
+    version: Option<ElementText>,
+    /// `/project/parent/version`
+    parent_version: Option<ElementText>,
+    /// `/project/parent/artifactId`
+    parent_artifact_id: Option<ElementText>,
+    /// `/project/parent/relativePath`
+    parent_relative_path: Option<ElementText>,
+    /// `/project/artifactId`
+    artifact_id: Option<ElementText>,
+}
+
+impl Pom {
+    fn parse(content: &str, path: &Path) -> Result<Self, Error> {
+        let mut pom = Self::default();
+        let mut reader = Reader::from_str(content);
+        let mut stack: Vec<Vec<u8>> = Vec::new();
+        loop {
+            let start = reader.buffer_position();
+            let event = reader.read_event().map_err(|source| Error::Xml {
+                path: path.into(),
+                source,
+            })?;
+            match event {
+                Event::Start(element) => stack.push(element.local_name().as_ref().to_vec()),
+                Event::End(_) => {
+                    stack.pop();
+                }
+                Event::Text(_) => {
+                    let end = reader.buffer_position();
+                    let slot = match stack.iter().map(Vec::as_slice).collect::<Vec<_>>()[..] {
+                        [b"project", b"version"] => &mut pom.version,
+                        [b"project", b"artifactId"] => &mut pom.artifact_id,
+                        [b"project", b"parent", b"version"] => &mut pom.parent_version,
+                        [b"project", b"parent", b"artifactId"] => &mut pom.parent_artifact_id,
+                        [b"project", b"parent", b"relativePath"] => &mut pom.parent_relative_path,
+                        _ => continue,
+                    };
+                    *slot = Some(trimmed(content, start..end));
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+        Ok(pom)
+    }
+
+    /// Whether the `<parent>` of this POM is a module on disk, rather than an external POM.
+    fn has_local_parent(&self, content: &str, path: &Path) -> bool {
+        let Some(parent_artifact_id) = &self.parent_artifact_id else {
+            return false;
+        };
+        let relative_path = self
+            .parent_relative_path
+            .as_ref()
+            .map_or("../pom.xml", |relative_path| relative_path.text(content));
+        let mut parent_path = path
+            .parent()
+            .unwrap_or_else(|| Path::new(""))
+            .join(relative_path);
+        if parent_path.is_dir() {
+            parent_path = parent_path.join("pom.xml");
+        }
+        let Ok(parent_content) = std::fs::read_to_string(&parent_path) else {
+            return false;
+        };
+        Self::parse(&parent_content, &parent_path)
+            .ok()
+            .and_then(|parent| parent.artifact_id)
+            .is_some_and(|artifact_id| {
+                artifact_id.text(&parent_content) == parent_artifact_id.text(content)
+            })
+    }
+}
+
+/// Shrink `range` to exclude any surrounding whitespace in `content`.
+fn trimmed(content: &str, range: Range<usize>) -> ElementText {
+    let raw = content.get(range.clone()).unwrap_or_default();
+    let start = range.start + (raw.len() - raw.trim_start().len());
+    let end = range.end - (raw.len() - raw.trim_end().len());
+    ElementText {
+        range: start..end.max(start),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{create_dir, write};
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::dry_run::fake_dry_run;
+
+    const POM: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<project xmlns="http://maven.apache.org/POM/4.0.0">
+  <!-- <version>9.9.9</version> -->
+  <modelVersion>4.0.0</modelVersion>
+  <parent>
+    <groupId>org.springframework.boot</groupId>
+    <artifactId>spring-boot-starter-parent</artifactId>
+    <version>3.1.0</version>
+  </parent>
+  <artifactId>tester</artifactId>
+  <version>
+    0.1.0-rc.0
+  </version>
+  <dependencies>
+    <dependency>
+      <groupId>org.example</groupId>
+      <artifactId>other</artifactId>
+      <version>0.1.0-rc.0</version>
+    </dependency>
+  </dependencies>
+</project>
+"#;
+
+    #[test]
+    fn test_get_version() {
+        assert_eq!(
+            get_version(POM, Path::new("")).unwrap(),
+            Version::from_str("0.1.0-rc.0").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_set_version() {
+        let new = set_version(
+            &mut fake_dry_run(),
+            String::from(POM),
+            "1.2.3-rc.4",
+            Path::new(""),
+        )
+        .unwrap();
+
+        let expected = POM.replace("\n    0.1.0-rc.0\n", "\n    1.2.3-rc.4\n");
+        assert_eq!(new, expected);
+    }
+
+    #[test]
+    fn module_of_local_parent() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        write(
+            temp_dir.path().join("pom.xml"),
+            "<project><artifactId>parent</artifactId><version>1.0.0</version></project>",
+        )
+        .unwrap();
+        create_dir(temp_dir.path().join("module")).unwrap();
+        let module_path = temp_dir.path().join("module").join("pom.xml");
+        let module = "<project>\n  <parent>\n    <artifactId>parent</artifactId>\n    \
+                      <version>1.0.0</version>\n  </parent>\n  <artifactId>module</artifactId>\n\
+                      </project>";
+
+        assert_eq!(
+            get_version(module, &module_path).unwrap(),
+            Version::new(1, 0, 0, None)
+        );
+        let new = set_version(&mut None, module.to_string(), "1.1.0", &module_path).unwrap();
+        assert_eq!(new, module.replace("1.0.0", "1.1.0"));
+    }
+
+    #[test]
+    fn module_of_external_parent() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let module_path = temp_dir.path().join("pom.xml");
+        let module = "<project>\n  <parent>\n    \
+                      <artifactId>spring-boot-starter-parent</artifactId>\n    \
+                      <version>3.1.0</version>\n  </parent>\n  <artifactId>module</artifactId>\n\
+                      </project>";
+
+        let result = get_version(module, &module_path);
+        assert!(
+            matches!(result, Err(Error::NoVersion(_))),
+            "Expected NoVersion, got {result:?}"
+        );
+        let result = set_version(&mut None, module.to_string(), "1.1.0", &module_path);
+        assert!(
+            matches!(result, Err(Error::NoVersion(_))),
+            "Expected NoVersion, got {result:?}"
+        );
+    }
+
+    #[test]
+    fn no_version() {
+        let result = get_version(
+            "<project><artifactId>a</artifactId></project>",
+            Path::new(""),
+        );
+        assert!(
+            matches!(result, Err(Error::NoVersion(_))),
+            "Expected NoVersion, got {result:?}"
+        );
+    }
+}
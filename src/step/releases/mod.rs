@@ -1,17 +1,21 @@
-use std::{collections::BTreeMap, fmt, fmt::Display};
+use std::{collections::BTreeMap, fmt, fmt::Display, str::FromStr};
 
 use ::changesets::PackageChange;
 use conventional_commits::{add_releases_from_conventional_commits, ConventionalCommit};
 use itertools::Itertools;
 use miette::Diagnostic;
 pub(crate) use non_empty_map::PrereleaseMap;
-use semver::{PreVersion, StableVersion, Version};
-use time::{macros::format_description, OffsetDateTime};
+use references::Links;
+use semver::{BuildMetadata, PreVersion, StableVersion, Version};
+use time::{
+    error::InvalidFormatDescription, format_description::OwnedFormatItem,
+    macros::format_description, OffsetDateTime,
+};
 use versioned_file::PackageFormat;
 
 pub(crate) use self::{
     changesets::{create_change_file, ChangeType},
-    git::tag_name,
+    groups::PackageGroup,
     package::{find_packages, ChangelogSectionSource, Package, PackageName},
     semver::{bump_version_and_update_state, Rule},
 };
@@ -20,18 +24,31 @@ use crate::{
     workflow::Verbose, RunType,
 };
 
+pub(crate) mod calver;
 mod cargo;
 pub(crate) mod changelog;
 pub(crate) mod changesets;
 mod conventional_commits;
+mod dependencies;
 pub(crate) mod git;
 pub(crate) mod github;
 pub(crate) mod go;
+mod gradle;
+mod groups;
+mod helm;
+mod maven;
+mod msbuild;
 pub(crate) mod package;
 mod package_json;
+mod pattern;
+pub(crate) mod pep440;
+mod pubspec;
 mod pyproject;
+mod references;
 pub(crate) mod semver;
+pub(crate) mod template;
 pub(crate) mod versioned_file;
+mod yaml;
 
 pub(crate) fn prepare_release(
     run_type: RunType,
@@ -45,22 +62,29 @@ pub(crate) fn prepare_release(
         return Err(package::Error::no_defined_packages_with_help().into());
     }
     let PrepareRelease { prerelease_label } = prepare_release;
-    state.packages = add_releases_from_conventional_commits(state.packages, state.verbose)
+    state.packages = add_releases_from_conventional_commits(
+        state.packages,
+        Links {
+            github: state.github_config.as_ref(),
+            jira: state.jira_config.as_ref(),
+        },
+        state.verbose,
+    )
+    .map_err(Error::from)
+    .and_then(|packages| {
+        changesets::add_releases_from_changeset(packages, &mut dry_run_stdout).map_err(Error::from)
+    })
+    .and_then(|packages| {
+        groups::write_releases(
+            packages,
+            &state.package_groups,
+            prerelease_label,
+            state.github_config.as_ref(),
+            &mut dry_run_stdout,
+            state.verbose,
+        )
         .map_err(Error::from)
-        .and_then(|packages| {
-            changesets::add_releases_from_changeset(packages, &mut dry_run_stdout)
-                .map_err(Error::from)
-        })
-        .and_then(|packages| {
-            packages
-                .into_iter()
-                .map(|package| {
-                    package
-                        .write_release(prerelease_label, &mut dry_run_stdout, state.verbose)
-                        .map_err(Error::from)
-                })
-                .collect()
-        })?;
+    })?;
 
     if let Some(stdout) = dry_run_stdout {
         Ok(RunType::DryRun { state, stdout })
@@ -86,6 +110,12 @@ pub(crate) struct Release {
     pub(crate) new_changelog: Option<String>,
     pub(crate) new_version: Version,
     date: OffsetDateTime,
+    /// How to write `date` in the title, if not `[year]-[month]-[day]`
+    date_format: Option<DateFormat>,
+    /// The whole changelog entry, including its heading, when it was rendered from a template
+    rendered_entry: Option<String>,
+    /// Where the version in the title links to, with a link reference definition
+    link: Option<String>,
 }
 
 impl Release {
@@ -94,24 +124,83 @@ impl Release {
             new_changelog: changelog,
             new_version: version,
             date: OffsetDateTime::now_utc(),
+            date_format: None,
+            rendered_entry: None,
+            link: None,
+        }
+    }
+
+    pub(crate) fn with_date_format(self, date_format: Option<DateFormat>) -> Self {
+        Self {
+            date_format,
+            ..self
         }
     }
 
+    pub(crate) fn with_link(self, link: Option<String>) -> Self {
+        Self { link, ..self }
+    }
+
+    /// The date of the release, in the configured format.
+    pub(crate) fn date(&self) -> Result<String, TimeError> {
+        let date = match &self.date_format {
+            Some(date_format) => self.date.format(&date_format.items)?,
+            None => self
+                .date
+                .format(format_description!("[year]-[month]-[day]"))?,
+        };
+        Ok(date)
+    }
+
     pub(crate) fn title(&self) -> Result<String, TimeError> {
-        let format = format_description!("[year]-[month]-[day]");
-        let date = self.date.format(&format)?;
-        Ok(format!("{} ({})", self.new_version, date))
+        Ok(format!("{} ({})", self.new_version, self.date()?))
     }
 
     pub(crate) fn changelog_entry(&self) -> Result<Option<String>, TimeError> {
-        self.title().map(|title| {
-            self.new_changelog
-                .as_ref()
-                .map(|changelog| format!("## {title}\n\n{changelog}"))
+        if let Some(rendered_entry) = &self.rendered_entry {
+            return Ok(Some(rendered_entry.clone()));
+        }
+        let title = if self.link.is_some() {
+            // Keep a Changelog style, `[1.2.3]` is a link defined at the end of the changelog
+            format!("[{}] ({})", self.new_version, self.date()?)
+        } else {
+            self.title()?
+        };
+        Ok(self.new_changelog.as_ref().map(|changelog| {
+            if changelog.trim().is_empty() {
+                format!("## {title}\n")
+            } else {
+                format!("## {title}\n\n{changelog}")
+            }
+        }))
+    }
+}
+
+/// How the date of a release is written in its title, from `date_format`—like
+/// `[year]-[month]-[day]`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct DateFormat {
+    description: String,
+    items: OwnedFormatItem,
+}
+
+impl FromStr for DateFormat {
+    type Err = InvalidFormatDescription;
+
+    fn from_str(description: &str) -> Result<Self, Self::Err> {
+        Ok(Self {
+            description: description.to_string(),
+            items: time::format_description::parse_owned::<2>(description)?,
         })
     }
 }
 
+impl Display for DateFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.description)
+    }
+}
+
 #[derive(Debug, Diagnostic, thiserror::Error)]
 #[error("Failed to format current time")]
 #[diagnostic(
@@ -153,9 +242,14 @@ pub(crate) enum Error {
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
+#[allow(clippy::enum_variant_names)]
 pub(crate) enum Change {
     ConventionalCommit(ConventionalCommit),
     ChangeSet(PackageChange),
+    /// Packages this one depends on were released with these versions
+    DependenciesUpdated(Vec<(String, Version)>),
+    /// Released only because these other members of its fixed group were
+    ReleasedWithGroup(Vec<String>),
 }
 
 impl Display for Change {
@@ -165,6 +259,14 @@ impl Display for Change {
             Change::ChangeSet(change) => {
                 write!(f, "{}", change.unique_id.to_file_name())
             }
+            Change::DependenciesUpdated(dependencies) => write!(
+                f,
+                "updated {}",
+                dependencies.iter().map(|(name, _)| name).join(", ")
+            ),
+            Change::ReleasedWithGroup(others) => {
+                write!(f, "released with {}", others.join(", "))
+            }
         }
     }
 }
@@ -174,13 +276,24 @@ impl Change {
         match self {
             Change::ConventionalCommit(commit) => commit.change_type.clone(),
             Change::ChangeSet(change) => (&change.change_type).into(),
+            Change::DependenciesUpdated(_) | Change::ReleasedWithGroup(_) => ChangeType::Fix,
         }
     }
 
-    fn summary(&self) -> String {
+    fn entry(&self) -> changelog::Entry {
         match self {
-            Change::ConventionalCommit(commit) => commit.message.clone(),
-            Change::ChangeSet(change) => change.summary.clone(),
+            Change::ConventionalCommit(commit) => changelog::Entry::from_text(&commit.message),
+            Change::ChangeSet(change) => changelog::Entry::from_markdown(&change.summary),
+            Change::DependenciesUpdated(dependencies) => changelog::Entry {
+                summary: String::from("Dependencies updated"),
+                body: dependencies
+                    .iter()
+                    .map(|(name, version)| format!("- {name} {version}"))
+                    .join("\n"),
+            },
+            Change::ReleasedWithGroup(others) => {
+                changelog::Entry::from_text(&format!("Released with {}", others.join(", ")))
+            }
         }
     }
 }
@@ -188,6 +301,8 @@ impl Change {
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
 pub(crate) struct CurrentVersions {
     pub(crate) stable: Option<StableVersion>,
+    /// Build metadata of `stable` as it was found, needed to recreate its tag
+    pub(crate) stable_build: Option<BuildMetadata>,
     pub(crate) prereleases: Prereleases,
 }
 
@@ -234,12 +349,18 @@ impl CurrentVersions {
             .pop_last()
             .map(|(stable_component, pres)| {
                 let pre_component = pres.into_last();
-                Version::Pre(PreVersion {
+                Version::from(PreVersion {
                     stable_component,
                     pre_component,
                 })
             })
-            .or_else(|| self.stable.map(Version::Stable))
+            .or_else(|| self.latest_stable())
+    }
+
+    /// The latest stable version, including any build metadata it was recorded with.
+    pub(crate) fn latest_stable(&self) -> Option<Version> {
+        self.stable
+            .map(|stable| Version::from(stable).with_build(self.stable_build.clone()))
     }
 
     /// Replace or insert the version in the correct location if it's newer than the current
@@ -248,18 +369,22 @@ impl CurrentVersions {
     /// the same stable component and label.
     pub(crate) fn update_version(&mut self, version: Version) {
         match version {
-            Version::Stable(new) => {
+            Version::Stable(new, build) => {
                 if let Some(existing) = &self.stable {
                     if existing >= &new {
                         return;
                     }
                 }
                 self.stable = Some(new);
+                self.stable_build = build;
             }
-            Version::Pre(PreVersion {
-                stable_component,
-                pre_component,
-            }) => {
+            Version::Pre(
+                PreVersion {
+                    stable_component,
+                    pre_component,
+                },
+                _,
+            ) => {
                 let recorded_pre = self
                     .prereleases
                     .get(&stable_component)
@@ -284,6 +409,7 @@ impl From<StableVersion> for CurrentVersions {
     fn from(version: StableVersion) -> Self {
         Self {
             stable: Some(version),
+            stable_build: None,
             prereleases: BTreeMap::new(),
         }
     }
@@ -338,6 +464,7 @@ pub(crate) fn release(run_type: RunType) -> Result<RunType, Error> {
         if let Some(github_config) = github_config.as_ref() {
             state.github = github::release(
                 package_to_release.package.name.as_ref(),
+                &package_to_release.package.tag_template(),
                 &package_to_release.release,
                 state.github,
                 github_config,
@@ -348,7 +475,7 @@ pub(crate) fn release(run_type: RunType) -> Result<RunType, Error> {
             git::release(
                 &mut dry_run_stdout,
                 &package_to_release.release.new_version,
-                package_to_release.package.name.as_ref(),
+                &package_to_release.package.tag_template(),
             )?;
         }
         add_go_mod_tags(&package_to_release, &mut dry_run_stdout)?;
@@ -372,8 +499,9 @@ fn find_prepared_release(package: &Package, verbose: Verbose) -> Result<Option<R
     let Some(current_version) = package.version_from_files(verbose)? else {
         return Ok(None);
     };
-    let last_tag = get_current_versions_from_tags(package.name.as_deref(), verbose)
-        .map(CurrentVersions::into_latest)?;
+    let last_tag =
+        get_current_versions_from_tags(&package.tag_template(), package.uses_pep440(), verbose)
+            .map(CurrentVersions::into_latest)?;
     let version_of_new_release = match last_tag {
         Some(last_tag) if last_tag != current_version => current_version,
         None => current_version,
@@ -386,6 +514,9 @@ fn find_prepared_release(package: &Package, verbose: Verbose) -> Result<Option<R
             .and_then(|changelog| changelog.get_section(&version_of_new_release)),
         new_version: version_of_new_release,
         date: OffsetDateTime::now_utc(),
+        date_format: package.date_format.clone(),
+        rendered_entry: None,
+        link: None,
     }))
 }
 
@@ -0,0 +1,379 @@
+use std::{
+    ffi::OsStr,
+    ops::Range,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use miette::Diagnostic;
+use quick_xml::{events::Event, Reader};
+use thiserror::Error;
+
+use super::semver::Version;
+use crate::{dry_run::DryRun, fs};
+
+/// The extensions of `MSBuild` project files, like `MyLibrary.csproj`.
+pub(crate) const PROJECT_EXTENSIONS: [&str; 3] = ["csproj", "fsproj", "vbproj"];
+
+/// Find the name of the one `MSBuild` project file directly in `dir`, if there is exactly one.
+pub(crate) fn single_project_file(dir: &Path) -> Option<PathBuf> {
+    let mut projects = std::fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok()?.file_name().to_str().map(PathBuf::from))
+        .filter(|file_name| {
+            file_name
+                .extension()
+                .and_then(OsStr::to_str)
+                .is_some_and(|extension| PROJECT_EXTENSIONS.contains(&extension))
+        });
+    let project = projects.next()?;
+    projects.next().is_none().then_some(project)
+}
+
+/// Get the version from an `MSBuild` project file (like `.csproj`) or `Directory.Build.props`.
+///
+/// This is the `<Version>` property if set, otherwise `<VersionPrefix>` plus `<VersionSuffix>`.
+/// `path` is used for error reporting.
+pub(crate) fn get_version(content: &str, path: &Path) -> Result<Version, Error> {
+    let project = Project::parse(content, path)?;
+    let text = |range: &Range<usize>| content.get(range.clone()).unwrap_or_default();
+    let version = if let Some(version) = &project.version {
+        text(version).to_string()
+    } else if let Some(prefix) = &project.version_prefix {
+        match project
+            .version_suffix
+            .as_ref()
+            .map(|suffix| suffix.text(content))
+        {
+            Some(suffix) if !suffix.is_empty() => format!("{}-{suffix}", text(prefix)),
+            _ => text(prefix).to_string(),
+        }
+    } else {
+        return Err(Error::NoVersion(path.into()));
+    };
+    Version::from_str(&version).map_err(Error::from)
+}
+
+/// Replace the `<Version>` and/or `<VersionPrefix>` & `<VersionSuffix>` with `new_version`.
+///
+/// When using `<VersionPrefix>`, the pre-release part of `new_version` goes in `<VersionSuffix>`,
+/// which is added right after `<VersionPrefix>` if it doesn't exist yet.
+/// `path` is used for error reporting.
+pub(crate) fn set_version(
+    dry_run: DryRun,
+    mut content: String,
+    new_version: &str,
+    path: &Path,
+) -> Result<String, Error> {
+    let project = Project::parse(&content, path)?;
+    let mut replacements: Vec<(Range<usize>, String)> = Vec::with_capacity(3);
+    if let Some(version) = project.version {
+        replacements.push((version, new_version.to_string()));
+    }
+    if let Some(prefix) = project.version_prefix {
+        let (new_prefix, new_suffix) = new_version.split_once('-').unwrap_or((new_version, ""));
+        let prefix_end = prefix.end;
+        replacements.push((prefix, new_prefix.to_string()));
+        match project.version_suffix {
+            Some(ElementText::Text(range)) => replacements.push((range, new_suffix.to_string())),
+            Some(ElementText::Empty(range)) if !new_suffix.is_empty() => replacements.push((
+                range,
+                format!("<VersionSuffix>{new_suffix}</VersionSuffix>"),
+            )),
+            None if !new_suffix.is_empty() => {
+                let element_end = content
+                    .get(prefix_end..)
+                    .and_then(|rest| rest.find('>'))
+                    .map_or(prefix_end, |offset| prefix_end + offset + 1);
+                let indent = line_indent(&content, prefix_end);
+                replacements.push((
+                    element_end..element_end,
+                    format!("\n{indent}<VersionSuffix>{new_suffix}</VersionSuffix>"),
+                ));
+            }
+            _ => {}
+        }
+    }
+    if replacements.is_empty() {
+        return Err(Error::NoVersion(path.into()));
+    }
+
+    replacements.sort_by_key(|(range, _)| range.start);
+    for (range, replacement) in replacements.into_iter().rev() {
+        content.replace_range(range, &replacement);
+    }
+    fs::write(dry_run, new_version, path, &content)?;
+    Ok(content)
+}
+
+#[derive(Debug, Diagnostic, Error)]
+pub(crate) enum Error {
+    #[error("Could not parse {path} as XML: {source}")]
+    #[diagnostic(
+        code(msbuild::xml),
+        help("knope expects MSBuild project files to be valid XML."),
+        url("https://knope-dev.github.io/knope/config/packages.html#net-project-files")
+    )]
+    Xml {
+        path: PathBuf,
+        #[source]
+        source: quick_xml::Error,
+    },
+    #[error("No version found in {0}")]
+    #[diagnostic(
+        code(msbuild::no_version),
+        help(
+            "knope expects the project file to have a `<Version>` or `<VersionPrefix>` in a \
+            `<PropertyGroup>`."
+        ),
+        url("https://knope-dev.github.io/knope/config/packages.html#net-project-files")
+    )]
+    NoVersion(PathBuf),
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Fs(#[from] fs::Error),
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Semver(#[from] super::semver::version::Error),
+}
+
+/// Where the value of a property is in the original file.
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum ElementText {
+    /// The (trimmed) text content of an element like `<Version>1.2.3</Version>`
+    Text(Range<usize>),
+    /// An entire empty element, like `<VersionSuffix />`
+    Empty(Range<usize>),
+}
+
+impl ElementText {
+    fn text<'content>(&self, content: &'content str) -> &'content str {
+        match self {
+            Self::Text(range) => content.get(range.clone()).unwrap_or_default(),
+            Self::Empty(_) => "",
+        }
+    }
+}
+
+/// The version properties of an `MSBuild` project. The first definition of each property is used.
+#[derive(Debug, Default)]
+struct Project {
+    version: Option<Range<usize>>,
+    version_prefix: Option<Range<usize>>,
+    version_suffix: Option<ElementText>,
+}
+
+impl Project {
+    fn parse(content: &str, path: &Path) -> Result<Self, Error> {
+        let mut project = Self::default();
+        let mut reader = Reader::from_str(content);
+        let mut stack: Vec<Vec<u8>> = Vec::new();
+        // Elements with no text content (like `<Version></Version>`) never produce a Text event
+        let mut element_end = 0;
+        loop {
+            let start = reader.buffer_position();
+            let event = reader.read_event().map_err(|source| Error::Xml {
+                path: path.into(),
+                source,
+            })?;
+            match event {
+                Event::Start(element) => {
+                    stack.push(element.local_name().as_ref().to_vec());
+                    element_end = reader.buffer_position();
+                }
+                Event::End(_) => {
+                    if let Some(property) = stack.pop() {
+                        if is_property(&stack) {
+                            project.set_text(&property, element_end..element_end);
+                        }
+                    }
+                }
+                Event::Empty(element) => {
+                    if is_property(&stack) && element.local_name().as_ref() == b"VersionSuffix" {
+                        let range = start..reader.buffer_position();
+                        project
+                            .version_suffix
+                            .get_or_insert(ElementText::Empty(range));
+                    }
+                }
+                Event::Text(_) => {
+                    let end = reader.buffer_position();
+                    if let Some((property, parents)) = stack.split_last() {
+                        if is_property(parents) {
+                            project.set_text(property, trimmed(content, start..end));
+                        }
+                    }
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+        Ok(project)
+    }
+
+    /// Record the text of a property, unless that property was already defined.
+    fn set_text(&mut self, property: &[u8], range: Range<usize>) {
+        match property {
+            b"Version" => {
+                self.version.get_or_insert(range);
+            }
+            b"VersionPrefix" => {
+                self.version_prefix.get_or_insert(range);
+            }
+            b"VersionSuffix" => {
+                self.version_suffix.get_or_insert(ElementText::Text(range));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Whether elements with these parents are properties, like `<Project><PropertyGroup><Version>`.
+fn is_property(parents: &[Vec<u8>]) -> bool {
+    matches!(parents, [project, group] if project == b"Project" && group == b"PropertyGroup")
+}
+
+/// Shrink `range` to exclude any surrounding whitespace in `content`.
+fn trimmed(content: &str, range: Range<usize>) -> Range<usize> {
+    let raw = content.get(range.clone()).unwrap_or_default();
+    let start = range.start + (raw.len() - raw.trim_start().len());
+    let end = range.end - (raw.len() - raw.trim_end().len());
+    start..end.max(start)
+}
+
+/// The leading whitespace of the line containing `position`.
+fn line_indent(content: &str, position: usize) -> &str {
+    let before = content.get(..position).unwrap_or_default();
+    let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+    let line = before.get(line_start..).unwrap_or_default();
+    line.get(..line.len() - line.trim_start().len())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::dry_run::fake_dry_run;
+
+    const CSPROJ: &str = r#"<Project Sdk="Microsoft.NET.Sdk">
+
+  <PropertyGroup>
+    <TargetFramework>net8.0</TargetFramework>
+    <!-- <Version>9.9.9</Version> -->
+    <Version>1.2.3-rc.0</Version>
+  </PropertyGroup>
+
+  <ItemGroup>
+    <PackageReference Include="Newtonsoft.Json" Version="13.0.3" />
+  </ItemGroup>
+
+</Project>
+"#;
+
+    #[test]
+    fn test_get_version() {
+        assert_eq!(
+            get_version(CSPROJ, Path::new("")).unwrap(),
+            Version::from_str("1.2.3-rc.0").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_set_version() {
+        let new = set_version(
+            &mut fake_dry_run(),
+            CSPROJ.to_string(),
+            "1.2.3",
+            Path::new(""),
+        )
+        .unwrap();
+        assert_eq!(
+            new,
+            CSPROJ.replace("<Version>1.2.3-rc.0<", "<Version>1.2.3<")
+        );
+    }
+
+    const PROPS: &str = "<Project>\n  <PropertyGroup>\n    <VersionPrefix>1.0.0</VersionPrefix>\n    <Authors>Me</Authors>\n  </PropertyGroup>\n</Project>\n";
+
+    #[test]
+    fn version_prefix() {
+        assert_eq!(
+            get_version(PROPS, Path::new("")).unwrap(),
+            Version::new(1, 0, 0, None)
+        );
+        let new = set_version(
+            &mut fake_dry_run(),
+            PROPS.to_string(),
+            "1.1.0",
+            Path::new(""),
+        )
+        .unwrap();
+        assert_eq!(new, PROPS.replace("1.0.0", "1.1.0"));
+    }
+
+    #[test]
+    fn add_version_suffix() {
+        let new = set_version(
+            &mut fake_dry_run(),
+            PROPS.to_string(),
+            "2.0.0-rc.0",
+            Path::new(""),
+        )
+        .unwrap();
+        assert_eq!(
+            new,
+            PROPS.replace(
+                "<VersionPrefix>1.0.0</VersionPrefix>",
+                "<VersionPrefix>2.0.0</VersionPrefix>\n    <VersionSuffix>rc.0</VersionSuffix>"
+            )
+        );
+        assert_eq!(
+            get_version(&new, Path::new("")).unwrap(),
+            Version::from_str("2.0.0-rc.0").unwrap()
+        );
+
+        let stable = set_version(&mut fake_dry_run(), new, "2.0.0", Path::new("")).unwrap();
+        assert!(
+            stable.contains("<VersionSuffix></VersionSuffix>"),
+            "{stable}"
+        );
+        assert_eq!(
+            get_version(&stable, Path::new("")).unwrap(),
+            Version::new(2, 0, 0, None)
+        );
+    }
+
+    #[test]
+    fn empty_version_suffix() {
+        let props = "<Project><PropertyGroup><VersionPrefix>1.0.0</VersionPrefix><VersionSuffix /></PropertyGroup></Project>";
+        assert_eq!(
+            get_version(props, Path::new("")).unwrap(),
+            Version::new(1, 0, 0, None)
+        );
+        let new = set_version(
+            &mut fake_dry_run(),
+            props.to_string(),
+            "1.1.0-beta.1",
+            Path::new(""),
+        )
+        .unwrap();
+        assert_eq!(
+            new,
+            "<Project><PropertyGroup><VersionPrefix>1.1.0</VersionPrefix><VersionSuffix>beta.1</VersionSuffix></PropertyGroup></Project>"
+        );
+    }
+
+    #[test]
+    fn no_version() {
+        let result = get_version(
+            "<Project><PropertyGroup><Version /></PropertyGroup></Project>",
+            Path::new(""),
+        );
+        assert!(
+            matches!(result, Err(Error::NoVersion(_))),
+            "Expected NoVersion, got {result:?}"
+        );
+    }
+}
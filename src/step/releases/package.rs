@@ -4,26 +4,30 @@ use std::{
     fmt::Display,
     io::Write,
     ops::Deref,
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 
+use glob::{MatchOptions, Pattern};
 use indexmap::IndexMap;
 use itertools::Itertools;
 use miette::Diagnostic;
 use serde::{Deserialize, Serialize};
 
 use super::{
+    calver::CalVerFormat,
     changelog,
-    changelog::Changelog,
-    changesets::DEFAULT_CHANGESET_PACKAGE_NAME,
-    semver,
-    semver::{bump, ConventionalRule, Label, Version},
+    changelog::{Changelog, BREAKING_CHANGES_SECTION, FEATURES_SECTION, FIXES_SECTION},
+    changesets::{ChangeType, DEFAULT_CHANGESET_PACKAGE_NAME},
+    git::TagTemplate,
+    msbuild, semver,
+    semver::{BuildMetadataSource, ConventionalRule, Label, Version},
+    template::Template,
     versioned_file,
-    versioned_file::{VersionedFile, PACKAGE_FORMAT_FILE_NAMES},
-    Change, Release, Rule,
+    versioned_file::{PackageFormat, VersionedFile, PACKAGE_FORMAT_FILE_NAMES},
+    Change, DateFormat, Release, Rule,
 };
 use crate::{
-    config::{ChangeLogSectionName, CommitFooter, CustomChangeType},
+    config::{ChangeLogSectionName, CommitFooter, CustomChangeType, GitHub},
     dry_run::DryRun,
     fs,
     integrations::git::{self, add_files},
@@ -36,26 +40,167 @@ pub(crate) struct Package {
     pub(crate) changelog: Option<Changelog>,
     pub(crate) name: Option<PackageName>,
     pub(crate) scopes: Option<Vec<String>>,
+    /// Only commits which change a file matching one of these count toward a release
+    pub(crate) paths: Option<Vec<Pattern>>,
     pub(crate) extra_changelog_sections: IndexMap<ChangelogSectionSource, ChangeLogSectionName>,
+    /// How commits of each conventional commit type affect releases, overriding `feat` and `fix`
+    pub(crate) commit_types: IndexMap<String, CommitTypeRule>,
+    /// What to add to the changelog entries of commits, besides their summary
+    pub(crate) commit_details: CommitDetails,
+    /// Renders new changelog entries instead of the built-in format
+    pub(crate) changelog_template: Option<Template>,
+    /// How dates are written in release titles, if not the default
+    pub(crate) date_format: Option<DateFormat>,
+    /// Whether to link each version in the changelog to its changes on GitHub
+    pub(crate) compare_links: bool,
     pub(crate) pending_changes: Vec<Change>,
     pub(crate) prepared_release: Option<Release>,
     /// Version manually set by the caller to use instead of the one determined by semantic rule
     pub(crate) override_version: Option<Version>,
     pub(crate) assets: Option<Vec<Asset>>,
+    /// Build metadata to attach to every new version
+    pub(crate) build_metadata: Option<BuildMetadataSource>,
+    pub(crate) versioning: Versioning,
+    /// How the Git tags for releases are named, if not the default
+    pub(crate) tag_template: Option<TagTemplate>,
+}
+
+pub(crate) const REVERT_COMMIT_TYPE: &str = "revert";
+const REVERTS_SECTION: &str = "Reverts";
+
+/// How commits of a conventional commit type affect a release, from `[package.commit_types]`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub(crate) struct CommitTypeRule {
+    pub(crate) bump: CommitTypeBump,
+    /// The changelog section for these commits, if not the one matching `bump`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) section: Option<ChangeLogSectionName>,
+}
+
+impl CommitTypeRule {
+    /// The changelog section for commits of this type, if they should be in the changelog.
+    pub(crate) fn section(&self) -> Option<ChangeLogSectionName> {
+        self.section.clone().or_else(|| match self.bump {
+            CommitTypeBump::Major => Some(ChangeLogSectionName::from(BREAKING_CHANGES_SECTION)),
+            CommitTypeBump::Minor => Some(ChangeLogSectionName::from(FEATURES_SECTION)),
+            CommitTypeBump::Patch => Some(ChangeLogSectionName::from(FIXES_SECTION)),
+            CommitTypeBump::None => None,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum CommitTypeBump {
+    Major,
+    Minor,
+    Patch,
+    /// Listed in the changelog (if there's a `section`), but doesn't cause a release
+    None,
+}
+
+impl CommitTypeBump {
+    fn rule(self) -> Option<ConventionalRule> {
+        match self {
+            Self::Major => Some(ConventionalRule::Major),
+            Self::Minor => Some(ConventionalRule::Minor),
+            Self::Patch => Some(ConventionalRule::Patch),
+            Self::None => None,
+        }
+    }
+}
+
+/// What to add to the changelog entry of each commit, from `[package.commit_details]`.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default)]
+pub(crate) struct CommitDetails {
+    /// Add the body of the commit as a paragraph below its summary
+    pub(crate) body: bool,
+    /// Add the name of the author of the commit
+    pub(crate) author: bool,
+    pub(crate) hash: CommitHash,
+}
+
+/// How the ID of a commit is added to its changelog entry.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum CommitHash {
+    #[default]
+    None,
+    /// The short ID of the commit
+    Short,
+    /// The short ID of the commit, linked to the commit on GitHub
+    Link,
+}
+
+/// How the next version of a package is determined.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) enum Versioning {
+    /// Semantic versioning, bumped according to the changes since the last release
+    SemVer {
+        /// Bump the minor version for breaking changes and the patch version for features
+        /// while the major version is 0
+        pre_1_0_convention: bool,
+    },
+    /// Calendar versioning, based on the date of the release
+    CalVer(CalVerFormat),
+}
+
+impl Default for Versioning {
+    fn default() -> Self {
+        Self::SemVer {
+            pre_1_0_convention: true,
+        }
+    }
 }
 
 impl Package {
-    fn bump_rule(&self, verbose: Verbose) -> ConventionalRule {
+    /// Whether `path` (relative to the repository root) matches one of the package's `paths`.
+    ///
+    /// Packages without `paths` don't match any path.
+    pub(crate) fn contains_path(&self, path: &str) -> bool {
+        let options = MatchOptions {
+            require_literal_separator: true,
+            ..MatchOptions::default()
+        };
+        self.paths
+            .iter()
+            .flatten()
+            .any(|pattern| pattern.matches_with(path, options))
+    }
+
+    /// The template for the Git tags of this package's releases.
+    pub(crate) fn tag_template(&self) -> Cow<TagTemplate> {
+        self.tag_template.as_ref().map_or_else(
+            || Cow::Owned(TagTemplate::default_for(self.name.as_ref())),
+            Cow::Borrowed,
+        )
+    }
+
+    /// Whether the versions of this package are written in PEP 440 format, because it's a Python
+    /// package.
+    pub(crate) fn uses_pep440(&self) -> bool {
+        self.versioned_files
+            .iter()
+            .any(|versioned_file| versioned_file.format == PackageFormat::Poetry)
+    }
+
+    pub(crate) fn bump_rule(&self, verbose: Verbose) -> ConventionalRule {
         self.pending_changes
             .iter()
-            .map(|change| {
-                let rule = change.change_type().into();
+            .filter_map(|change| {
+                let rule = self.change_rule(change);
                 let change_source = match change {
                     Change::ConventionalCommit(_) => "commit",
                     Change::ChangeSet(_) => "changeset",
+                    Change::DependenciesUpdated(_) => "dependency",
+                    Change::ReleasedWithGroup(_) => "group",
                 };
                 if let Verbose::Yes = verbose {
-                    println!("{change_source} {change}\n\timplies rule {rule}");
+                    match rule {
+                        Some(rule) => println!("{change_source} {change}\n\timplies rule {rule}"),
+                        None => println!("{change_source} {change}\n\timplies no rule"),
+                    }
                 }
                 rule
             })
@@ -63,53 +208,112 @@ impl Package {
             .unwrap_or_default()
     }
 
-    pub(crate) fn write_release(
-        mut self,
-        prerelease_label: &Option<Label>,
-        dry_run: DryRun,
-        verbose: Verbose,
-    ) -> Result<Self, Error> {
-        if self.pending_changes.is_empty() {
-            return Ok(self);
+    /// How commits of `commit_type` affect releases, if they're configured or have a built-in rule.
+    ///
+    /// Only `revert` commits have a built-in rule: a patch in the "Reverts" section.
+    pub(crate) fn commit_type_rule(&self, commit_type: &str) -> Option<Cow<CommitTypeRule>> {
+        self.commit_types
+            .get(commit_type)
+            .map(Cow::Borrowed)
+            .or_else(|| {
+                (commit_type == REVERT_COMMIT_TYPE).then(|| {
+                    Cow::Owned(CommitTypeRule {
+                        bump: CommitTypeBump::Patch,
+                        section: Some(ChangeLogSectionName::from(REVERTS_SECTION)),
+                    })
+                })
+            })
+    }
+
+    /// Whether any pending change should cause a release on its own.
+    pub(crate) fn has_releasable_changes(&self) -> bool {
+        self.pending_changes
+            .iter()
+            .any(|change| self.change_rule(change).is_some())
+    }
+
+    /// The bump needed for `change`, if any.
+    fn change_rule(&self, change: &Change) -> Option<ConventionalRule> {
+        match change.change_type() {
+            ChangeType::Custom(ChangelogSectionSource::CommitType(commit_type)) => self
+                .commit_type_rule(&commit_type)
+                .and_then(|rule| rule.bump.rule()),
+            change_type => Some(change_type.into()),
         }
+    }
+
+    /// The changelog section for changes from `source`, if they belong in the changelog.
+    pub(crate) fn changelog_section(
+        &self,
+        source: &ChangelogSectionSource,
+    ) -> Option<ChangeLogSectionName> {
+        match source {
+            ChangelogSectionSource::CommitType(commit_type) => self
+                .commit_type_rule(commit_type)
+                .and_then(|rule| rule.section()),
+            _ => self.extra_changelog_sections.get(source).cloned(),
+        }
+    }
 
+    /// Determine the version for a release with changes implying `bump_rule` (or the rule implied
+    /// by the pending changes if not set), unless a version was set manually.
+    pub(crate) fn next_release_version(
+        &mut self,
+        prerelease_label: &Option<Label>,
+        bump_rule: Option<ConventionalRule>,
+        verbose: Verbose,
+    ) -> Result<Version, Error> {
         if let Verbose::Yes = verbose {
             if let Some(package_name) = &self.name {
                 println!("Determining new version for {package_name}");
             }
         }
 
-        let new_version = if let Some(override_version) = self.override_version.take() {
+        if let Some(override_version) = self.override_version.take() {
             if let Verbose::Yes = verbose {
                 println!("Using overridden version {override_version}");
             }
-            override_version
+            return Ok(override_version);
+        }
+        let versions = self.get_version(verbose)?;
+        let bump_rule = bump_rule.unwrap_or_else(|| self.bump_rule(verbose));
+        let rule = Rule::from_changes(bump_rule, prerelease_label);
+        self.next_version(versions, &rule, verbose)
+            .map_err(Error::from)
+    }
+
+    /// Write `new_version` to the versioned files and a new entry for all pending changes to the
+    /// changelog, then stage the changes in Git.
+    pub(crate) fn write_release(
+        mut self,
+        new_version: Version,
+        github: Option<&GitHub>,
+        dry_run: DryRun,
+    ) -> Result<Self, Error> {
+        let new_version = self.add_build_metadata(new_version)?;
+        // Templates and compare links refer to the previous version, which is gone once the new
+        // one is written
+        let previous_version = if self.changelog_template.is_some() || self.compare_links {
+            self.version_from_files(Verbose::No)?
         } else {
-            let versions = self.get_version(verbose)?;
-            let bump_rule = self.bump_rule(verbose);
-            let rule = if let Some(pre_label) = prerelease_label {
-                Rule::Pre {
-                    label: pre_label.clone(),
-                    stable_rule: bump_rule,
-                }
-            } else {
-                bump_rule.into()
-            };
-            bump(versions, &rule, verbose)?
+            None
         };
 
         self = self.write_version(&new_version, dry_run)?;
-        self.prepared_release = Some(self.write_changelog(new_version, dry_run)?);
+        self.prepared_release =
+            Some(self.write_changelog(new_version, previous_version, github, dry_run)?);
         self.stage_changes_to_git(dry_run)?;
 
         Ok(self)
     }
+
     fn stage_changes_to_git(&self, dry_run: DryRun) -> Result<(), Error> {
         let changeset_path = PathBuf::from(".changeset");
         let paths = self
             .versioned_files
             .iter()
-            .map(|versioned_file| versioned_file.path.clone())
+            .flat_map(VersionedFile::paths)
+            .unique()
             .chain(
                 self.changelog
                     .as_ref()
@@ -222,6 +426,8 @@ pub(crate) struct AssetNameError {
 pub(crate) enum ChangelogSectionSource {
     CommitFooter(CommitFooter),
     CustomChangeType(CustomChangeType),
+    /// A conventional commit type configured in `[package.commit_types]`
+    CommitType(String),
 }
 
 impl From<CommitFooter> for ChangelogSectionSource {
@@ -241,6 +447,7 @@ impl Display for ChangelogSectionSource {
         match self {
             Self::CommitFooter(footer) => footer.fmt(f),
             Self::CustomChangeType(change_type) => change_type.fmt(f),
+            Self::CommitType(commit_type) => commit_type.fmt(f),
         }
     }
 }
@@ -255,8 +462,9 @@ pub(crate) fn find_packages() -> Result<Package, Error> {
 
     let versioned_files = PACKAGE_FORMAT_FILE_NAMES
         .iter()
-        .filter_map(|name| {
-            let path = PathBuf::from(name);
+        .map(PathBuf::from)
+        .chain(msbuild::single_project_file(Path::new(".")))
+        .filter_map(|path| {
             if path.exists() {
                 Some(VersionedFile::try_from(path))
             } else {
@@ -325,7 +533,15 @@ pub(crate) fn suggested_package_toml() -> Result<String, Error> {
             "No supported package managers found in current directory. \
                     The supported formats are {formats}. Here's how you might define a package for `Cargo.toml`:\
                     \n\n```\n[package]\nversioned_files = [\"Cargo.toml\"]\nchangelog = \"CHANGELOG.md\"\n```",
-            formats = PACKAGE_FORMAT_FILE_NAMES.join(", ")
+            formats = PACKAGE_FORMAT_FILE_NAMES
+                .iter()
+                .map(ToString::to_string)
+                .chain(
+                    msbuild::PROJECT_EXTENSIONS
+                        .iter()
+                        .map(|extension| format!("*.{extension}"))
+                )
+                .join(", ")
         ))
     } else {
         let toml = crate::config::toml::Package::from(package);
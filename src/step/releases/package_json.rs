@@ -11,7 +11,10 @@ use thiserror::Error;
 use crate::{
     dry_run::DryRun,
     fs,
-    step::{releases, releases::semver::Version},
+    step::{
+        releases,
+        releases::{dependencies::update_requirement, semver::Version},
+    },
 };
 
 pub(crate) fn get_version(content: &str, path: &Path) -> Result<Version, Error> {
@@ -48,6 +51,78 @@ pub(crate) fn set_version(
     Ok(contents)
 }
 
+/// The `name` of the package defined in a `package.json` file's content, if any.
+pub(crate) fn package_name(content: &str) -> Option<String> {
+    serde_json::from_str::<Map<String, Value>>(content)
+        .ok()?
+        .get("name")?
+        .as_str()
+        .map(String::from)
+}
+
+/// The names of every package that a `package.json` file's content depends on, not counting
+/// `devDependencies`.
+pub(crate) fn dependencies(content: &str) -> Vec<String> {
+    let Ok(json) = serde_json::from_str::<Map<String, Value>>(content) else {
+        return Vec::new();
+    };
+    DEPENDENCY_KEYS
+        .iter()
+        .filter_map(|key| json.get(*key)?.as_object())
+        .flat_map(|dependencies| dependencies.keys().cloned())
+        .collect()
+}
+
+/// Set the requirement on the package named `dependency` to `new_version`, keeping its operator.
+pub(crate) fn set_dependency_version(
+    dry_run: DryRun,
+    package_json: String,
+    dependency: &str,
+    new_version: &str,
+    path: &Path,
+) -> Result<String, Error> {
+    let mut json = serde_json::from_str::<Map<String, Value>>(&package_json).map_err(|source| {
+        Error::Deserialize {
+            path: path.into(),
+            source,
+        }
+    })?;
+    let mut changed = false;
+    for key in DEPENDENCY_KEYS {
+        let Some(requirement) = json
+            .get_mut(key)
+            .and_then(Value::as_object_mut)
+            .and_then(|dependencies| dependencies.get_mut(dependency))
+        else {
+            continue;
+        };
+        if let Some(new_requirement) = requirement
+            .as_str()
+            .and_then(|requirement| update_requirement(requirement, new_version))
+        {
+            *requirement = Value::String(new_requirement);
+            changed = true;
+        }
+    }
+    if !changed {
+        return Ok(package_json);
+    }
+    let contents = serde_json::to_string_pretty(&json).map_err(|source| Error::Serialize {
+        path: path.into(),
+        source,
+    })?;
+    fs::write(
+        dry_run,
+        &format!("{dependency} {new_version}"),
+        path,
+        &contents,
+    )?;
+    Ok(contents)
+}
+
+/// The keys of dependencies which are needed by users of the package.
+const DEPENDENCY_KEYS: [&str; 3] = ["dependencies", "peerDependencies", "optionalDependencies"];
+
 #[derive(Debug, Diagnostic, Error)]
 pub(crate) enum Error {
     #[error("Error deserializing {path}: {source}")]
@@ -139,4 +214,46 @@ mod tests {
         .to_string();
         assert_eq!(new, expected);
     }
+
+    #[test]
+    fn dependencies() {
+        let content = r#"{
+        "name": "app",
+        "version": "1.0.0",
+        "dependencies": {"core": "^1.2.3", "left-pad": "*"},
+        "peerDependencies": {"core": "~1.2.3"},
+        "devDependencies": {"core": "1.2.3"}
+        }"#;
+
+        assert_eq!(package_name(content).as_deref(), Some("app"));
+        assert_eq!(
+            super::dependencies(content),
+            vec!["core", "left-pad", "core"]
+        );
+
+        let new = set_dependency_version(
+            &mut fake_dry_run(),
+            content.to_string(),
+            "core",
+            "2.0.0",
+            Path::new(""),
+        )
+        .unwrap();
+
+        let expected = r#"{
+  "name": "app",
+  "version": "1.0.0",
+  "dependencies": {
+    "core": "^2.0.0",
+    "left-pad": "*"
+  },
+  "peerDependencies": {
+    "core": "~2.0.0"
+  },
+  "devDependencies": {
+    "core": "1.2.3"
+  }
+}"#;
+        assert_eq!(new, expected);
+    }
 }
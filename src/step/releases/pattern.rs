@@ -0,0 +1,229 @@
+use std::{
+    fmt,
+    fmt::{Display, Formatter},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use itertools::Itertools;
+use miette::Diagnostic;
+use regex::Regex;
+use thiserror::Error;
+
+use super::semver::Version;
+use crate::{dry_run::DryRun, fs};
+
+/// A regular expression with a named `version` capture group, used to find the version in a file
+/// that doesn't have a natively supported format.
+#[derive(Clone, Debug)]
+pub(crate) struct VersionPattern(Regex);
+
+const CAPTURE_NAME: &str = "version";
+
+impl FromStr for VersionPattern {
+    type Err = Error;
+
+    fn from_str(pattern: &str) -> Result<Self, Self::Err> {
+        let regex = Regex::new(pattern).map_err(|source| Error::InvalidPattern {
+            pattern: pattern.to_string(),
+            source,
+        })?;
+        if regex.capture_names().flatten().contains(&CAPTURE_NAME) {
+            Ok(Self(regex))
+        } else {
+            Err(Error::MissingCapture(pattern.to_string()))
+        }
+    }
+}
+
+impl PartialEq for VersionPattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.as_str() == other.0.as_str()
+    }
+}
+
+impl Eq for VersionPattern {}
+
+impl Display for VersionPattern {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.as_str())
+    }
+}
+
+impl VersionPattern {
+    /// Get the version from every match of the pattern in `content`, which must all be the same.
+    ///
+    /// `path` is used for error reporting.
+    pub(crate) fn get_version(&self, content: &str, path: &Path) -> Result<Version, Error> {
+        let mut versions = self
+            .0
+            .captures_iter(content)
+            .filter_map(|captures| captures.name(CAPTURE_NAME))
+            .map(|version| version.as_str())
+            .unique();
+        let version = versions.next().ok_or_else(|| Error::NoMatch {
+            pattern: self.to_string(),
+            path: path.into(),
+        })?;
+        if let Some(other) = versions.next() {
+            return Err(Error::InconsistentVersions {
+                first: version.to_string(),
+                second: other.to_string(),
+                path: path.into(),
+            });
+        }
+        Version::from_str(version).map_err(Error::from)
+    }
+
+    /// Replace the `version` capture of every match in `content` with `new_version`.
+    ///
+    /// `path` is used for error reporting.
+    pub(crate) fn set_version(
+        &self,
+        dry_run: DryRun,
+        mut content: String,
+        new_version: &str,
+        path: &Path,
+    ) -> Result<String, Error> {
+        let ranges = self
+            .0
+            .captures_iter(&content)
+            .filter_map(|captures| captures.name(CAPTURE_NAME))
+            .map(|version| version.range())
+            .collect_vec();
+        if ranges.is_empty() {
+            return Err(Error::NoMatch {
+                pattern: self.to_string(),
+                path: path.into(),
+            });
+        }
+        for range in ranges.into_iter().rev() {
+            content.replace_range(range, new_version);
+        }
+        fs::write(dry_run, new_version, path, &content)?;
+        Ok(content)
+    }
+}
+
+#[derive(Debug, Diagnostic, Error)]
+pub(crate) enum Error {
+    #[error("Invalid pattern {pattern}: {source}")]
+    #[diagnostic(
+        code(pattern::invalid),
+        help("The pattern must be a valid regular expression."),
+        url("https://knope-dev.github.io/knope/config/packages.html#other-file-formats")
+    )]
+    InvalidPattern {
+        pattern: String,
+        #[source]
+        source: regex::Error,
+    },
+    #[error("The pattern {0} has no `version` capture group")]
+    #[diagnostic(
+        code(pattern::missing_capture),
+        help("Mark the version in the pattern with a named capture group, like `(?<version>.+)`."),
+        url("https://knope-dev.github.io/knope/config/packages.html#other-file-formats")
+    )]
+    MissingCapture(String),
+    #[error("The pattern {pattern} did not match anything in {path}")]
+    #[diagnostic(
+        code(pattern::no_match),
+        help("Make sure the pattern matches the current version in the file."),
+        url("https://knope-dev.github.io/knope/config/packages.html#other-file-formats")
+    )]
+    NoMatch { pattern: String, path: PathBuf },
+    #[error("Found conflicting versions {first} and {second} in {path}")]
+    #[diagnostic(
+        code(pattern::inconsistent),
+        help("Every match of the pattern in a file must contain the same version."),
+        url("https://knope-dev.github.io/knope/config/packages.html#other-file-formats")
+    )]
+    InconsistentVersions {
+        first: String,
+        second: String,
+        path: PathBuf,
+    },
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Fs(#[from] fs::Error),
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Semver(#[from] super::semver::version::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::dry_run::fake_dry_run;
+
+    #[test]
+    fn test_get_version() {
+        let pattern = VersionPattern::from_str(r#"__version__ = "(?<version>[^"]+)""#).unwrap();
+        let content = "\"\"\"A package\"\"\"\n__version__ = \"1.2.3-rc.0\"\n";
+        assert_eq!(
+            pattern.get_version(content, Path::new("")).unwrap(),
+            Version::from_str("1.2.3-rc.0").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_set_version() {
+        let pattern = VersionPattern::from_str(r"badge/version-(?<version>[^-]+)-blue").unwrap();
+        let content =
+            "![badge/version-1.0.0-blue](https://img.shields.io/badge/version-1.0.0-blue)";
+        let new = pattern
+            .set_version(
+                &mut fake_dry_run(),
+                content.to_string(),
+                "1.1.0",
+                Path::new(""),
+            )
+            .unwrap();
+        assert_eq!(new, content.replace("1.0.0", "1.1.0"));
+    }
+
+    #[test]
+    fn whole_file() {
+        let pattern = VersionPattern::from_str(r"^(?<version>\S+)").unwrap();
+        let new = pattern
+            .set_version(
+                &mut fake_dry_run(),
+                "0.1.0\n".to_string(),
+                "0.2.0",
+                Path::new(""),
+            )
+            .unwrap();
+        assert_eq!(new, "0.2.0\n");
+    }
+
+    #[test]
+    fn missing_capture() {
+        let result = VersionPattern::from_str(r"version: (\S+)");
+        assert!(
+            matches!(result, Err(Error::MissingCapture(_))),
+            "Expected MissingCapture, got {result:?}"
+        );
+    }
+
+    #[test]
+    fn inconsistent_versions() {
+        let pattern = VersionPattern::from_str(r"v(?<version>\d+\.\d+\.\d+)").unwrap();
+        let result = pattern.get_version("v1.0.0 and v1.0.1", Path::new(""));
+        assert!(
+            matches!(result, Err(Error::InconsistentVersions { .. })),
+            "Expected InconsistentVersions, got {result:?}"
+        );
+    }
+
+    #[test]
+    fn no_match() {
+        let pattern = VersionPattern::from_str(r"v(?<version>\d+\.\d+\.\d+)").unwrap();
+        let result = pattern.get_version("nothing here", Path::new(""));
+        assert!(
+            matches!(result, Err(Error::NoMatch { .. })),
+            "Expected NoMatch, got {result:?}"
+        );
+    }
+}
@@ -0,0 +1,288 @@
+//! Conversion between [PEP 440](https://peps.python.org/pep-0440/) versions, used by Python
+//! packages, and knope's semantic [`Version`].
+//!
+//! Pre-releases map to [`Prerelease`]s (`1.2.3rc1` is `1.2.3-rc.1`), developmental releases use the
+//! `dev` label (`1.2.3.dev4` is `1.2.3-dev.4`), and post-releases and local versions are kept as
+//! build metadata (`1.2.3.post1+abc` is `1.2.3+post.1.abc`), so they don't affect bumping.
+
+use std::str::FromStr;
+
+use itertools::Itertools;
+use miette::Diagnostic;
+use thiserror::Error;
+
+use super::semver::{BuildMetadata, Label, PreVersion, Prerelease, StableVersion, Version};
+
+const SEPARATORS: [char; 3] = ['-', '_', '.'];
+/// Pre-release spellings allowed by PEP 440, longest first so prefixes don't shadow them.
+const PRE_LABELS: [(&str, &str); 8] = [
+    ("preview", "rc"),
+    ("alpha", "alpha"),
+    ("beta", "beta"),
+    ("pre", "rc"),
+    ("rc", "rc"),
+    ("a", "alpha"),
+    ("b", "beta"),
+    ("c", "rc"),
+];
+const POST_LABELS: [&str; 3] = ["post", "rev", "r"];
+const DEV_LABEL: &str = "dev";
+const POST_BUILD: &str = "post";
+
+/// Parse a PEP 440 version (or a semantic version, which is usually valid PEP 440 as well).
+pub(crate) fn parse(version: &str) -> Result<Version, Error> {
+    let invalid = || Error::Invalid(version.to_string());
+    let normalized = version.trim().to_ascii_lowercase();
+    let normalized = normalized.strip_prefix('v').unwrap_or(&normalized);
+    if normalized.contains('!') {
+        return Err(Error::Epoch(version.to_string()));
+    }
+    let (public, local) = match normalized.split_once('+') {
+        Some((public, local)) => (public, Some(local)),
+        None => (normalized, None),
+    };
+
+    let release_end = public
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(public.len());
+    let (release, mut rest) = public.split_at(release_end);
+    let release = release.strip_suffix('.').unwrap_or(release);
+    let stable = parse_release(release).ok_or_else(invalid)?;
+
+    let pre = take_segment(&mut rest, PRE_LABELS.iter().map(|(spelling, _)| *spelling)).map(
+        |(spelling, number)| {
+            let label = PRE_LABELS
+                .iter()
+                .find_map(|(known, label)| (*known == spelling).then_some(*label))
+                .unwrap_or(spelling);
+            Prerelease::new(Label::from(label), number)
+        },
+    );
+    let post = take_implicit_post(&mut rest)
+        .or_else(|| take_segment(&mut rest, POST_LABELS).map(|(_, number)| number));
+    let dev = take_segment(&mut rest, [DEV_LABEL])
+        .map(|(_, number)| Prerelease::new(Label::from(DEV_LABEL), number));
+    if !rest.is_empty() {
+        return Err(invalid());
+    }
+
+    let pre_component = match (pre, dev) {
+        (Some(_), Some(_)) => return Err(Error::Unsupported(version.to_string())),
+        (pre, dev) => pre.or(dev),
+    };
+    if pre_component.is_some() && post.is_some() {
+        return Err(Error::Unsupported(version.to_string()));
+    }
+
+    let build = post
+        .map(|post| format!("{POST_BUILD}.{post}"))
+        .into_iter()
+        .chain(local.map(|local| local.replace(SEPARATORS, ".")))
+        .reduce(|post, local| format!("{post}.{local}"))
+        .map(|build| BuildMetadata::from_str(&build))
+        .transpose()
+        .map_err(|_| invalid())?;
+
+    let version = match pre_component {
+        Some(pre_component) => Version::from(PreVersion {
+            stable_component: stable,
+            pre_component,
+        }),
+        None => Version::from(stable),
+    };
+    Ok(version.with_build(build))
+}
+
+/// Format `version` as a normalized PEP 440 version, like `1.2.3rc1`.
+pub(crate) fn format(version: &Version) -> Result<String, Error> {
+    let mut formatted = version.stable_component().to_string();
+    if let Version::Pre(PreVersion { pre_component, .. }, _) = version {
+        let label = match pre_component.label.0.as_str() {
+            "alpha" | "a" => "a",
+            "beta" | "b" => "b",
+            "rc" | "c" => "rc",
+            DEV_LABEL => ".dev",
+            _ => return Err(Error::UnsupportedLabel(pre_component.label.clone())),
+        };
+        formatted.push_str(label);
+        formatted.push_str(&pre_component.version.to_string());
+    }
+    if let Some(build) = version.build() {
+        let identifiers = build.as_str().split('.').collect_vec();
+        let local = match identifiers.as_slice() {
+            [POST_BUILD, post, local @ ..] if post.parse::<u64>().is_ok() => {
+                formatted.push_str(".post");
+                formatted.push_str(post);
+                local
+            }
+            local => local,
+        };
+        if !local.is_empty() {
+            formatted.push('+');
+            formatted.push_str(&local.join("."));
+        }
+    }
+    Ok(formatted)
+}
+
+/// Parse the release segment (like `1.2.3`), which may have fewer than three components.
+fn parse_release(release: &str) -> Option<StableVersion> {
+    let components = release
+        .split('.')
+        .map(|component| component.parse::<u64>().ok())
+        .collect::<Option<Vec<_>>>()?;
+    match components.as_slice() {
+        [major] => Some((*major, 0, 0)),
+        [major, minor] => Some((*major, *minor, 0)),
+        [major, minor, patch] => Some((*major, *minor, *patch)),
+        _ => None,
+    }
+    .map(|(major, minor, patch)| StableVersion {
+        major,
+        minor,
+        patch,
+    })
+}
+
+/// Take a segment like `.post1`, `-rc.2`, or `dev` from the start of `rest`, if it's there.
+///
+/// Returns which of `labels` was found and the number (`0` if it's implicit).
+fn take_segment<'label>(
+    rest: &mut &str,
+    labels: impl IntoIterator<Item = &'label str>,
+) -> Option<(&'label str, u64)> {
+    let without_separator = rest.strip_prefix(SEPARATORS).unwrap_or(rest);
+    let (label, after_label) = labels.into_iter().find_map(|label| {
+        without_separator
+            .strip_prefix(label)
+            .map(|after_label| (label, after_label))
+    })?;
+    let after_separator = after_label.strip_prefix(SEPARATORS).unwrap_or(after_label);
+    let digits_end = after_separator
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(after_separator.len());
+    let (digits, remaining) = after_separator.split_at(digits_end);
+    let number = if digits.is_empty() {
+        *rest = after_label;
+        0
+    } else {
+        *rest = remaining;
+        digits.parse().ok()?
+    };
+    Some((label, number))
+}
+
+/// Take an implicit post-release like `-1` from the start of `rest`, if it's there.
+fn take_implicit_post(rest: &mut &str) -> Option<u64> {
+    let digits = rest.strip_prefix('-')?;
+    let digits_end = digits
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(digits.len());
+    let (digits, remaining) = digits.split_at(digits_end);
+    let post = digits.parse().ok()?;
+    *rest = remaining;
+    Some(post)
+}
+
+#[derive(Debug, Diagnostic, Error)]
+pub(crate) enum Error {
+    #[error("{0} is not a valid PEP 440 version")]
+    #[diagnostic(
+        code(pep440::invalid),
+        help("Python versions must follow PEP 440, like `1.2.3`, `1.2.3rc1`, or `1.2.3.post1`."),
+        url("https://peps.python.org/pep-0440/")
+    )]
+    Invalid(String),
+    #[error("Version epochs are not supported, found {0}")]
+    #[diagnostic(
+        code(pep440::epoch),
+        help("knope can't bump versions with an epoch (like `1!2.0.0`), please open an issue if you need this."),
+        url("https://knope-dev.github.io/knope/config/packages.html#python-versions")
+    )]
+    Epoch(String),
+    #[error("Version {0} combines segments knope doesn't support together")]
+    #[diagnostic(
+        code(pep440::unsupported),
+        help(
+            "knope supports one of a pre-release (`rc1`), a post-release (`.post1`), or a \
+            developmental release (`.dev1`) at a time."
+        ),
+        url("https://knope-dev.github.io/knope/config/packages.html#python-versions")
+    )]
+    Unsupported(String),
+    #[error("Pre-release label {0} can't be used in a Python version")]
+    #[diagnostic(
+        code(pep440::unsupported_label),
+        help("Python pre-releases must use the `alpha`, `beta`, `rc`, or `dev` label."),
+        url("https://knope-dev.github.io/knope/config/packages.html#python-versions")
+    )]
+    UnsupportedLabel(Label),
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn parse_versions() {
+        for (pep440, semver) in [
+            ("1.2.3", "1.2.3"),
+            ("1.2", "1.2.0"),
+            ("1.2.3rc1", "1.2.3-rc.1"),
+            ("1.2.3-rc.1", "1.2.3-rc.1"),
+            ("1.2.3a1", "1.2.3-alpha.1"),
+            ("1.2.3.beta2", "1.2.3-beta.2"),
+            ("1.2.3c", "1.2.3-rc.0"),
+            ("1.2.3.post1", "1.2.3+post.1"),
+            ("1.2.3-2", "1.2.3+post.2"),
+            ("1.2.3.dev4", "1.2.3-dev.4"),
+            ("1.2.3+ubuntu-1", "1.2.3+ubuntu.1"),
+            ("1.2.3.post1+abc", "1.2.3+post.1.abc"),
+            ("v1.2.3RC1", "1.2.3-rc.1"),
+        ] {
+            let version = parse(pep440).unwrap();
+            assert_eq!(version.to_string(), semver, "parsing {pep440}");
+        }
+    }
+
+    #[test]
+    fn format_versions() {
+        for (semver, pep440) in [
+            ("1.2.3", "1.2.3"),
+            ("1.2.3-rc.1", "1.2.3rc1"),
+            ("1.2.3-alpha.0", "1.2.3a0"),
+            ("1.2.3-beta.2", "1.2.3b2"),
+            ("1.2.3-dev.4", "1.2.3.dev4"),
+            ("1.2.3+post.1", "1.2.3.post1"),
+            ("1.2.3+post.1.abc", "1.2.3.post1+abc"),
+            ("1.2.3+a1b2c3d", "1.2.3+a1b2c3d"),
+        ] {
+            let version = Version::from_str(semver).unwrap();
+            assert_eq!(format(&version).unwrap(), pep440, "formatting {semver}");
+        }
+    }
+
+    #[test]
+    fn invalid_versions() {
+        for version in ["", "1.2.3.4", "1.2.3foo", "1.2.3rc1rc2", "abc"] {
+            let result = parse(version);
+            assert!(
+                matches!(result, Err(Error::Invalid(_))),
+                "Expected {version} to be invalid, got {result:?}"
+            );
+        }
+        assert!(matches!(parse("1!2.0.0"), Err(Error::Epoch(_))));
+        assert!(matches!(parse("1.2.3rc1.dev1"), Err(Error::Unsupported(_))));
+    }
+
+    #[test]
+    fn unsupported_label() {
+        let result = format(&Version::from_str("1.2.3-preview.1").unwrap());
+        assert!(
+            matches!(result, Err(Error::UnsupportedLabel(_))),
+            "Expected UnsupportedLabel, got {result:?}"
+        );
+    }
+}
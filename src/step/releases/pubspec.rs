@@ -0,0 +1,160 @@
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use miette::Diagnostic;
+use thiserror::Error;
+
+use super::{
+    semver::{BuildMetadata, Version},
+    yaml,
+};
+use crate::{dry_run::DryRun, fs};
+
+/// Get the `version` from a Dart `pubspec.yaml`, including any build number (like `+45`).
+///
+/// `path` is used for error reporting.
+pub(crate) fn get_version(content: &str, path: &Path) -> Result<Version, Error> {
+    let range =
+        yaml::top_level_scalar(content, "version").ok_or_else(|| Error::NoVersion(path.into()))?;
+    Version::from_str(content.get(range).unwrap_or_default()).map_err(Error::from)
+}
+
+/// Replace the `version` in a `pubspec.yaml` with `new_version`.
+///
+/// Unless `new_version` has its own build metadata, the existing build number is kept—or
+/// incremented by one if `increment_build_number` is set.
+/// `path` is used for error reporting.
+pub(crate) fn set_version(
+    dry_run: DryRun,
+    mut content: String,
+    new_version: &Version,
+    path: &Path,
+    increment_build_number: bool,
+) -> Result<String, Error> {
+    let range =
+        yaml::top_level_scalar(&content, "version").ok_or_else(|| Error::NoVersion(path.into()))?;
+    let old_version = Version::from_str(content.get(range.clone()).unwrap_or_default())?;
+    let mut build = new_version.build().or_else(|| old_version.build()).cloned();
+    if increment_build_number && new_version.build().is_none() {
+        let build_number = build
+            .as_ref()
+            .and_then(|build| build.as_str().parse::<u64>().ok())
+            .ok_or_else(|| Error::NoBuildNumber(path.into()))?;
+        build = Some(BuildMetadata::from_str(&(build_number + 1).to_string())?);
+    }
+    let new_version = new_version.clone().with_build(build).to_string();
+    content.replace_range(range, &new_version);
+    fs::write(dry_run, &new_version, path, &content)?;
+    Ok(content)
+}
+
+#[derive(Debug, Diagnostic, Error)]
+pub(crate) enum Error {
+    #[error("No version found in {0}")]
+    #[diagnostic(
+        code(pubspec::no_version),
+        help("knope expects the pubspec.yaml file to have a top-level `version`."),
+        url("https://knope-dev.github.io/knope/config/packages.html#dart-pubspecyaml")
+    )]
+    NoVersion(PathBuf),
+    #[error("No numeric build number found in {0}")]
+    #[diagnostic(
+        code(pubspec::no_build_number),
+        help(
+            "`increment_build_number` requires the version to end with a build number, \
+            like `version: 1.2.3+1`."
+        ),
+        url("https://knope-dev.github.io/knope/config/packages.html#dart-pubspecyaml")
+    )]
+    NoBuildNumber(PathBuf),
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Fs(#[from] fs::Error),
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Semver(#[from] super::semver::version::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::dry_run::fake_dry_run;
+
+    const PUBSPEC: &str = r"name: my_app
+description: A new Flutter project.
+publish_to: 'none' # Remove this line if you wish to publish to pub.dev
+
+version: 1.2.3+45
+
+environment:
+  sdk: '>=3.1.0 <4.0.0'
+
+dependencies:
+  flutter:
+    sdk: flutter
+  cupertino_icons: ^1.0.2
+";
+
+    #[test]
+    fn test_get_version() {
+        let version = get_version(PUBSPEC, Path::new("")).unwrap();
+        assert_eq!(version, Version::new(1, 2, 3, None));
+        assert_eq!(version.to_string(), "1.2.3+45");
+    }
+
+    #[test]
+    fn test_set_version() {
+        let new = set_version(
+            &mut fake_dry_run(),
+            PUBSPEC.to_string(),
+            &Version::new(1, 3, 0, None),
+            Path::new(""),
+            false,
+        )
+        .unwrap();
+        assert_eq!(new, PUBSPEC.replace("1.2.3+45", "1.3.0+45"));
+    }
+
+    #[test]
+    fn increment_build_number() {
+        let new = set_version(
+            &mut fake_dry_run(),
+            PUBSPEC.to_string(),
+            &Version::from_str("2.0.0-rc.0").unwrap(),
+            Path::new(""),
+            true,
+        )
+        .unwrap();
+        assert_eq!(new, PUBSPEC.replace("1.2.3+45", "2.0.0-rc.0+46"));
+    }
+
+    #[test]
+    fn no_build_number() {
+        let pubspec = "name: my_app\nversion: 1.0.0\n";
+        let new = set_version(
+            &mut fake_dry_run(),
+            pubspec.to_string(),
+            &Version::new(1, 0, 1, None),
+            Path::new(""),
+            false,
+        )
+        .unwrap();
+        assert_eq!(new, "name: my_app\nversion: 1.0.1\n");
+
+        let result = set_version(
+            &mut fake_dry_run(),
+            pubspec.to_string(),
+            &Version::new(1, 0, 1, None),
+            Path::new(""),
+            true,
+        );
+        assert!(
+            matches!(result, Err(Error::NoBuildNumber(_))),
+            "Expected NoBuildNumber, got {result:?}"
+        );
+    }
+}
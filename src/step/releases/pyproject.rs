@@ -1,35 +1,44 @@
 use std::{
+    collections::BTreeMap,
+    fmt,
+    ops::Range,
     path::{Path, PathBuf},
-    str::FromStr,
 };
 
+use itertools::Itertools;
 use miette::Diagnostic;
-use serde::Deserialize;
+use serde::{
+    de::{IgnoredAny, MapAccess, SeqAccess, Visitor},
+    Deserialize, Deserializer,
+};
 use thiserror::Error;
 use toml::Spanned;
 
-use super::{semver, semver::Version};
+use super::{dependencies::update_requirement, pep440, semver::Version};
 use crate::{dry_run::DryRun, fs};
 
 /// Extract the consistent version from a `pyproject.toml` file's content or return an error.
 ///
+/// The version is parsed as a PEP 440 version, see [`pep440`].
 /// `path` is used for error reporting.
 pub(crate) fn get_version(content: &str, path: &Path) -> Result<Version, Error> {
     toml::from_str::<PyProject>(content)
         .map_err(|source| Error::Deserialization(path.into(), source))
         .and_then(|pyproject| pyproject.version(path))
-        .and_then(|version| Version::from_str(&version).map_err(Error::from))
+        .and_then(|version| pep440::parse(&version).map_err(Error::from))
 }
 
 /// Replace the version(s) in a `pyproject.toml` file's content with `new_version` or return an error.
 ///
+/// `new_version` is written as a PEP 440 version, like `1.2.3rc1`.
 /// `path` is used for error reporting.
 pub(crate) fn set_version(
     dry_run: DryRun,
     pyproject_toml: String,
-    new_version: &str,
+    new_version: &Version,
     path: &Path,
 ) -> Result<String, Error> {
+    let new_version = &pep440::format(new_version)?;
     let contents = toml::from_str(&pyproject_toml)
         .map_err(|source| Error::Deserialization(path.into(), source))
         .map(|pyproject: PyProject| pyproject.set_version(pyproject_toml, new_version))?;
@@ -37,6 +46,101 @@ pub(crate) fn set_version(
     Ok(contents)
 }
 
+/// The normalized name of the project defined in a `pyproject.toml` file's content, if any.
+pub(crate) fn package_name(content: &str) -> Option<String> {
+    let pyproject = toml::from_str::<PyProject>(content).ok()?;
+    let project_name = pyproject.project.and_then(|project| project.name);
+    let poetry_name = pyproject
+        .tool
+        .and_then(|tool| tool.poetry)
+        .and_then(|poetry| poetry.name);
+    project_name
+        .or(poetry_name)
+        .map(|name| normalize_name(&name))
+}
+
+/// The normalized names of every project that a `pyproject.toml` file's content requires, from
+/// `project.dependencies` and `tool.poetry.dependencies`.
+pub(crate) fn dependencies(content: &str) -> Vec<String> {
+    toml::from_str::<PyProject>(content)
+        .map(|pyproject| {
+            pyproject
+                .requirements(content)
+                .into_iter()
+                .map(|(name, _)| name)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Set the requirement on the project named `dependency` to `new_version`, keeping its operator.
+///
+/// `path` is used for error reporting.
+pub(crate) fn set_dependency_version(
+    dry_run: DryRun,
+    mut pyproject_toml: String,
+    dependency: &str,
+    new_version: &Version,
+    path: &Path,
+) -> Result<String, Error> {
+    let new_version = pep440::format(new_version)?;
+    let pyproject: PyProject = toml::from_str(&pyproject_toml)
+        .map_err(|source| Error::Deserialization(path.into(), source))?;
+    let dependency = normalize_name(dependency);
+    let replacements = pyproject
+        .requirements(&pyproject_toml)
+        .into_iter()
+        .filter(|(name, _)| *name == dependency)
+        .filter_map(|(_, range)| {
+            let requirement = pyproject_toml.get(range.clone())?;
+            Some((range, update_requirement(requirement, &new_version)?))
+        })
+        .sorted_by_key(|(range, _)| std::cmp::Reverse(range.start))
+        .collect_vec();
+    if replacements.is_empty() {
+        return Ok(pyproject_toml);
+    }
+    for (range, requirement) in replacements {
+        pyproject_toml.replace_range(range, &requirement);
+    }
+    fs::write(
+        dry_run,
+        &format!("{dependency} {new_version}"),
+        path,
+        &pyproject_toml,
+    )?;
+    Ok(pyproject_toml)
+}
+
+/// Normalize a project name [like pip does](https://packaging.python.org/en/latest/specifications/name-normalization/),
+/// so `My_Project` and `my-project` are the same.
+fn normalize_name(name: &str) -> String {
+    name.split(['-', '_', '.'])
+        .filter(|part| !part.is_empty())
+        .join("-")
+        .to_lowercase()
+}
+
+/// Find the name and the version specifier in a PEP 508 requirement like
+/// `core[extra] >= 1.2; python_version > "3.8"`.
+///
+/// The range of the specifier is relative to `requirement`.
+fn parse_requirement(requirement: &str) -> Option<(String, Range<usize>)> {
+    let name_end = requirement
+        .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')))
+        .unwrap_or(requirement.len());
+    let name = requirement
+        .get(..name_end)
+        .filter(|name| !name.is_empty())?;
+    let mut rest = requirement.get(name_end..)?.trim_start();
+    if let Some(after_extras) = rest.strip_prefix('[') {
+        rest = after_extras.split_once(']')?.1.trim_start();
+    }
+    let specifier = rest.split(';').next()?.trim_end();
+    let start = requirement.len() - rest.len();
+    Some((normalize_name(name), start..start + specifier.len()))
+}
+
 #[derive(Debug, Diagnostic, Error)]
 pub(crate) enum Error {
     #[error(transparent)]
@@ -73,7 +177,7 @@ pub(crate) enum Error {
     NoVersions(PathBuf),
     #[error(transparent)]
     #[diagnostic(transparent)]
-    Semver(#[from] semver::version::Error),
+    Pep440(#[from] pep440::Error),
 }
 
 #[derive(Debug, Deserialize)]
@@ -121,7 +225,12 @@ impl PyProject {
     fn set_version(self, mut raw_contents: String, new_version: &str) -> String {
         let (poetry_version, project_version) = self.into_versions();
 
-        for version in [poetry_version, project_version].into_iter().flatten() {
+        // Replace from the end of the file so earlier spans stay valid when the length changes
+        for version in [poetry_version, project_version]
+            .into_iter()
+            .flatten()
+            .sorted_by_key(|version| std::cmp::Reverse(version.span().start))
+        {
             // Account for quotes around value with +- 1
             let start = version.span().start + 1;
             let end = version.span().end - 1;
@@ -129,21 +238,114 @@ impl PyProject {
         }
         raw_contents
     }
+
+    /// Every requirement with its normalized name and the range of its version specifier in
+    /// `content`, which must be the content `self` was parsed from.
+    fn requirements(&self, content: &str) -> Vec<(String, Range<usize>)> {
+        let pep_621 = self
+            .project
+            .iter()
+            .flat_map(|project| project.dependencies.iter())
+            .filter_map(|requirement| {
+                // Only strings without escapes can be edited in place
+                let start = requirement.span().start + 1;
+                let raw = content.get(start..requirement.span().end - 1)?;
+                if raw != requirement.get_ref() {
+                    return None;
+                }
+                let (name, range) = parse_requirement(raw)?;
+                Some((name, start + range.start..start + range.end))
+            });
+        let poetry = self
+            .tool
+            .iter()
+            .filter_map(|tool| tool.poetry.as_ref())
+            .flat_map(|poetry| poetry.dependencies.iter())
+            .filter_map(|(name, dependency)| {
+                let span = dependency.get_ref().version.as_ref().map_or_else(
+                    || dependency.get_ref().is_bare.then(|| dependency.span()),
+                    |version| Some(version.span()),
+                )?;
+                // Account for quotes around value with +- 1
+                Some((normalize_name(name), span.start + 1..span.end - 1))
+            });
+        pep_621.chain(poetry).collect()
+    }
 }
 
 #[derive(Debug, Deserialize)]
 struct Tool {
-    poetry: Option<Metadata>,
+    poetry: Option<Poetry>,
 }
 
 #[derive(Debug, Deserialize)]
 struct Metadata {
+    name: Option<String>,
+    version: Option<Spanned<String>>,
+    /// PEP 508 requirements, like `requests>=2.0`
+    #[serde(default)]
+    dependencies: Vec<Spanned<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Poetry {
+    name: Option<String>,
     version: Option<Spanned<String>>,
+    #[serde(default)]
+    dependencies: BTreeMap<String, Spanned<PoetryDependency>>,
+}
+
+/// A Poetry dependency, either a bare requirement like `"^1.2"` or a table with a `version`.
+#[derive(Debug, Default)]
+struct PoetryDependency {
+    is_bare: bool,
+    version: Option<Spanned<String>>,
+}
+
+impl<'de> Deserialize<'de> for PoetryDependency {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct PoetryDependencyVisitor;
+
+        impl<'de> Visitor<'de> for PoetryDependencyVisitor {
+            type Value = PoetryDependency;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a version requirement or a dependency table")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, _: &str) -> Result<Self::Value, E> {
+                Ok(PoetryDependency {
+                    is_bare: true,
+                    version: None,
+                })
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut dependency = PoetryDependency::default();
+                while let Some(key) = map.next_key::<String>()? {
+                    if key == "version" {
+                        dependency.version = Some(map.next_value()?);
+                    } else {
+                        map.next_value::<IgnoredAny>()?;
+                    }
+                }
+                Ok(dependency)
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                // Multiple constraints for different environments aren't updated
+                while seq.next_element::<IgnoredAny>()?.is_some() {}
+                Ok(PoetryDependency::default())
+            }
+        }
+
+        deserializer.deserialize_any(PoetryDependencyVisitor)
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::path::PathBuf;
+    use std::{path::PathBuf, str::FromStr};
 
     use super::*;
     use crate::dry_run::fake_dry_run;
@@ -194,6 +396,20 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_get_version_pep440() {
+        let content = r#"
+        [project]
+        name = "tester"
+        version = "0.1.0b2"
+        "#;
+
+        assert_eq!(
+            get_version(content, PathBuf::new().as_path()).unwrap(),
+            Version::from_str("0.1.0-beta.2").unwrap()
+        );
+    }
+
     #[test]
     fn test_get_version_mismatch() {
         let content = r#"
@@ -232,7 +448,7 @@ mod tests {
         let new = set_version(
             &mut fake_dry_run(),
             String::from(content),
-            "1.2.3-rc.4",
+            &Version::from_str("1.2.3-rc.4").unwrap(),
             &PathBuf::new(),
         )
         .unwrap();
@@ -240,13 +456,51 @@ mod tests {
         let expected = r#"
         [tool.poetry]
         name = "tester"
-        version = "1.2.3-rc.4"
+        version = "1.2.3rc4"
         
         [project]
         name = "tester"
-        version = "1.2.3-rc.4"
+        version = "1.2.3rc4"
         "#
         .to_string();
         assert_eq!(new, expected);
     }
+
+    #[test]
+    fn dependencies() {
+        let content = r#"
+        [project]
+        name = "My_App"
+        version = "1.0.0"
+        dependencies = [
+            "core-lib >= 1.2",
+            "Core.Extras[cli]~=1.2.0; python_version > '3.8'",
+            "requests",
+        ]
+
+        [tool.poetry.dependencies]
+        python = "^3.8"
+        core-lib = { version = "^1.2.0", optional = true }
+        "#;
+
+        assert_eq!(package_name(content).as_deref(), Some("my-app"));
+        assert_eq!(
+            super::dependencies(content),
+            vec!["core-lib", "core-extras", "requests", "core-lib", "python"]
+        );
+
+        let new = set_dependency_version(
+            &mut fake_dry_run(),
+            content.to_string(),
+            "core_lib",
+            &Version::from_str("2.0.0-rc.1").unwrap(),
+            PathBuf::new().as_path(),
+        )
+        .unwrap();
+
+        let expected = content
+            .replace(">= 1.2", ">= 2.0.0rc1")
+            .replace("^1.2.0", "^2.0.0rc1");
+        assert_eq!(new, expected);
+    }
 }
@@ -0,0 +1,225 @@
+//! Links for the issues and pull requests referenced in commit messages, like `#123` on GitHub or
+//! `PROJ-12` in Jira.
+
+use std::collections::HashSet;
+
+use itertools::Itertools;
+
+use crate::config::{GitHub, Jira};
+
+/// Where references in commit messages link to, from the `[github]` and `[jira]` config.
+///
+/// References are only found for the configured services, so without `[github]`, `#123` is left
+/// as plain text.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Links<'config> {
+    pub(crate) github: Option<&'config GitHub>,
+    pub(crate) jira: Option<&'config Jira>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+enum Reference<'text> {
+    /// An issue or pull request on GitHub, this is only the number
+    GitHub(&'text str),
+    /// The key of a Jira issue, like `PROJ-12`
+    Jira(&'text str),
+}
+
+impl Reference<'_> {
+    fn text(&self) -> String {
+        match self {
+            Self::GitHub(number) => format!("#{number}"),
+            Self::Jira(key) => (*key).to_string(),
+        }
+    }
+}
+
+impl Links<'_> {
+    /// Replace every reference in `text` with a Markdown link to it.
+    ///
+    /// References in code spans or which are already part of a link are left alone.
+    pub(crate) fn link(&self, text: &str) -> String {
+        let mut linked = String::with_capacity(text.len());
+        let mut copied_until = 0;
+        for (start, reference) in self.find(text) {
+            let Some(url) = self.url(reference) else {
+                continue;
+            };
+            let reference_text = reference.text();
+            linked.push_str(text.get(copied_until..start).unwrap_or_default());
+            linked.push_str(&format!("[{reference_text}]({url})"));
+            copied_until = start + reference_text.len();
+        }
+        linked.push_str(text.get(copied_until..).unwrap_or_default());
+        linked
+    }
+
+    /// A Markdown link for each different reference in `text`, in the order they first appear,
+    /// leaving out those which are already in `shown`.
+    pub(crate) fn related(&self, text: &str, shown: &str) -> Vec<String> {
+        let shown = self
+            .find(shown)
+            .into_iter()
+            .map(|(_, reference)| reference)
+            .collect::<HashSet<_>>();
+        self.find(text)
+            .into_iter()
+            .map(|(_, reference)| reference)
+            .filter(|reference| !shown.contains(reference))
+            .unique()
+            .filter_map(|reference| {
+                self.url(reference)
+                    .map(|url| format!("[{}]({url})", reference.text()))
+            })
+            .collect()
+    }
+
+    fn url(&self, reference: Reference) -> Option<String> {
+        match reference {
+            Reference::GitHub(number) => self.github.map(|GitHub { owner, repo }| {
+                format!("https://github.com/{owner}/{repo}/issues/{number}")
+            }),
+            Reference::Jira(key) => self
+                .jira
+                .map(|jira| format!("{}/browse/{key}", jira.url.trim_end_matches('/'))),
+        }
+    }
+
+    /// Every reference in `text`, along with where it starts.
+    fn find<'text>(&self, text: &'text str) -> Vec<(usize, Reference<'text>)> {
+        let jira_prefix = self.jira.map(|jira| format!("{}-", jira.project));
+        let mut references = Vec::new();
+        let mut in_code = false;
+        let mut previous = None;
+        for (start, c) in text.char_indices() {
+            let after_boundary = previous.map_or(true, |previous: char| {
+                !(previous.is_alphanumeric() || matches!(previous, '[' | '/' | '-' | '_' | '&'))
+            });
+            previous = Some(c);
+            if c == '`' {
+                in_code = !in_code;
+            }
+            if in_code || !after_boundary {
+                continue;
+            }
+            let rest = text.get(start..).unwrap_or_default();
+            let reference = if let (Some(number), Some(_)) =
+                (rest.strip_prefix('#').and_then(leading_number), self.github)
+            {
+                Some(Reference::GitHub(number))
+            } else if let Some(prefix) = &jira_prefix {
+                rest.strip_prefix(prefix.as_str())
+                    .and_then(leading_number)
+                    .and_then(|number| rest.get(..prefix.len() + number.len()))
+                    .map(Reference::Jira)
+            } else {
+                None
+            };
+            references.extend(reference.map(|reference| (start, reference)));
+        }
+        references
+    }
+}
+
+/// The number at the start of `text`, if it isn't followed by more of the same word.
+fn leading_number(text: &str) -> Option<&str> {
+    let end = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    let number = text.get(..end).filter(|number| !number.is_empty())?;
+    let following = text.get(end..).and_then(|rest| rest.chars().next());
+    if following.is_some_and(|c| c.is_alphanumeric() || c == '_') {
+        None
+    } else {
+        Some(number)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn github() -> GitHub {
+        GitHub {
+            owner: String::from("knope-dev"),
+            repo: String::from("knope"),
+        }
+    }
+
+    fn jira() -> Jira {
+        Jira {
+            url: String::from("https://knope.atlassian.net/"),
+            project: String::from("PROJ"),
+        }
+    }
+
+    #[test]
+    fn link_references() {
+        let github = github();
+        let jira = jira();
+        let links = Links {
+            github: Some(&github),
+            jira: Some(&jira),
+        };
+        assert_eq!(
+            links.link("Fix #12 and PROJ-3, (#4)"),
+            "Fix [#12](https://github.com/knope-dev/knope/issues/12) and \
+            [PROJ-3](https://knope.atlassian.net/browse/PROJ-3), \
+            ([#4](https://github.com/knope-dev/knope/issues/4))"
+        );
+        for unchanged in [
+            "Use `#12` in code",
+            "Already [#12](https://example.com)",
+            "Not an issue: C#12, #12a, ABC#1, PROJ-, PROJ-1a, XPROJ-1, OTHER-1",
+        ] {
+            assert_eq!(links.link(unchanged), unchanged);
+        }
+    }
+
+    #[test]
+    fn only_configured_services() {
+        let jira = jira();
+        let links = Links {
+            github: None,
+            jira: Some(&jira),
+        };
+        assert_eq!(
+            links.link("#12 PROJ-3"),
+            "#12 [PROJ-3](https://knope.atlassian.net/browse/PROJ-3)"
+        );
+        assert_eq!(Links::default().link("#12 PROJ-3"), "#12 PROJ-3");
+    }
+
+    #[test]
+    fn related() {
+        let github = github();
+        let jira = jira();
+        let links = Links {
+            github: Some(&github),
+            jira: Some(&jira),
+        };
+        assert_eq!(
+            links.related(
+                "feat: A feature (#12)\n\nFor PROJ-3\n\nCloses #12\nRefs: #7",
+                ""
+            ),
+            vec![
+                "[#12](https://github.com/knope-dev/knope/issues/12)",
+                "[PROJ-3](https://knope.atlassian.net/browse/PROJ-3)",
+                "[#7](https://github.com/knope-dev/knope/issues/7)",
+            ]
+        );
+        assert_eq!(
+            links.related(
+                "feat: A feature (#12)\n\nFor PROJ-3\n\nCloses #12\nRefs: #7",
+                "A feature (#12)"
+            ),
+            vec![
+                "[PROJ-3](https://knope.atlassian.net/browse/PROJ-3)",
+                "[#7](https://github.com/knope-dev/knope/issues/7)",
+            ]
+        );
+    }
+}
@@ -1,10 +1,17 @@
-use std::fmt::Display;
+use std::{collections::BTreeMap, fmt::Display, path::PathBuf, str::FromStr};
 
 use miette::Diagnostic;
 use serde::{Deserialize, Serialize};
-pub(crate) use version::{Label, PreVersion, Prerelease, StableVersion, Version};
-
-use super::{package::Package, versioned_file, ChangeType, CurrentVersions, Prereleases, Release};
+use time::OffsetDateTime;
+pub(crate) use version::{BuildMetadata, Label, PreVersion, Prerelease, StableVersion, Version};
+
+use super::{
+    calver, cargo,
+    package::{Package, Versioning},
+    versioned_file,
+    versioned_file::VersionedFile,
+    ChangeType, CurrentVersions, Prereleases, Release,
+};
 use crate::{
     dry_run::DryRun,
     integrations::{git, git::get_current_versions_from_tags},
@@ -28,6 +35,25 @@ pub(crate) enum Rule {
         stable_rule: ConventionalRule,
     },
     Release,
+    /// Go from a 0.x version to 1.0.0
+    Graduate,
+}
+
+impl Rule {
+    /// The rule for a release of changes which imply `stable_rule`, as a pre-release if there's a
+    /// `prerelease_label`.
+    pub(crate) fn from_changes(
+        stable_rule: ConventionalRule,
+        prerelease_label: &Option<Label>,
+    ) -> Self {
+        match prerelease_label {
+            Some(label) => Rule::Pre {
+                label: label.clone(),
+                stable_rule,
+            },
+            None => stable_rule.into(),
+        }
+    }
 }
 
 impl From<ConventionalRule> for Rule {
@@ -97,6 +123,34 @@ impl From<ChangeType> for ConventionalRule {
     }
 }
 
+/// Where the build metadata attached to every new version of a package comes from.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum BuildMetadataSource {
+    /// The abbreviated hash of the current Git commit, like `1.2.3+a1b2c3d`
+    Sha,
+    /// The current UTC date, like `1.2.3+20240101`
+    Date,
+}
+
+impl BuildMetadataSource {
+    pub(crate) fn build_metadata(self) -> Result<BuildMetadata, Error> {
+        let build = match self {
+            Self::Sha => git::short_head_commit_id()?,
+            Self::Date => {
+                let today = OffsetDateTime::now_utc().date();
+                format!(
+                    "{:04}{:02}{:02}",
+                    today.year(),
+                    u8::from(today.month()),
+                    today.day()
+                )
+            }
+        };
+        BuildMetadata::from_str(&build).map_err(Error::from)
+    }
+}
+
 #[derive(Debug, Eq, PartialEq)]
 pub(crate) struct PackageVersion {
     /// The current version for the package
@@ -124,8 +178,9 @@ pub(crate) fn bump_version_and_update_state(
             let version = if let Some(override_version) = package.override_version.clone() {
                 override_version
             } else {
-                bump(package.get_version(state.verbose)?, rule, state.verbose)?
+                package.next_version(package.get_version(state.verbose)?, rule, state.verbose)?
             };
+            let version = package.add_build_metadata(version)?;
             let mut package = package.write_version(&version, &mut dry_run_stdout)?;
             package.prepared_release = Some(Release::new(None, version));
             Ok(package)
@@ -139,10 +194,43 @@ pub(crate) fn bump_version_and_update_state(
 }
 
 impl Package {
+    /// Determine the version after `versions` using `rule` and the package's [`Versioning`].
+    pub(crate) fn next_version(
+        &self,
+        versions: CurrentVersions,
+        rule: &Rule,
+        verbose: Verbose,
+    ) -> Result<Version, Error> {
+        match &self.versioning {
+            Versioning::SemVer { pre_1_0_convention } => {
+                bump(versions, rule, *pre_1_0_convention, verbose)
+            }
+            Versioning::CalVer(format) => calver::bump(
+                versions,
+                rule,
+                format,
+                OffsetDateTime::now_utc().date(),
+                verbose,
+            )
+            .map_err(Error::from),
+        }
+    }
+
+    /// Attach this package's configured build metadata to `version`, unless it already has some.
+    pub(crate) fn add_build_metadata(&self, version: Version) -> Result<Version, Error> {
+        match self.build_metadata {
+            Some(source) if version.build().is_none() => {
+                Ok(version.with_build(Some(source.build_metadata()?)))
+            }
+            _ => Ok(version),
+        }
+    }
+
     /// Get the current version of a package determined by the last tag for the package _and_ the
     /// version in versioned files. The version from files takes precedent over version from tag.
     pub(crate) fn get_version(&self, verbose: Verbose) -> Result<CurrentVersions, Error> {
-        let mut current_versions = get_current_versions_from_tags(self.name.as_deref(), verbose)?;
+        let mut current_versions =
+            get_current_versions_from_tags(&self.tag_template(), self.uses_pep440(), verbose)?;
 
         if let Some(version_from_files) = self.version_from_files(verbose)? {
             current_versions.update_version(version_from_files);
@@ -183,15 +271,31 @@ impl Package {
     /// Consumes a [`PackageVersion`], writing it back to the file it came from. Returns the new version
     /// that was written. Adds all modified package files to Git.
     ///
+    /// Any `Cargo.lock` recording a crate from a versioned `Cargo.toml` is updated as well.
+    ///
     /// If `dry_run` is `true`, the version will not be written to any files.
     pub(crate) fn write_version(
         mut self,
         version: &Version,
         dry_run: DryRun,
     ) -> Result<Self, versioned_file::Error> {
+        let mut lockfiles: BTreeMap<PathBuf, Vec<(String, String)>> = BTreeMap::new();
+        for locked in self
+            .versioned_files
+            .iter()
+            .filter_map(VersionedFile::locked_crates)
+        {
+            let crates = lockfiles.entry(locked.lockfile).or_default();
+            for name in locked.names {
+                crates.push((name, locked.version.clone()));
+            }
+        }
         for versioned_file in &mut self.versioned_files {
             versioned_file.set_version(dry_run, version)?;
         }
+        for (lockfile, crates) in lockfiles {
+            cargo::set_lockfile_versions(dry_run, &lockfile, &crates, &version.to_string())?;
+        }
         Ok(self)
     }
 }
@@ -219,6 +323,19 @@ pub(crate) enum Error {
     #[error(transparent)]
     #[diagnostic(transparent)]
     Git(#[from] git::Error),
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    BuildMetadata(#[from] version::Error),
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    CalVer(#[from] calver::Error),
+    #[error("Can't graduate {0} to 1.0.0, it's already 1.0.0 or later")]
+    #[diagnostic(
+        code(semver::already_graduated),
+        help("The Graduate rule is only for going from 0.x to 1.0.0, use the Major rule instead."),
+        url("https://knope-dev.github.io/knope/config/step/BumpVersion.html#graduate")
+    )]
+    AlreadyGraduated(String),
 }
 
 #[derive(Debug, Diagnostic, thiserror::Error)]
@@ -237,52 +354,65 @@ pub(crate) struct InvalidPreReleaseVersion(String);
 ///
 /// ### Versions 0.x
 ///
-/// Versions with major component 0 have special meaning in Semantic Versioning and therefore have
-/// different behavior:
+/// Versions with major component 0 have special meaning in Semantic Versioning. If
+/// `pre_1_0_convention` is set, they therefore have different behavior:
 /// 1. [`Rule::Major`] will bump the minor component.
 /// 2. [`Rule::Minor`] will bump the patch component.
+///
+/// Either way, only [`Rule::Graduate`] goes from 0.x to 1.0.0 explicitly.
 pub(crate) fn bump(
     mut versions: CurrentVersions,
     rule: &Rule,
+    pre_1_0_convention: bool,
     verbose: Verbose,
-) -> Result<Version, InvalidPreReleaseVersion> {
+) -> Result<Version, Error> {
     let stable = versions.stable.unwrap_or_default();
-    let is_0 = stable.major == 0;
+    let is_0 = pre_1_0_convention && stable.major == 0;
     match (rule, is_0) {
         (Rule::Major, false) => {
             let new_stable = stable.increment_major();
             if let Verbose::Yes = verbose {
                 println!("Using MAJOR rule to bump from {stable} to {new_stable}");
             }
-            Ok(Version::Stable(new_stable))
+            Ok(Version::from(new_stable))
         }
         (Rule::Minor, false) => {
             let new_stable = stable.increment_minor();
             if let Verbose::Yes = verbose {
                 println!("Using MINOR rule to bump from {stable} to {new_stable}");
             }
-            Ok(Version::Stable(new_stable))
+            Ok(Version::from(new_stable))
         }
         (Rule::Major, true) => {
             let new_stable = stable.increment_minor();
             if let Verbose::Yes = verbose {
                 println!("Rule is MAJOR, but major component is 0. Bumping minor component from {stable} to {new_stable}");
             }
-            Ok(Version::Stable(new_stable))
+            Ok(Version::from(new_stable))
         }
         (Rule::Minor, true) => {
             let new_stable = stable.increment_patch();
             if let Verbose::Yes = verbose {
                 println!("Rule is MINOR, but major component is 0. Bumping patch component from {stable} to {new_stable}");
             }
-            Ok(Version::Stable(new_stable))
+            Ok(Version::from(new_stable))
         }
         (Rule::Patch, _) => {
             let new_stable = stable.increment_patch();
             if let Verbose::Yes = verbose {
                 println!("Using PATCH rule to bump from {stable} to {new_stable}");
             }
-            Ok(Version::Stable(new_stable))
+            Ok(Version::from(new_stable))
+        }
+        (Rule::Graduate, _) => {
+            if stable.major != 0 {
+                return Err(Error::AlreadyGraduated(stable.to_string()));
+            }
+            let new_stable = stable.increment_major();
+            if let Verbose::Yes = verbose {
+                println!("Using GRADUATE rule to bump from {stable} to {new_stable}");
+            }
+            Ok(Version::from(new_stable))
         }
         (Rule::Release, _) => {
             let version = versions
@@ -294,11 +424,16 @@ pub(crate) fn bump(
                         "No prerelease version found, but a Release rule was requested".to_string(),
                     )
                 })?;
-            Ok(Version::Stable(version))
-        }
-        (Rule::Pre { label, stable_rule }, _) => {
-            bump_pre(stable, &versions.prereleases, label, *stable_rule, verbose)
+            Ok(Version::from(version))
         }
+        (Rule::Pre { label, stable_rule }, _) => bump_pre(
+            stable,
+            &versions.prereleases,
+            label,
+            *stable_rule,
+            pre_1_0_convention,
+            verbose,
+        ),
     }
 }
 
@@ -313,7 +448,7 @@ mod test_bump {
     #[test]
     fn major() {
         let stable = Version::new(1, 2, 3, None);
-        let version = bump(stable.into(), &Rule::Major, Verbose::No).unwrap();
+        let version = bump(stable.into(), &Rule::Major, true, Verbose::No).unwrap();
 
         assert_eq!(version, Version::new(2, 0, 0, None,));
     }
@@ -321,14 +456,14 @@ mod test_bump {
     #[test]
     fn major_0() {
         let stable = Version::new(0, 1, 2, None);
-        let version = bump(stable.into(), &Rule::Major, Verbose::No).unwrap();
+        let version = bump(stable.into(), &Rule::Major, true, Verbose::No).unwrap();
 
         assert_eq!(version, Version::new(0, 2, 0, None,));
     }
 
     #[test]
     fn major_unset() {
-        let version = bump(CurrentVersions::default(), &Rule::Major, Verbose::No).unwrap();
+        let version = bump(CurrentVersions::default(), &Rule::Major, true, Verbose::No).unwrap();
 
         assert_eq!(version, Version::new(0, 1, 0, None,));
     }
@@ -340,7 +475,7 @@ mod test_bump {
     fn major_after_pre(#[case] pre_version: &str) {
         let mut versions = CurrentVersions::from(Version::new(1, 2, 3, None));
         versions.update_version(Version::from_str(pre_version).unwrap());
-        let version = bump(versions, &Rule::Major, Verbose::No).unwrap();
+        let version = bump(versions, &Rule::Major, true, Verbose::No).unwrap();
 
         assert_eq!(version, Version::new(2, 0, 0, None));
     }
@@ -348,7 +483,7 @@ mod test_bump {
     #[test]
     fn minor() {
         let stable = Version::new(1, 2, 3, None);
-        let version = bump(stable.into(), &Rule::Minor, Verbose::No).unwrap();
+        let version = bump(stable.into(), &Rule::Minor, true, Verbose::No).unwrap();
 
         assert_eq!(version, Version::new(1, 3, 0, None));
     }
@@ -356,14 +491,14 @@ mod test_bump {
     #[test]
     fn minor_0() {
         let stable = Version::new(0, 1, 2, None);
-        let version = bump(stable.into(), &Rule::Minor, Verbose::No).unwrap();
+        let version = bump(stable.into(), &Rule::Minor, true, Verbose::No).unwrap();
 
         assert_eq!(version, Version::new(0, 1, 3, None));
     }
 
     #[test]
     fn minor_unset() {
-        let version = bump(CurrentVersions::default(), &Rule::Minor, Verbose::No).unwrap();
+        let version = bump(CurrentVersions::default(), &Rule::Minor, true, Verbose::No).unwrap();
 
         assert_eq!(version, Version::new(0, 0, 1, None,));
     }
@@ -374,7 +509,7 @@ mod test_bump {
     fn minor_after_pre(#[case] pre_version: &str) {
         let mut versions = CurrentVersions::from(Version::new(1, 2, 3, None));
         versions.update_version(Version::from_str(pre_version).unwrap());
-        let version = bump(versions, &Rule::Minor, Verbose::No).unwrap();
+        let version = bump(versions, &Rule::Minor, true, Verbose::No).unwrap();
 
         assert_eq!(version, Version::new(1, 3, 0, None,));
     }
@@ -382,7 +517,7 @@ mod test_bump {
     #[test]
     fn patch() {
         let stable = Version::new(1, 2, 3, None);
-        let version = bump(stable.into(), &Rule::Patch, Verbose::No).unwrap();
+        let version = bump(stable.into(), &Rule::Patch, true, Verbose::No).unwrap();
 
         assert_eq!(version, Version::new(1, 2, 4, None));
     }
@@ -390,14 +525,14 @@ mod test_bump {
     #[test]
     fn patch_0() {
         let stable = Version::new(0, 1, 0, None);
-        let version = bump(stable.into(), &Rule::Patch, Verbose::No).unwrap();
+        let version = bump(stable.into(), &Rule::Patch, true, Verbose::No).unwrap();
 
         assert_eq!(version, Version::new(0, 1, 1, None,));
     }
 
     #[test]
     fn patch_unset() {
-        let version = bump(CurrentVersions::default(), &Rule::Patch, Verbose::No).unwrap();
+        let version = bump(CurrentVersions::default(), &Rule::Patch, true, Verbose::No).unwrap();
 
         assert_eq!(version, Version::new(0, 0, 1, None,));
     }
@@ -406,7 +541,7 @@ mod test_bump {
     fn patch_after_pre() {
         let mut versions = CurrentVersions::from(Version::new(1, 2, 3, None));
         versions.update_version(Version::from_str("1.2.4-rc.0").unwrap());
-        let version = bump(versions, &Rule::Patch, Verbose::No).unwrap();
+        let version = bump(versions, &Rule::Patch, true, Verbose::No).unwrap();
 
         assert_eq!(version, Version::new(1, 2, 4, None,));
     }
@@ -420,6 +555,7 @@ mod test_bump {
                 label: Label::from("rc"),
                 stable_rule: ConventionalRule::Minor,
             },
+            true,
             Verbose::No,
         )
         .unwrap();
@@ -439,6 +575,7 @@ mod test_bump {
                 label: Label::from("rc"),
                 stable_rule: ConventionalRule::Minor,
             },
+            true,
             Verbose::No,
         )
         .unwrap();
@@ -457,6 +594,7 @@ mod test_bump {
                 label: Label::from("beta"),
                 stable_rule: ConventionalRule::Patch,
             },
+            true,
             Verbose::No,
         )
         .unwrap();
@@ -474,6 +612,7 @@ mod test_bump {
                 label: Label::from("rc"),
                 stable_rule: ConventionalRule::Minor,
             },
+            true,
             Verbose::No,
         )
         .unwrap();
@@ -481,6 +620,41 @@ mod test_bump {
         assert_eq!(new, Version::from_str("1.3.0-rc.0").unwrap());
     }
 
+    #[test]
+    fn major_0_without_convention() {
+        let stable = Version::new(0, 1, 2, None);
+        let version = bump(stable.into(), &Rule::Major, false, Verbose::No).unwrap();
+
+        assert_eq!(version, Version::new(1, 0, 0, None));
+    }
+
+    #[test]
+    fn minor_0_without_convention() {
+        let stable = Version::new(0, 1, 2, None);
+        let version = bump(stable.into(), &Rule::Minor, false, Verbose::No).unwrap();
+
+        assert_eq!(version, Version::new(0, 2, 0, None));
+    }
+
+    #[test]
+    fn graduate() {
+        let stable = Version::new(0, 4, 2, None);
+        let version = bump(stable.into(), &Rule::Graduate, true, Verbose::No).unwrap();
+
+        assert_eq!(version, Version::new(1, 0, 0, None));
+    }
+
+    #[test]
+    fn graduate_after_1_0() {
+        let stable = Version::new(1, 4, 2, None);
+        let result = bump(stable.into(), &Rule::Graduate, true, Verbose::No);
+
+        assert!(
+            matches!(result, Err(Error::AlreadyGraduated(_))),
+            "Expected AlreadyGraduated, got {result:?}"
+        );
+    }
+
     #[test]
     fn release() {
         let mut versions = CurrentVersions::default();
@@ -488,7 +662,7 @@ mod test_bump {
         versions.update_version(Version::from_str("1.2.4-rc.1").unwrap());
         versions.update_version(Version::from_str("2.0.0-rc.2").unwrap());
 
-        let version = bump(versions, &Rule::Release, Verbose::No).unwrap();
+        let version = bump(versions, &Rule::Release, true, Verbose::No).unwrap();
 
         assert_eq!(version, Version::new(2, 0, 0, None,));
     }
@@ -508,12 +682,34 @@ fn bump_pre(
     prereleases: &Prereleases,
     label: &Label,
     stable_rule: ConventionalRule,
+    pre_1_0_convention: bool,
     verbose: Verbose,
-) -> Result<Version, InvalidPreReleaseVersion> {
+) -> Result<Version, Error> {
     if let Verbose::Yes = verbose {
         println!("Pre-release label {label} selected. Determining next stable version...");
     }
-    let stable_component = bump(stable.into(), &stable_rule.into(), verbose)?.stable_component();
+    let stable_component = bump(
+        stable.into(),
+        &stable_rule.into(),
+        pre_1_0_convention,
+        verbose,
+    )?
+    .stable_component();
+    Ok(next_prerelease(
+        stable_component,
+        prereleases,
+        label,
+        verbose,
+    ))
+}
+
+/// The next pre-release of `stable_component` with `label`, continuing from any existing one.
+pub(crate) fn next_prerelease(
+    stable_component: StableVersion,
+    prereleases: &Prereleases,
+    label: &Label,
+    verbose: Verbose,
+) -> Version {
     let pre_component = prereleases
         .get(&stable_component)
         .and_then(|pres| {
@@ -533,8 +729,8 @@ fn bump_pre(
             pre
         });
 
-    Ok(Version::Pre(PreVersion {
+    Version::from(PreVersion {
         stable_component,
         pre_component,
-    }))
+    })
 }
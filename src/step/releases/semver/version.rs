@@ -3,22 +3,39 @@ use std::{cmp::Ordering, fmt::Display, str::FromStr};
 use miette::Diagnostic;
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+/// A semantic version, optionally with build metadata (like `+45` in `1.2.3+45`).
+///
+/// Build metadata is kept when parsing and displaying, but ignored when comparing versions.
+#[derive(Clone, Debug)]
 pub(crate) enum Version {
-    Stable(StableVersion),
-    Pre(PreVersion),
+    Stable(StableVersion, Option<BuildMetadata>),
+    Pre(PreVersion, Option<BuildMetadata>),
 }
 
 impl Version {
     pub(crate) const fn stable_component(&self) -> StableVersion {
         match self {
-            Self::Stable(stable) => *stable,
-            Self::Pre(pre) => pre.stable_component,
+            Self::Stable(stable, _) => *stable,
+            Self::Pre(pre, _) => pre.stable_component,
         }
     }
 
     pub(crate) const fn is_prerelease(&self) -> bool {
-        matches!(self, Version::Pre(_))
+        matches!(self, Version::Pre(..))
+    }
+
+    pub(crate) const fn build(&self) -> Option<&BuildMetadata> {
+        match self {
+            Self::Stable(_, build) | Self::Pre(_, build) => build.as_ref(),
+        }
+    }
+
+    /// Replace the build metadata of this version.
+    pub(crate) fn with_build(self, build: Option<BuildMetadata>) -> Self {
+        match self {
+            Self::Stable(stable, _) => Self::Stable(stable, build),
+            Self::Pre(pre, _) => Self::Pre(pre, build),
+        }
     }
 }
 
@@ -30,11 +47,14 @@ impl Version {
             patch,
         };
         match pre {
-            Some(pre) => Self::Pre(PreVersion {
-                stable_component: stable,
-                pre_component: pre,
-            }),
-            None => Self::Stable(stable),
+            Some(pre) => Self::Pre(
+                PreVersion {
+                    stable_component: stable,
+                    pre_component: pre,
+                },
+                None,
+            ),
+            None => Self::Stable(stable, None),
         }
     }
 }
@@ -47,7 +67,13 @@ impl Default for Version {
 
 impl From<StableVersion> for Version {
     fn from(stable: StableVersion) -> Self {
-        Self::Stable(stable)
+        Self::Stable(stable, None)
+    }
+}
+
+impl From<PreVersion> for Version {
+    fn from(pre: PreVersion) -> Self {
+        Self::Pre(pre, None)
     }
 }
 
@@ -121,10 +147,10 @@ impl Ord for Version {
     fn cmp(&self, other: &Self) -> Ordering {
         match self.stable_component().cmp(&other.stable_component()) {
             Ordering::Equal => match (self, other) {
-                (Self::Stable(_), Self::Stable(_)) => Ordering::Equal,
-                (Self::Stable(_), Self::Pre(_)) => Ordering::Greater,
-                (Self::Pre(_), Self::Stable(_)) => Ordering::Less,
-                (Self::Pre(pre), Self::Pre(other_pre)) => {
+                (Self::Stable(..), Self::Stable(..)) => Ordering::Equal,
+                (Self::Stable(..), Self::Pre(..)) => Ordering::Greater,
+                (Self::Pre(..), Self::Stable(..)) => Ordering::Less,
+                (Self::Pre(pre, _), Self::Pre(other_pre, _)) => {
                     pre.pre_component.cmp(&other_pre.pre_component)
                 }
             },
@@ -139,10 +165,23 @@ impl PartialOrd for Version {
     }
 }
 
+/// Versions which only differ in build metadata are equal, per the spec.
+impl PartialEq for Version {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Version {}
+
 impl FromStr for Version {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (s, build) = s
+            .split_once('+')
+            .map_or((s, None), |(version, build)| (version, Some(build)));
+        let build = build.map(BuildMetadata::from_str).transpose()?;
         let (version, pre) = s
             .split_once('-')
             .map_or((s, None), |(version, pre)| (version, Some(pre)));
@@ -158,12 +197,15 @@ impl FromStr for Version {
             patch: version_parts[2],
         };
         if let Some(pre) = pre {
-            Ok(Self::Pre(PreVersion {
-                stable_component: stable,
-                pre_component: Prerelease::from_str(pre)?,
-            }))
+            Ok(Self::Pre(
+                PreVersion {
+                    stable_component: stable,
+                    pre_component: Prerelease::from_str(pre)?,
+                },
+                build,
+            ))
         } else {
-            Ok(Self::Stable(stable))
+            Ok(Self::Stable(stable, build))
         }
     }
 }
@@ -180,21 +222,31 @@ pub(crate) struct Error(String);
 impl Display for Version {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Stable(StableVersion {
-                major,
-                minor,
-                patch,
-            }) => write!(f, "{major}.{minor}.{patch}"),
-            Self::Pre(PreVersion {
-                stable_component:
-                    StableVersion {
-                        major,
-                        minor,
-                        patch,
-                    },
-                pre_component,
-            }) => write!(f, "{major}.{minor}.{patch}-{pre_component}",),
+            Self::Stable(
+                StableVersion {
+                    major,
+                    minor,
+                    patch,
+                },
+                _,
+            ) => write!(f, "{major}.{minor}.{patch}"),
+            Self::Pre(
+                PreVersion {
+                    stable_component:
+                        StableVersion {
+                            major,
+                            minor,
+                            patch,
+                        },
+                    pre_component,
+                },
+                _,
+            ) => write!(f, "{major}.{minor}.{patch}-{pre_component}",),
+        }?;
+        if let Some(build) = self.build() {
+            write!(f, "+{build}")?;
         }
+        Ok(())
     }
 }
 
@@ -246,6 +298,41 @@ impl Prerelease {
     }
 }
 
+/// Build metadata, like `45` in `1.2.3+45`: dot-separated identifiers of ASCII alphanumerics and
+/// hyphens.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct BuildMetadata(String);
+
+impl BuildMetadata {
+    pub(crate) fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for BuildMetadata {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let is_valid = s.split('.').all(|identifier| {
+            !identifier.is_empty()
+                && identifier
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-')
+        });
+        if is_valid {
+            Ok(Self(s.to_string()))
+        } else {
+            Err(Error(format!("Invalid build metadata {s}")))
+        }
+    }
+}
+
+impl Display for BuildMetadata {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
 /// The label component of a Prerelease (e.g., "alpha" in "1.0.0-alpha.1").
 #[derive(Clone, Debug, Deserialize, Eq, Ord, PartialEq, PartialOrd, Serialize)]
 #[repr(transparent)]
@@ -262,3 +349,37 @@ impl From<&str> for Label {
         Self(s.to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn build_metadata_round_trip() {
+        for version in ["1.2.3+45", "1.2.3-rc.0+build.5", "0.1.0+20240101-abc"] {
+            assert_eq!(Version::from_str(version).unwrap().to_string(), version);
+        }
+    }
+
+    #[test]
+    fn build_metadata_ignored_in_comparisons() {
+        let with_build = Version::from_str("1.2.3+45").unwrap();
+        let without_build = Version::new(1, 2, 3, None);
+        assert_eq!(with_build, without_build);
+        assert_eq!(with_build.cmp(&without_build), Ordering::Equal);
+        assert!(Version::from_str("1.2.4+1").unwrap() > with_build);
+        assert!(Version::from_str("1.2.3-rc.0+99").unwrap() < without_build);
+    }
+
+    #[test]
+    fn invalid_build_metadata() {
+        for version in ["1.2.3+", "1.2.3+a..b", "1.2.3+a_b"] {
+            assert!(
+                Version::from_str(version).is_err(),
+                "{version} should be invalid"
+            );
+        }
+    }
+}
@@ -0,0 +1,502 @@
+//! A small template language for changelogs, based on a subset of [Jinja](https://jinja.palletsprojects.com):
+//!
+//! - `{{ version }}` or `{{ section.title }}` writes a variable
+//! - `{% for entry in section.entries %}…{% endfor %}` repeats for every item of a list
+//! - `{% if entry.body %}…{% else %}…{% endif %}` checks whether a variable is set and not empty
+//!
+//! Like Jinja's `trim_blocks`, the first newline after a `{% … %}` tag is removed, so tags can be
+//! on their own lines without adding blank lines to the output.
+
+use std::{ops::Range, path::PathBuf};
+
+use miette::{Diagnostic, NamedSource, SourceSpan};
+use serde_json::Value;
+use thiserror::Error;
+
+/// A parsed template, ready to be rendered any number of times.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct Template {
+    pub(crate) path: PathBuf,
+    source: String,
+    nodes: Vec<Node>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Node {
+    Text(Range<usize>),
+    Variable(Path),
+    For {
+        item: String,
+        list: Path,
+        body: Vec<Node>,
+    },
+    If {
+        condition: Path,
+        then: Vec<Node>,
+        otherwise: Vec<Node>,
+    },
+}
+
+/// A variable like `section.title`, along with where it is in the template.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Path {
+    segments: Vec<String>,
+    span: Range<usize>,
+}
+
+impl Path {
+    fn name(&self) -> String {
+        self.segments.join(".")
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Token {
+    Text(Range<usize>),
+    /// `{{ … }}`, with the range of what's inside
+    Variable(Range<usize>),
+    /// `{% … %}`, with the range of what's inside and the range of the whole tag
+    Statement(Range<usize>, Range<usize>),
+}
+
+/// A tag which ends a block, like `{% endfor %}`.
+struct BlockEnd {
+    keyword: String,
+    span: Range<usize>,
+}
+
+impl Template {
+    /// Parse the template in `source`, which was loaded from `path`.
+    pub(crate) fn parse(path: PathBuf, source: String) -> Result<Self, Error> {
+        let mut template = Self {
+            path,
+            source,
+            nodes: Vec::new(),
+        };
+        let mut tokens = template.tokenize()?.into_iter();
+        let (nodes, end) = template.parse_nodes(&mut tokens)?;
+        if let Some(end) = end {
+            return Err(template.syntax_error(format!("unexpected `{}`", end.keyword), end.span));
+        }
+        template.nodes = nodes;
+        Ok(template)
+    }
+
+    /// Render the template with the variables in `context`, which must be an object.
+    pub(crate) fn render(&self, context: &Value) -> Result<String, Error> {
+        let mut output = String::with_capacity(self.source.len());
+        self.render_nodes(&self.nodes, context, &mut Vec::new(), &mut output)?;
+        Ok(output)
+    }
+
+    fn tokenize(&self) -> Result<Vec<Token>, Error> {
+        let source = self.source.as_str();
+        let mut tokens = Vec::new();
+        let mut position = 0;
+        while position < source.len() {
+            let rest = source.get(position..).unwrap_or_default();
+            let Some(offset) = [rest.find("{{"), rest.find("{%")]
+                .into_iter()
+                .flatten()
+                .min()
+            else {
+                tokens.push(Token::Text(position..source.len()));
+                break;
+            };
+            if offset > 0 {
+                tokens.push(Token::Text(position..position + offset));
+            }
+            let start = position + offset;
+            let is_variable = rest.get(offset..).is_some_and(|tag| tag.starts_with("{{"));
+            let close = if is_variable { "}}" } else { "%}" };
+            let inner_start = start + 2;
+            let Some(inner_length) = source
+                .get(inner_start..)
+                .and_then(|inner| inner.find(close))
+            else {
+                return Err(self.syntax_error(format!("missing `{close}`"), start..inner_start));
+            };
+            let inner = inner_start..inner_start + inner_length;
+            position = inner.end + 2;
+            if is_variable {
+                tokens.push(Token::Variable(inner));
+            } else {
+                tokens.push(Token::Statement(inner, start..position));
+                let after = source.get(position..).unwrap_or_default();
+                if after.starts_with("\r\n") {
+                    position += 2;
+                } else if after.starts_with('\n') {
+                    position += 1;
+                }
+            }
+        }
+        Ok(tokens)
+    }
+
+    /// Parse nodes until the end of the template or a tag which ends a block, which is returned.
+    fn parse_nodes(
+        &self,
+        tokens: &mut impl Iterator<Item = Token>,
+    ) -> Result<(Vec<Node>, Option<BlockEnd>), Error> {
+        let mut nodes = Vec::new();
+        while let Some(token) = tokens.next() {
+            let (inner, span) = match token {
+                Token::Text(range) => {
+                    nodes.push(Node::Text(range));
+                    continue;
+                }
+                Token::Variable(inner) => {
+                    nodes.push(Node::Variable(self.parse_path(inner)?));
+                    continue;
+                }
+                Token::Statement(inner, span) => (inner, span),
+            };
+            let statement = self.source.get(inner.clone()).unwrap_or_default();
+            match self.words(&inner).as_slice() {
+                [("for", _), (item, _), ("in", _), (_, list)] if is_identifier(item) => {
+                    let list = self.parse_path(list.clone())?;
+                    let body = self.parse_block(tokens, &span, &["endfor"])?.0;
+                    nodes.push(Node::For {
+                        item: (*item).to_string(),
+                        list,
+                        body,
+                    });
+                }
+                [("if", _), (_, condition)] => {
+                    let condition = self.parse_path(condition.clone())?;
+                    let (then, end) = self.parse_block(tokens, &span, &["else", "endif"])?;
+                    let otherwise = if end.keyword == "else" {
+                        self.parse_block(tokens, &span, &["endif"])?.0
+                    } else {
+                        Vec::new()
+                    };
+                    nodes.push(Node::If {
+                        condition,
+                        then,
+                        otherwise,
+                    });
+                }
+                [(keyword @ ("endfor" | "else" | "endif"), _)] => {
+                    return Ok((
+                        nodes,
+                        Some(BlockEnd {
+                            keyword: (*keyword).to_string(),
+                            span,
+                        }),
+                    ));
+                }
+                _ => {
+                    return Err(self
+                        .syntax_error(format!("unknown statement `{}`", statement.trim()), span))
+                }
+            }
+        }
+        Ok((nodes, None))
+    }
+
+    /// Parse the body of the block started by the tag at `start`, which must end with one of
+    /// `keywords`.
+    fn parse_block(
+        &self,
+        tokens: &mut impl Iterator<Item = Token>,
+        start: &Range<usize>,
+        keywords: &[&str],
+    ) -> Result<(Vec<Node>, BlockEnd), Error> {
+        let (nodes, end) = self.parse_nodes(tokens)?;
+        match end {
+            Some(end) if keywords.contains(&end.keyword.as_str()) => Ok((nodes, end)),
+            Some(end) => Err(self.syntax_error(format!("unexpected `{}`", end.keyword), end.span)),
+            None => Err(self.syntax_error(
+                format!("missing `{{% {} %}}`", keywords.last().unwrap_or(&"end")),
+                start.clone(),
+            )),
+        }
+    }
+
+    fn parse_path(&self, range: Range<usize>) -> Result<Path, Error> {
+        let text = self.source.get(range.clone()).unwrap_or_default();
+        let start = range.start + (text.len() - text.trim_start().len());
+        let span = start..start + text.trim().len();
+        let segments = text.trim().split('.').map(String::from).collect::<Vec<_>>();
+        if segments.iter().all(|segment| is_identifier(segment)) {
+            Ok(Path { segments, span })
+        } else {
+            Err(self.syntax_error(format!("`{}` is not a variable", text.trim()), span))
+        }
+    }
+
+    /// The words separated by whitespace in `inner`, each with its range in the source.
+    fn words(&self, inner: &Range<usize>) -> Vec<(&str, Range<usize>)> {
+        let text = self.source.get(inner.clone()).unwrap_or_default();
+        let mut words = Vec::new();
+        let mut word_start = None;
+        // A trailing space ends the last word
+        for (index, char) in text.char_indices().chain([(text.len(), ' ')]) {
+            match (word_start, char.is_whitespace()) {
+                (Some(start), true) => {
+                    words.push((
+                        text.get(start..index).unwrap_or_default(),
+                        inner.start + start..inner.start + index,
+                    ));
+                    word_start = None;
+                }
+                (None, false) => word_start = Some(index),
+                _ => {}
+            }
+        }
+        words
+    }
+
+    fn render_nodes(
+        &self,
+        nodes: &[Node],
+        context: &Value,
+        scopes: &mut Vec<(String, Value)>,
+        output: &mut String,
+    ) -> Result<(), Error> {
+        for node in nodes {
+            match node {
+                Node::Text(range) => {
+                    output.push_str(self.source.get(range.clone()).unwrap_or_default());
+                }
+                Node::Variable(path) => match self.lookup(path, context, scopes)? {
+                    Value::String(text) => output.push_str(&text),
+                    Value::Number(number) => output.push_str(&number.to_string()),
+                    Value::Bool(value) => output.push_str(&value.to_string()),
+                    Value::Null => {}
+                    Value::Array(_) | Value::Object(_) => {
+                        return Err(self.render_error(
+                            format!(
+                                "`{}` is a list or table, so it can't be written",
+                                path.name()
+                            ),
+                            path,
+                        ))
+                    }
+                },
+                Node::For { item, list, body } => {
+                    let items = match self.lookup(list, context, scopes)? {
+                        Value::Array(items) => items,
+                        Value::Null => Vec::new(),
+                        _ => {
+                            return Err(
+                                self.render_error(format!("`{}` is not a list", list.name()), list)
+                            )
+                        }
+                    };
+                    for value in items {
+                        scopes.push((item.clone(), value));
+                        let result = self.render_nodes(body, context, scopes, output);
+                        scopes.pop();
+                        result?;
+                    }
+                }
+                Node::If {
+                    condition,
+                    then,
+                    otherwise,
+                } => {
+                    let branch = if is_truthy(&self.lookup(condition, context, scopes)?) {
+                        then
+                    } else {
+                        otherwise
+                    };
+                    self.render_nodes(branch, context, scopes, output)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Find the value of `path`, checking loop variables (innermost first) before `context`.
+    fn lookup(
+        &self,
+        path: &Path,
+        context: &Value,
+        scopes: &[(String, Value)],
+    ) -> Result<Value, Error> {
+        let mut segments = path.segments.iter();
+        let first = segments.next().map(String::as_str).unwrap_or_default();
+        let value = scopes
+            .iter()
+            .rev()
+            .find_map(|(name, value)| (name == first).then_some(value))
+            .or_else(|| context.get(first));
+        segments
+            .fold(value, |value, segment| {
+                value.and_then(|value| value.get(segment))
+            })
+            .cloned()
+            .ok_or_else(|| self.render_error(format!("unknown variable `{}`", path.name()), path))
+    }
+
+    fn named_source(&self) -> NamedSource {
+        NamedSource::new(self.path.display().to_string(), self.source.clone())
+    }
+
+    fn syntax_error(&self, message: String, span: Range<usize>) -> Error {
+        Error::Syntax {
+            path: self.path.clone(),
+            message,
+            source_code: self.named_source(),
+            span: span.into(),
+        }
+    }
+
+    fn render_error(&self, message: String, path: &Path) -> Error {
+        Error::Render {
+            path: self.path.clone(),
+            message,
+            source_code: self.named_source(),
+            span: path.span.clone().into(),
+        }
+    }
+}
+
+fn is_identifier(word: &str) -> bool {
+    !word.is_empty() && word.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Whether a value counts as set in an `if`: not `null`, `false`, `0`, or empty.
+fn is_truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(value) => *value,
+        Value::Number(number) => number.as_f64().is_some_and(|number| number != 0.0),
+        Value::String(text) => !text.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(fields) => !fields.is_empty(),
+    }
+}
+
+#[derive(Debug, Diagnostic, Error)]
+pub(crate) enum Error {
+    #[error("Invalid template {path}: {message}")]
+    #[diagnostic(
+        code(template::syntax),
+        help(
+            "Templates can use `{{{{ variable }}}}`, `{{% for item in list %}}…{{% endfor %}}`, \
+            and `{{% if variable %}}…{{% else %}}…{{% endif %}}`."
+        ),
+        url("https://knope-dev.github.io/knope/config/packages.html#changelog_template")
+    )]
+    Syntax {
+        path: PathBuf,
+        message: String,
+        #[source_code]
+        source_code: NamedSource,
+        #[label("here")]
+        span: SourceSpan,
+    },
+    #[error("Could not render template {path}: {message}")]
+    #[diagnostic(
+        code(template::render),
+        help("Check the variables used in the template against the documented ones."),
+        url("https://knope-dev.github.io/knope/config/packages.html#changelog_template")
+    )]
+    Render {
+        path: PathBuf,
+        message: String,
+        #[source_code]
+        source_code: NamedSource,
+        #[label("here")]
+        span: SourceSpan,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    fn render(template: &str, context: &Value) -> Result<String, Error> {
+        Template::parse(PathBuf::from("test.md"), template.to_string())?.render(context)
+    }
+
+    #[test]
+    fn variables_and_blocks() {
+        let template = "\
+## {{ version }}
+{% for section in sections %}
+
+### {{ section.title }}
+{% for entry in section.entries %}
+- {{ entry.summary }}{% if entry.body %}: {{ entry.body }}{% else %}.{% endif %}
+
+{% endfor %}
+{% endfor %}
+";
+        let context = json!({
+            "version": "1.2.3",
+            "sections": [
+                {
+                    "title": "Features",
+                    "entries": [
+                        {"summary": "A feature", "body": "with details"},
+                        {"summary": "Another", "body": ""},
+                    ],
+                },
+                {"title": "Fixes", "entries": [{"summary": "A fix", "body": null}]},
+            ],
+        });
+        assert_eq!(
+            render(template, &context).unwrap(),
+            "## 1.2.3\n\n### Features\n- A feature: with details\n- Another.\n\n### Fixes\n- A fix.\n"
+        );
+    }
+
+    #[test]
+    fn syntax_errors() {
+        for template in [
+            "{{ version",
+            "{% for x in list %}",
+            "{% if x %}{% endfor %}",
+            "{% endif %}",
+            "{% while x %}",
+            "{{ not a variable }}",
+        ] {
+            let result = Template::parse(PathBuf::from("test.md"), template.to_string());
+            assert!(
+                matches!(result, Err(Error::Syntax { .. })),
+                "Expected a syntax error for {template}, got {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn error_spans() {
+        let template = "{% for  entry\tin sections.list- %}{% endfor %}";
+        let Err(Error::Syntax { span, .. }) =
+            Template::parse(PathBuf::from("test.md"), template.to_string())
+        else {
+            panic!("Expected a syntax error");
+        };
+        assert_eq!(span.offset(), template.find("sections").unwrap());
+        assert_eq!(span.len(), "sections.list-".len());
+
+        let template = "{% if  entry.body\t%}{% endif %}";
+        let Err(Error::Render { span, .. }) = render(template, &json!({})) else {
+            panic!("Expected a render error");
+        };
+        assert_eq!(span.offset(), template.find("entry").unwrap());
+        assert_eq!(span.len(), "entry.body".len());
+    }
+
+    #[test]
+    fn render_errors() {
+        let context = json!({"version": "1.2.3", "sections": []});
+        for template in [
+            "{{ missing }}",
+            "{{ sections }}",
+            "{% for x in version %}{% endfor %}",
+        ] {
+            let result = render(template, &context);
+            assert!(
+                matches!(result, Err(Error::Render { .. })),
+                "Expected a render error for {template}, got {result:?}"
+            );
+        }
+    }
+}
@@ -2,13 +2,19 @@ use std::{
     ffi::OsStr,
     fs::read_to_string,
     path::{Path, PathBuf},
+    str::FromStr,
 };
 
 use itertools::Itertools;
 use miette::Diagnostic;
 use thiserror::Error;
 
-use super::{cargo, git, go, package_json, pyproject, semver::Version};
+use super::{
+    cargo, git, go, gradle, helm, maven, msbuild, package_json,
+    pattern::{self, VersionPattern},
+    pubspec, pyproject,
+    semver::Version,
+};
 use crate::{dry_run::DryRun, workflow::Verbose};
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -67,6 +73,17 @@ enum ErrorKind {
         url("https://knope-dev.github.io/knope/config/packages.html#supported-formats-for-versioning")
     )]
     VersionedFileFormat(PathBuf),
+    #[error("The versioned file {path} does not support `{option}`")]
+    #[diagnostic(
+        code(versioned_file::unsupported_option),
+        help("`{option}` can only be set for {supported}"),
+        url("https://knope-dev.github.io/knope/config/packages.html#supported-formats-for-versioning")
+    )]
+    UnsupportedOption {
+        path: PathBuf,
+        option: &'static str,
+        supported: &'static str,
+    },
     #[error(transparent)]
     #[diagnostic(transparent)]
     Git(#[from] git::Error),
@@ -78,6 +95,24 @@ enum ErrorKind {
     Cargo(#[from] cargo::Error),
     #[error(transparent)]
     #[diagnostic(transparent)]
+    Gradle(#[from] gradle::Error),
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Helm(#[from] helm::Error),
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Maven(#[from] maven::Error),
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    MsBuild(#[from] msbuild::Error),
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Pattern(#[from] pattern::Error),
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Pubspec(#[from] pubspec::Error),
+    #[error(transparent)]
+    #[diagnostic(transparent)]
     PyProject(#[from] pyproject::Error),
     #[error(transparent)]
     #[diagnostic(transparent)]
@@ -97,14 +132,176 @@ impl VersionedFile {
                 .set_version(dry_run, self.content.clone(), version_str, &self.path)?;
         Ok(())
     }
+    /// Create a file of any format, whose version is found using `pattern`.
+    pub(crate) fn with_pattern(path: PathBuf, pattern: &str) -> Result<Self> {
+        let format = PackageFormat::Pattern(VersionPattern::from_str(pattern)?);
+        let content = read_to_string(&path).map_err(|e| ErrorKind::Io(path.clone(), e))?;
+        Ok(Self {
+            format,
+            path,
+            content,
+        })
+    }
+
+    /// Also increment the Android `versionCode` whenever the version is set.
+    ///
+    /// Only supported for Gradle build scripts.
+    pub(crate) fn increment_version_code(mut self) -> Result<Self> {
+        match &mut self.format {
+            PackageFormat::Gradle {
+                increment_version_code,
+            } => {
+                *increment_version_code = true;
+                Ok(self)
+            }
+            _ => Err(ErrorKind::UnsupportedOption {
+                path: self.path,
+                option: "increment_version_code",
+                supported: "`build.gradle` or `build.gradle.kts` files",
+            }
+            .into()),
+        }
+    }
+
+    /// Keep the `appVersion` of a Helm chart the same as its `version`.
+    ///
+    /// Only supported for `Chart.yaml` files.
+    pub(crate) fn app_version(mut self) -> Result<Self> {
+        match &mut self.format {
+            PackageFormat::Helm { app_version } => {
+                *app_version = true;
+                Ok(self)
+            }
+            _ => Err(ErrorKind::UnsupportedOption {
+                path: self.path,
+                option: "app_version",
+                supported: "`Chart.yaml` files",
+            }
+            .into()),
+        }
+    }
+
+    /// Also increment the build number whenever the version is set.
+    ///
+    /// Only supported for `pubspec.yaml` files.
+    pub(crate) fn increment_build_number(mut self) -> Result<Self> {
+        match &mut self.format {
+            PackageFormat::Pubspec {
+                increment_build_number,
+            } => {
+                *increment_build_number = true;
+                Ok(self)
+            }
+            _ => Err(ErrorKind::UnsupportedOption {
+                path: self.path,
+                option: "increment_build_number",
+                supported: "`pubspec.yaml` files",
+            }
+            .into()),
+        }
+    }
+
+    /// The `Cargo.lock` entries which need to change along with this file, if any.
+    pub(crate) fn locked_crates(&self) -> Option<cargo::LockedCrates> {
+        match self.format {
+            PackageFormat::Cargo => cargo::locked_crates(&self.content, &self.path),
+            _ => None,
+        }
+    }
+
+    /// Every path which [`Self::set_version`] modifies, starting with `self.path`.
+    pub(crate) fn paths(&self) -> Vec<PathBuf> {
+        let mut paths = vec![self.path.clone()];
+        if let PackageFormat::Cargo = self.format {
+            paths.extend(cargo::related_paths(&self.content, &self.path));
+        }
+        paths
+    }
+
+    /// The name of the package this file defines, for matching it against the dependencies of
+    /// other packages.
+    pub(crate) fn package_name(&self) -> Option<String> {
+        match self.format {
+            PackageFormat::Cargo => cargo::package_name(&self.content),
+            PackageFormat::JavaScript => package_json::package_name(&self.content),
+            PackageFormat::Poetry => pyproject::package_name(&self.content),
+            _ => None,
+        }
+    }
+
+    /// The names of the packages this file depends on, not counting development dependencies.
+    pub(crate) fn dependencies(&self) -> Vec<String> {
+        match self.format {
+            PackageFormat::Cargo => cargo::dependencies(&self.content),
+            PackageFormat::JavaScript => package_json::dependencies(&self.content),
+            PackageFormat::Poetry => pyproject::dependencies(&self.content),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Update the requirement on `dependency` to `new_version`, if this file has one.
+    pub(crate) fn set_dependency_version(
+        &mut self,
+        dry_run: DryRun,
+        dependency: &str,
+        new_version: &Version,
+    ) -> Result<()> {
+        let content = self.content.clone();
+        self.content = match self.format {
+            PackageFormat::Cargo => cargo::set_dependency_version(
+                dry_run,
+                content,
+                dependency,
+                &new_version.to_string(),
+                &self.path,
+            )?,
+            PackageFormat::JavaScript => package_json::set_dependency_version(
+                dry_run,
+                content,
+                dependency,
+                &new_version.to_string(),
+                &self.path,
+            )?,
+            PackageFormat::Poetry => pyproject::set_dependency_version(
+                dry_run,
+                content,
+                dependency,
+                new_version,
+                &self.path,
+            )?,
+            _ => content,
+        };
+        Ok(())
+    }
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub(crate) enum PackageFormat {
     Cargo,
     Go,
+    /// `build.gradle` or `build.gradle.kts`
+    Gradle {
+        /// Whether to bump the Android `versionCode` with every new version
+        increment_version_code: bool,
+    },
+    GradleProperties,
+    /// A Helm `Chart.yaml`
+    Helm {
+        /// Whether `appVersion` should be kept the same as `version`
+        app_version: bool,
+    },
     JavaScript,
+    Maven,
+    /// A .NET project file (like `.csproj`) or `Directory.Build.props`
+    MsBuild,
+    /// Any file, with the version found by a user-provided pattern
+    Pattern(VersionPattern),
     Poetry,
+    /// A Dart `pubspec.yaml`
+    Pubspec {
+        /// Whether to bump the build number (like `+45`) with every new version
+        increment_build_number: bool,
+    },
 }
 
 impl TryFrom<&PathBuf> for PackageFormat {
@@ -115,10 +312,17 @@ impl TryFrom<&PathBuf> for PackageFormat {
             .file_name()
             .and_then(OsStr::to_str)
             .ok_or_else(|| ErrorKind::NotAFile(path.clone()))?;
+        let is_msbuild_project = path
+            .extension()
+            .and_then(OsStr::to_str)
+            .is_some_and(|extension| msbuild::PROJECT_EXTENSIONS.contains(&extension));
+        if is_msbuild_project {
+            return Ok(PackageFormat::MsBuild);
+        }
         PACKAGE_FORMAT_FILE_NAMES
             .iter()
             .find_position(|&name| *name == file_name)
-            .and_then(|(pos, _)| ALL_PACKAGE_FORMATS.get(pos).copied())
+            .and_then(|(pos, _)| ALL_PACKAGE_FORMATS.get(pos).cloned())
             .ok_or_else(|| Error::from(ErrorKind::VersionedFileFormat(path.clone())))
     }
 }
@@ -127,7 +331,7 @@ impl PackageFormat {
     /// Get the version from `content` for package named `name` (if any name).
     /// `path` is used for error reporting.
     pub(crate) fn get_version(
-        self,
+        &self,
         content: &str,
         path: &Path,
         verbose: Verbose,
@@ -139,6 +343,12 @@ impl PackageFormat {
                     version,
                     source: path.display().to_string(),
                 }),
+            PackageFormat::Pubspec { .. } => pubspec::get_version(content, path)
+                .map_err(ErrorKind::Pubspec)
+                .map(|version| VersionFromSource {
+                    version,
+                    source: path.display().to_string(),
+                }),
             PackageFormat::Poetry => pyproject::get_version(content, path)
                 .map_err(ErrorKind::PyProject)
                 .map(|version| VersionFromSource {
@@ -151,6 +361,43 @@ impl PackageFormat {
                     version,
                     source: path.display().to_string(),
                 }),
+            PackageFormat::Maven => maven::get_version(content, path)
+                .map_err(ErrorKind::Maven)
+                .map(|version| VersionFromSource {
+                    version,
+                    source: path.display().to_string(),
+                }),
+            PackageFormat::Gradle { .. } => gradle::get_version(content, path)
+                .map_err(ErrorKind::Gradle)
+                .map(|version| VersionFromSource {
+                    version,
+                    source: path.display().to_string(),
+                }),
+            PackageFormat::Helm { app_version } => helm::get_version(content, path, *app_version)
+                .map_err(ErrorKind::Helm)
+                .map(|version| VersionFromSource {
+                    version,
+                    source: path.display().to_string(),
+                }),
+            PackageFormat::GradleProperties => gradle::get_properties_version(content, path)
+                .map_err(ErrorKind::Gradle)
+                .map(|version| VersionFromSource {
+                    version,
+                    source: path.display().to_string(),
+                }),
+            PackageFormat::MsBuild => msbuild::get_version(content, path)
+                .map_err(ErrorKind::MsBuild)
+                .map(|version| VersionFromSource {
+                    version,
+                    source: path.display().to_string(),
+                }),
+            PackageFormat::Pattern(pattern) => pattern
+                .get_version(content, path)
+                .map_err(ErrorKind::Pattern)
+                .map(|version| VersionFromSource {
+                    version,
+                    source: path.display().to_string(),
+                }),
             PackageFormat::Go => go::get_version(content, path, verbose).map_err(ErrorKind::Go),
         }
         .map_err(Error::from)
@@ -160,7 +407,7 @@ impl PackageFormat {
     ///
     /// `path` is only used for error reporting.
     pub(crate) fn set_version(
-        self,
+        &self,
         dry_run: DryRun,
         content: String,
         new_version: &Version,
@@ -171,14 +418,50 @@ impl PackageFormat {
                 cargo::set_version(dry_run, content, &new_version.to_string(), path)
                     .map_err(Error::from)
             }
+            PackageFormat::Pubspec {
+                increment_build_number,
+            } => pubspec::set_version(dry_run, content, new_version, path, *increment_build_number)
+                .map_err(Error::from),
             PackageFormat::Poetry => {
-                pyproject::set_version(dry_run, content, &new_version.to_string(), path)
-                    .map_err(Error::from)
+                pyproject::set_version(dry_run, content, new_version, path).map_err(Error::from)
             }
             PackageFormat::JavaScript => {
                 package_json::set_version(dry_run, &content, &new_version.to_string(), path)
                     .map_err(Error::from)
             }
+            PackageFormat::Maven => {
+                maven::set_version(dry_run, content, &new_version.to_string(), path)
+                    .map_err(Error::from)
+            }
+            PackageFormat::Gradle {
+                increment_version_code,
+            } => gradle::set_version(
+                dry_run,
+                content,
+                &new_version.to_string(),
+                path,
+                *increment_version_code,
+            )
+            .map_err(Error::from),
+            PackageFormat::Helm { app_version } => helm::set_version(
+                dry_run,
+                content,
+                &new_version.to_string(),
+                path,
+                *app_version,
+            )
+            .map_err(Error::from),
+            PackageFormat::GradleProperties => {
+                gradle::set_properties_version(dry_run, content, &new_version.to_string(), path)
+                    .map_err(Error::from)
+            }
+            PackageFormat::MsBuild => {
+                msbuild::set_version(dry_run, content, &new_version.to_string(), path)
+                    .map_err(Error::from)
+            }
+            PackageFormat::Pattern(pattern) => pattern
+                .set_version(dry_run, content, &new_version.to_string(), path)
+                .map_err(Error::from),
             PackageFormat::Go => {
                 go::set_version_in_file(dry_run, &content, new_version, path).map_err(Error::from)
             }
@@ -192,11 +475,35 @@ pub(crate) struct VersionFromSource {
     pub(crate) source: String,
 }
 
-const ALL_PACKAGE_FORMATS: [PackageFormat; 4] = [
+const ALL_PACKAGE_FORMATS: [PackageFormat; 11] = [
+    PackageFormat::Gradle {
+        increment_version_code: false,
+    },
+    PackageFormat::Gradle {
+        increment_version_code: false,
+    },
     PackageFormat::Cargo,
+    PackageFormat::Helm { app_version: false },
+    PackageFormat::MsBuild,
     PackageFormat::Go,
+    PackageFormat::GradleProperties,
     PackageFormat::JavaScript,
+    PackageFormat::Maven,
+    PackageFormat::Pubspec {
+        increment_build_number: false,
+    },
     PackageFormat::Poetry,
 ];
-pub(crate) const PACKAGE_FORMAT_FILE_NAMES: [&str; ALL_PACKAGE_FORMATS.len()] =
-    ["Cargo.toml", "go.mod", "package.json", "pyproject.toml"];
+pub(crate) const PACKAGE_FORMAT_FILE_NAMES: [&str; ALL_PACKAGE_FORMATS.len()] = [
+    "build.gradle",
+    "build.gradle.kts",
+    "Cargo.toml",
+    "Chart.yaml",
+    "Directory.Build.props",
+    "go.mod",
+    "gradle.properties",
+    "package.json",
+    "pom.xml",
+    "pubspec.yaml",
+    "pyproject.toml",
+];
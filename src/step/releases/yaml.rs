@@ -0,0 +1,33 @@
+//! Minimal, line-based YAML helpers which find values without reformatting the rest of the file.
+
+use std::ops::Range;
+
+/// Find the value of a top-level `key: value` in `content`, without quotes or trailing comments.
+///
+/// Only the first definition of `key` is considered.
+pub(crate) fn top_level_scalar(content: &str, key: &str) -> Option<Range<usize>> {
+    let mut offset = 0;
+    for line in content.split_inclusive('\n') {
+        let start = offset;
+        offset += line.len();
+        if let Some(range) = scalar_value(line, key) {
+            return Some(range.start + start..range.end + start);
+        }
+    }
+    None
+}
+
+/// If `line` is a top-level `key: value`, return the range of `value` without quotes or comments.
+fn scalar_value(line: &str, key: &str) -> Option<Range<usize>> {
+    let rest = line.strip_prefix(key)?.strip_prefix(':')?;
+    let value = rest.trim_start();
+    let value_start = line.len() - value.len();
+    let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'');
+    let (start, len) = if let Some(quote) = quote {
+        (1, value.get(1..)?.find(quote)?)
+    } else {
+        let end = value.find(" #").unwrap_or(value.len());
+        (0, value.get(..end)?.trim_end().len())
+    };
+    (len > 0).then_some(value_start + start..value_start + start + len)
+}
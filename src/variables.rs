@@ -201,6 +201,7 @@ mod test_replace_variables {
             github_config: None,
             issue: state::Issue::Selected(issue),
             packages: vec![package().0],
+            package_groups: Vec::new(),
             verbose: Verbose::No,
         };
 
@@ -232,7 +233,7 @@ mod test_replace_variables {
         let template = "blah $$ other blah".to_string();
         let mut variables = IndexMap::new();
         variables.insert("$$".to_string(), Variable::Version);
-        let state = State::new(None, None, vec![package().0], Verbose::No);
+        let state = State::new(None, None, vec![package().0], Vec::new(), Verbose::No);
 
         let result = replace_variables(
             Template {
@@ -261,7 +262,7 @@ mod test_replace_variables {
         let template = "blah $$ other blah".to_string();
         let mut variables = IndexMap::new();
         variables.insert("$$".to_string(), Variable::Version);
-        let mut state = State::new(None, None, vec![package().0], Verbose::No);
+        let mut state = State::new(None, None, vec![package().0], Vec::new(), Verbose::No);
         let version = Version::new(1, 2, 3, None);
         state.packages[0].prepared_release = Some(Release::new(None, version.clone()));
 
@@ -293,6 +294,7 @@ mod test_replace_variables {
             github_config: None,
             issue: state::Issue::Selected(issue),
             packages: Vec::new(),
+            package_groups: Vec::new(),
             verbose: Verbose::No,
         };
 
@@ -313,7 +315,7 @@ mod test_replace_variables {
         let template = "blah $$ other blah".to_string();
         let mut variables = IndexMap::new();
         variables.insert("$$".to_string(), Variable::ChangelogEntry);
-        let mut state = State::new(None, None, vec![package().0], Verbose::No);
+        let mut state = State::new(None, None, vec![package().0], Vec::new(), Verbose::No);
         let version = Version::new(1, 2, 3, None);
         let changelog_entry_body = "### Features\n#### Blah".to_string();
         state.packages[0].prepared_release =
@@ -350,7 +352,7 @@ mod test_replace_variables {
         let changelog_path = package.changelog.take().unwrap().path;
         write(&changelog_path, changelog_entry).unwrap();
         package.changelog = Some(changelog_path.try_into().unwrap()); // Have to reload content
-        let state = State::new(None, None, vec![package], Verbose::No);
+        let state = State::new(None, None, vec![package], Vec::new(), Verbose::No);
 
         let result = replace_variables(
             Template {
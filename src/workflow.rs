@@ -5,7 +5,13 @@ use miette::Diagnostic;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-use crate::{state::RunType, step, step::Step, State};
+use crate::{
+    integrations::git,
+    state::RunType,
+    step,
+    step::{releases::changelog::lint::Problem, Step},
+    State,
+};
 
 /// A workflow is basically the state machine to run for a single execution of knope.
 #[derive(Debug, Deserialize, Serialize)]
@@ -43,10 +49,26 @@ impl From<bool> for Verbose {
 
 /// A collection of errors from running with the `--validate` option.
 #[derive(Debug, Error, Diagnostic)]
-#[error("There are problems with the defined workflows")]
+#[error("There are problems with the {checked}")]
 pub struct ValidationErrorCollection {
+    /// What the errors are about, like "defined workflows"
+    checked: &'static str,
     #[related]
-    errors: Vec<Error>,
+    errors: Vec<ValidationError>,
+}
+
+/// A problem found by the `--validate` option.
+#[derive(Debug, Error, Diagnostic)]
+enum ValidationError {
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Workflow(#[from] Error),
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Changelog(#[from] Box<Problem>),
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Git(#[from] git::Error),
 }
 
 /// An error from running or validating a single workflow.
@@ -79,7 +101,7 @@ pub(crate) fn validate(
     workflows: Vec<Workflow>,
     state: State,
 ) -> Result<(), ValidationErrorCollection> {
-    let errors = workflows
+    let mut errors = workflows
         .into_iter()
         .filter_map(|workflow| {
             run(
@@ -90,14 +112,29 @@ pub(crate) fn validate(
                 },
             )
             .err()
+            .map(ValidationError::from)
         })
         .collect_vec();
+    let workflow_errors = errors.len();
 
-    if errors.is_empty() {
-        Ok(())
-    } else {
-        Err(ValidationErrorCollection { errors })
+    for package in &state.packages {
+        match package.lint_changelog(state.verbose) {
+            Ok(problems) => errors.extend(
+                problems
+                    .into_iter()
+                    .map(|problem| ValidationError::from(Box::new(problem))),
+            ),
+            Err(err) => errors.push(err.into()),
+        }
     }
+
+    let checked = match (workflow_errors, errors.len() - workflow_errors) {
+        (0, 0) => return Ok(()),
+        (_, 0) => "defined workflows",
+        (0, _) => "changelogs",
+        _ => "defined workflows and changelogs",
+    };
+    Err(ValidationErrorCollection { checked, errors })
 }
 
 impl std::fmt::Display for Workflow {
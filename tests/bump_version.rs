@@ -244,3 +244,46 @@ fn override_version_multiple_packages() {
         );
     }
 }
+
+/// Going from 0.x to 1.0.0, either explicitly or because the package doesn't follow the pre-1.0
+/// convention.
+#[rstest]
+#[case("graduate_knope.toml", "graduate")]
+#[case("no_pre_1_0_convention_knope.toml", "major")]
+fn pre_1_0(#[case] knope_toml: &str, #[case] workflow: &str) {
+    // Arrange.
+    let temp_dir = tempfile::tempdir().unwrap();
+    let temp_path = temp_dir.path();
+    init(temp_path);
+    commit(temp_path, "Initial commit");
+    tag(temp_path, "v0.4.2");
+    let source_path = Path::new("tests/bump_version/pre_1_0");
+
+    std::fs::copy(source_path.join(knope_toml), temp_path.join("knope.toml")).unwrap();
+    std::fs::write(
+        temp_path.join("Cargo.toml"),
+        "[package]\nversion = \"0.4.2\"\n",
+    )
+    .unwrap();
+
+    // Act.
+    let dry_run_assert = Command::new(cargo_bin!("knope"))
+        .arg(workflow)
+        .arg("--dry-run")
+        .current_dir(temp_dir.path())
+        .assert();
+    let actual_assert = Command::new(cargo_bin!("knope"))
+        .arg(workflow)
+        .current_dir(temp_dir.path())
+        .assert();
+
+    // Assert.
+    dry_run_assert
+        .success()
+        .stdout_eq("Would add the following to Cargo.toml: 1.0.0\n");
+    actual_assert.success().stdout_eq("");
+    assert_eq!(
+        read_to_string(temp_path.join("Cargo.toml")).unwrap(),
+        "[package]\nversion = \"1.0.0\"\n"
+    );
+}
@@ -121,3 +121,52 @@ fn multiple_packages() {
     }
     assert_eq!(get_tags(temp_path), vec!["first/v2.0.0", "second/v0.5.0"]);
 }
+
+/// Verify that each package's `tag_template` is used to find its last release and to tag the new one
+#[test]
+fn tag_template() {
+    // Arrange.
+    let temp_dir = tempfile::tempdir().unwrap();
+    let temp_path = temp_dir.path();
+    let source_path = Path::new("tests/git_release/tag_template");
+
+    init(temp_path);
+    commit(temp_path, "feat!: Existing breaking feature");
+    tag(temp_path, "v2.0.0"); // Doesn't match any template, so the breaking change is released
+    commit(temp_path, "feat: Existing feature");
+    tag(temp_path, "release-1.2.3");
+    tag(temp_path, "@scope/pkg@0.4.6");
+    commit(temp_path, "feat: New feature");
+
+    for file in ["knope.toml", "Cargo.toml", "package.json"] {
+        copy(source_path.join(file), temp_path.join(file)).unwrap();
+    }
+
+    // Act.
+    let dry_run_assert = Command::new(cargo_bin!("knope"))
+        .arg("release")
+        .arg("--dry-run")
+        .current_dir(temp_dir.path())
+        .assert();
+    let actual_assert = Command::new(cargo_bin!("knope"))
+        .arg("release")
+        .current_dir(temp_dir.path())
+        .assert();
+
+    // Assert.
+    dry_run_assert
+        .success()
+        .with_assert(assert())
+        .stdout_matches_path(source_path.join("dry_run_output.txt"));
+    actual_assert.success();
+    for file in ["Cargo.toml", "package.json"] {
+        assert().matches_path(
+            source_path.join(format!("EXPECTED_{file}")),
+            read_to_string(temp_path.join(file)).unwrap(),
+        );
+    }
+    assert_eq!(
+        get_tags(temp_path),
+        vec!["@scope/pkg@0.4.7", "release-1.3.0"]
+    );
+}
@@ -214,6 +214,34 @@ fn no_previous_tag() {
         .stdout_matches_path(source_path.join("dry_run_output.txt"));
 }
 
+#[test]
+fn keep_a_changelog() {
+    // Arrange a package with release notes written in the Keep a Changelog format
+    let temp_dir = tempfile::tempdir().unwrap();
+    let temp_path = temp_dir.path();
+    let source_path = Path::new("tests/github_release/keep_a_changelog");
+    init(temp_path);
+    commit(temp_path, "feat: Existing feature");
+    tag(temp_path, "v1.0.0");
+    commit(temp_path, "fix: A fix");
+    for file in ["knope.toml", "CHANGELOG.md", "Cargo.toml"] {
+        copy(source_path.join(file), temp_path.join(file)).unwrap();
+    }
+
+    // Run the actual release (but dry-run because don't test GitHub)
+    let dry_run_assert = Command::new(cargo_bin!("knope"))
+        .arg("release")
+        .arg("--dry-run")
+        .current_dir(temp_dir.path())
+        .assert();
+
+    // Assert.
+    dry_run_assert
+        .success()
+        .with_assert(assert())
+        .stdout_matches_path(source_path.join("dry_run_output.txt"));
+}
+
 #[test]
 fn version_go_mod() {
     // Arrange a package that is ready to release, but hasn't been released yet
@@ -60,6 +60,22 @@ pub fn add_remote(path: &Path, remote: &str) {
     );
 }
 
+/// Clone the Git repo at `from` into `to`, keeping only the last `depth` commits.
+pub fn shallow_clone(from: &Path, to: &Path, depth: usize) {
+    let output = Command::new("git")
+        .arg("clone")
+        .arg(format!("--depth={depth}"))
+        .arg(format!("file://{}", from.display()))
+        .arg(to)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
 /// Create a commit with `message` in the Git repo which exists in `path`.
 pub fn commit(path: &Path, message: &str) {
     let output = Command::new("git")
@@ -92,6 +108,38 @@ pub fn tag(path: &Path, label: &str) {
     );
 }
 
+/// Revert a commit with the default message from `git revert`
+pub fn revert(path: &Path, revision: &str) {
+    let output = Command::new("git")
+        .arg("revert")
+        .arg("--no-edit")
+        .arg(revision)
+        .current_dir(path)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+/// The full ID of the commit at `HEAD`
+pub fn head_id(path: &Path) -> String {
+    let output = Command::new("git")
+        .arg("rev-parse")
+        .arg("HEAD")
+        .current_dir(path)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap().trim().to_string()
+}
+
 /// Create and switch to a new branch
 pub fn create_branch(path: &Path, name: &str) {
     let output = Command::new("git")
@@ -1,5 +1,5 @@
 use std::{
-    fs::{copy, create_dir, read_to_string, write},
+    fs::{copy, create_dir, create_dir_all, read_to_string, write},
     path::Path,
     thread::sleep,
     time::Duration,
@@ -10,6 +10,7 @@ use helpers::*;
 use pretty_assertions::assert_eq;
 use rstest::rstest;
 use snapbox::cmd::{cargo_bin, Command};
+use time::{macros::format_description, OffsetDateTime};
 
 mod helpers;
 
@@ -509,6 +510,793 @@ fn prepare_release_pyproject_toml(#[case] input_file: &str) {
     );
 }
 
+/// Run a `PrepareRelease` on a member of a Cargo workspace which inherits its version, keeping
+/// `Cargo.lock` in sync.
+#[test]
+fn prepare_release_cargo_workspace() {
+    // Arrange.
+    let temp_dir = tempfile::tempdir().unwrap();
+    let temp_path = temp_dir.path();
+    let source_path = Path::new("tests/prepare_release/cargo_workspace");
+
+    init(temp_path);
+    for dir in ["crates", "crates/first", "crates/second"] {
+        create_dir(temp_path.join(dir)).unwrap();
+    }
+    for file in [
+        "knope.toml",
+        "Cargo.toml",
+        "Cargo.lock",
+        "crates/first/Cargo.toml",
+        "crates/second/Cargo.toml",
+    ] {
+        copy(source_path.join(file), temp_path.join(file)).unwrap();
+    }
+    add_all(temp_path);
+    commit(temp_path, "feat: Existing feature");
+    tag(temp_path, "v1.0.0");
+    commit(temp_path, "feat!: New feature");
+
+    // Act.
+    let dry_run_assert = Command::new(cargo_bin!("knope"))
+        .arg("release")
+        .arg("--dry-run")
+        .current_dir(temp_dir.path())
+        .assert();
+    let actual_assert = Command::new(cargo_bin!("knope"))
+        .arg("release")
+        .current_dir(temp_dir.path())
+        .assert();
+
+    // Assert.
+    dry_run_assert
+        .success()
+        .with_assert(assert())
+        .stdout_matches_path(source_path.join("dry_run_output.txt"));
+    actual_assert.success().stdout_eq("");
+    assert().matches_path(
+        source_path.join("EXPECTED_Cargo.toml"),
+        read_to_string(temp_path.join("Cargo.toml")).unwrap(),
+    );
+    assert().matches_path(
+        source_path.join("crates/second/EXPECTED_Cargo.toml"),
+        read_to_string(temp_path.join("crates/second/Cargo.toml")).unwrap(),
+    );
+    assert().matches_path(
+        source_path.join("EXPECTED_Cargo.lock"),
+        read_to_string(temp_path.join("Cargo.lock")).unwrap(),
+    );
+
+    let expected_changes = [
+        "M  Cargo.lock",
+        "M  Cargo.toml",
+        "M  crates/second/Cargo.toml",
+    ];
+    assert_eq!(
+        status(temp_path),
+        expected_changes,
+        "All modified changes should be added to Git"
+    );
+}
+
+/// Run a `PrepareRelease` on a multi-module Maven project.
+#[test]
+fn prepare_release_maven() {
+    // Arrange.
+    let temp_dir = tempfile::tempdir().unwrap();
+    let temp_path = temp_dir.path();
+    let source_path = Path::new("tests/prepare_release/maven");
+
+    init(temp_path);
+    create_dir(temp_path.join("module")).unwrap();
+    for file in ["knope.toml", "pom.xml", "module/pom.xml"] {
+        copy(source_path.join(file), temp_path.join(file)).unwrap();
+    }
+    add_all(temp_path);
+    commit(temp_path, "feat: Existing feature");
+    tag(temp_path, "v1.0.0");
+    commit(temp_path, "feat!: New feature");
+
+    // Act.
+    let dry_run_assert = Command::new(cargo_bin!("knope"))
+        .arg("release")
+        .arg("--dry-run")
+        .current_dir(temp_dir.path())
+        .assert();
+    let actual_assert = Command::new(cargo_bin!("knope"))
+        .arg("release")
+        .current_dir(temp_dir.path())
+        .assert();
+
+    // Assert.
+    dry_run_assert
+        .success()
+        .with_assert(assert())
+        .stdout_matches_path(source_path.join("dry_run_output.txt"));
+    actual_assert.success().stdout_eq("");
+    assert().matches_path(
+        source_path.join("EXPECTED_pom.xml"),
+        read_to_string(temp_path.join("pom.xml")).unwrap(),
+    );
+    assert().matches_path(
+        source_path.join("module/EXPECTED_pom.xml"),
+        read_to_string(temp_path.join("module/pom.xml")).unwrap(),
+    );
+
+    let expected_changes = ["M  module/pom.xml", "M  pom.xml"];
+    assert_eq!(
+        status(temp_path),
+        expected_changes,
+        "All modified changes should be added to Git"
+    );
+}
+
+#[test]
+fn prepare_release_gradle() {
+    // Arrange.
+    let temp_dir = tempfile::tempdir().unwrap();
+    let temp_path = temp_dir.path();
+    let source_path = Path::new("tests/prepare_release/gradle");
+
+    init(temp_path);
+    create_dir(temp_path.join("app")).unwrap();
+    for file in ["knope.toml", "gradle.properties", "app/build.gradle.kts"] {
+        copy(source_path.join(file), temp_path.join(file)).unwrap();
+    }
+    add_all(temp_path);
+    commit(temp_path, "feat: Existing feature");
+    tag(temp_path, "v1.0.0");
+    commit(temp_path, "feat: New feature");
+
+    // Act.
+    let dry_run_assert = Command::new(cargo_bin!("knope"))
+        .arg("release")
+        .arg("--dry-run")
+        .current_dir(temp_dir.path())
+        .assert();
+    let actual_assert = Command::new(cargo_bin!("knope"))
+        .arg("release")
+        .current_dir(temp_dir.path())
+        .assert();
+
+    // Assert.
+    dry_run_assert
+        .success()
+        .with_assert(assert())
+        .stdout_matches_path(source_path.join("dry_run_output.txt"));
+    actual_assert.success().stdout_eq("");
+    assert().matches_path(
+        source_path.join("EXPECTED_gradle.properties"),
+        read_to_string(temp_path.join("gradle.properties")).unwrap(),
+    );
+    assert().matches_path(
+        source_path.join("app/EXPECTED_build.gradle.kts"),
+        read_to_string(temp_path.join("app/build.gradle.kts")).unwrap(),
+    );
+
+    let expected_changes = ["M  app/build.gradle.kts", "M  gradle.properties"];
+    assert_eq!(
+        status(temp_path),
+        expected_changes,
+        "All modified changes should be added to Git"
+    );
+}
+
+#[test]
+fn prepare_release_pattern() {
+    // Arrange.
+    let temp_dir = tempfile::tempdir().unwrap();
+    let temp_path = temp_dir.path();
+    let source_path = Path::new("tests/prepare_release/pattern");
+
+    init(temp_path);
+    for file in ["knope.toml", "VERSION", "__init__.py"] {
+        copy(source_path.join(file), temp_path.join(file)).unwrap();
+    }
+    add_all(temp_path);
+    commit(temp_path, "feat: Existing feature");
+    tag(temp_path, "v1.0.0");
+    commit(temp_path, "feat: New feature");
+
+    // Act.
+    let dry_run_assert = Command::new(cargo_bin!("knope"))
+        .arg("release")
+        .arg("--dry-run")
+        .current_dir(temp_dir.path())
+        .assert();
+    let actual_assert = Command::new(cargo_bin!("knope"))
+        .arg("release")
+        .current_dir(temp_dir.path())
+        .assert();
+
+    // Assert.
+    dry_run_assert
+        .success()
+        .with_assert(assert())
+        .stdout_matches_path(source_path.join("dry_run_output.txt"));
+    actual_assert.success().stdout_eq("");
+    for file in ["VERSION", "__init__.py"] {
+        assert().matches_path(
+            source_path.join(format!("EXPECTED_{file}")),
+            read_to_string(temp_path.join(file)).unwrap(),
+        );
+    }
+
+    let expected_changes = ["M  VERSION", "M  __init__.py"];
+    assert_eq!(
+        status(temp_path),
+        expected_changes,
+        "All modified changes should be added to Git"
+    );
+}
+
+/// Snapshot the error when a versioned file pattern has no `version` capture group.
+#[test]
+fn prepare_release_pattern_missing_capture() {
+    // Arrange.
+    let temp_dir = tempfile::tempdir().unwrap();
+    let temp_path = temp_dir.path();
+    let source_path = Path::new("tests/prepare_release/pattern");
+
+    init(temp_path);
+    copy(source_path.join("VERSION"), temp_path.join("VERSION")).unwrap();
+    copy(
+        source_path.join("missing_capture_knope.toml"),
+        temp_path.join("knope.toml"),
+    )
+    .unwrap();
+
+    // Act.
+    let actual_assert = Command::new(cargo_bin!("knope"))
+        .arg("release")
+        .current_dir(temp_dir.path())
+        .assert();
+
+    // Assert.
+    actual_assert
+        .failure()
+        .stderr_eq_path(source_path.join("missing_capture_output.txt"));
+}
+
+#[test]
+fn prepare_release_helm() {
+    // Arrange.
+    let temp_dir = tempfile::tempdir().unwrap();
+    let temp_path = temp_dir.path();
+    let source_path = Path::new("tests/prepare_release/helm");
+
+    init(temp_path);
+    create_dir(temp_path.join("chart")).unwrap();
+    for file in ["knope.toml", "Cargo.toml", "chart/Chart.yaml"] {
+        copy(source_path.join(file), temp_path.join(file)).unwrap();
+    }
+    add_all(temp_path);
+    commit(temp_path, "feat: Existing feature");
+    tag(temp_path, "v1.0.0");
+    commit(temp_path, "feat!: New feature");
+
+    // Act.
+    let dry_run_assert = Command::new(cargo_bin!("knope"))
+        .arg("release")
+        .arg("--dry-run")
+        .current_dir(temp_dir.path())
+        .assert();
+    let actual_assert = Command::new(cargo_bin!("knope"))
+        .arg("release")
+        .current_dir(temp_dir.path())
+        .assert();
+
+    // Assert.
+    dry_run_assert
+        .success()
+        .with_assert(assert())
+        .stdout_matches_path(source_path.join("dry_run_output.txt"));
+    actual_assert.success().stdout_eq("");
+    assert().matches_path(
+        source_path.join("EXPECTED_Cargo.toml"),
+        read_to_string(temp_path.join("Cargo.toml")).unwrap(),
+    );
+    assert().matches_path(
+        source_path.join("chart/EXPECTED_Chart.yaml"),
+        read_to_string(temp_path.join("chart/Chart.yaml")).unwrap(),
+    );
+
+    let expected_changes = ["M  Cargo.toml", "M  chart/Chart.yaml"];
+    assert_eq!(
+        status(temp_path),
+        expected_changes,
+        "All modified changes should be added to Git"
+    );
+}
+
+#[test]
+fn prepare_release_msbuild() {
+    // Arrange.
+    let temp_dir = tempfile::tempdir().unwrap();
+    let temp_path = temp_dir.path();
+    let source_path = Path::new("tests/prepare_release/msbuild");
+
+    init(temp_path);
+    create_dir(temp_path.join("src")).unwrap();
+    for file in [
+        "knope.toml",
+        "Directory.Build.props",
+        "src/MyLibrary.csproj",
+    ] {
+        copy(source_path.join(file), temp_path.join(file)).unwrap();
+    }
+    add_all(temp_path);
+    commit(temp_path, "feat: Existing feature");
+    tag(temp_path, "v1.0.0");
+    commit(temp_path, "feat: New feature");
+
+    // Act.
+    let dry_run_assert = Command::new(cargo_bin!("knope"))
+        .arg("prerelease")
+        .arg("--dry-run")
+        .current_dir(temp_dir.path())
+        .assert();
+    let actual_assert = Command::new(cargo_bin!("knope"))
+        .arg("prerelease")
+        .current_dir(temp_dir.path())
+        .assert();
+
+    // Assert.
+    dry_run_assert
+        .success()
+        .with_assert(assert())
+        .stdout_matches_path(source_path.join("dry_run_output.txt"));
+    actual_assert.success().stdout_eq("");
+    assert().matches_path(
+        source_path.join("EXPECTED_Directory.Build.props"),
+        read_to_string(temp_path.join("Directory.Build.props")).unwrap(),
+    );
+    assert().matches_path(
+        source_path.join("src/EXPECTED_MyLibrary.csproj"),
+        read_to_string(temp_path.join("src/MyLibrary.csproj")).unwrap(),
+    );
+
+    let expected_changes = ["M  Directory.Build.props", "M  src/MyLibrary.csproj"];
+    assert_eq!(
+        status(temp_path),
+        expected_changes,
+        "All modified changes should be added to Git"
+    );
+}
+
+#[test]
+fn prepare_release_pubspec() {
+    // Arrange.
+    let temp_dir = tempfile::tempdir().unwrap();
+    let temp_path = temp_dir.path();
+    let source_path = Path::new("tests/prepare_release/pubspec");
+
+    init(temp_path);
+    for file in ["knope.toml", "pubspec.yaml"] {
+        copy(source_path.join(file), temp_path.join(file)).unwrap();
+    }
+    add_all(temp_path);
+    commit(temp_path, "feat: Existing feature");
+    tag(temp_path, "v1.0.0");
+    commit(temp_path, "feat: New feature");
+
+    // Act.
+    let dry_run_assert = Command::new(cargo_bin!("knope"))
+        .arg("release")
+        .arg("--dry-run")
+        .current_dir(temp_dir.path())
+        .assert();
+    let actual_assert = Command::new(cargo_bin!("knope"))
+        .arg("release")
+        .current_dir(temp_dir.path())
+        .assert();
+
+    // Assert.
+    dry_run_assert
+        .success()
+        .with_assert(assert())
+        .stdout_matches_path(source_path.join("dry_run_output.txt"));
+    actual_assert.success().stdout_eq("");
+    assert().matches_path(
+        source_path.join("EXPECTED_pubspec.yaml"),
+        read_to_string(temp_path.join("pubspec.yaml")).unwrap(),
+    );
+
+    let expected_changes = ["M  pubspec.yaml"];
+    assert_eq!(
+        status(temp_path),
+        expected_changes,
+        "All modified changes should be added to Git"
+    );
+}
+
+/// Tags with build metadata are recognized, and the configured build metadata is attached to the
+/// new version.
+#[test]
+fn prepare_release_build_metadata() {
+    // Arrange.
+    let temp_dir = tempfile::tempdir().unwrap();
+    let temp_path = temp_dir.path();
+    let source_path = Path::new("tests/prepare_release/build_metadata");
+
+    init(temp_path);
+    for file in ["knope.toml", "Cargo.toml", "CHANGELOG.md"] {
+        copy(source_path.join(file), temp_path.join(file)).unwrap();
+    }
+    add_all(temp_path);
+    commit(temp_path, "feat!: Breaking change in existing release");
+    tag(temp_path, "v1.2.3+20240101");
+    commit(temp_path, "feat: New feature");
+
+    // Act.
+    let dry_run_assert = Command::new(cargo_bin!("knope"))
+        .arg("release")
+        .arg("--dry-run")
+        .current_dir(temp_dir.path())
+        .assert();
+    let actual_assert = Command::new(cargo_bin!("knope"))
+        .arg("release")
+        .current_dir(temp_dir.path())
+        .assert();
+
+    // Assert.
+    dry_run_assert
+        .success()
+        .with_assert(assert())
+        .stdout_matches_path(source_path.join("dry_run_output.txt"));
+    actual_assert.success().stdout_eq("");
+    assert().matches_path(
+        source_path.join("EXPECTED_Cargo.toml"),
+        read_to_string(temp_path.join("Cargo.toml")).unwrap(),
+    );
+    assert().matches_path(
+        source_path.join("EXPECTED_CHANGELOG.md"),
+        read_to_string(temp_path.join("CHANGELOG.md")).unwrap(),
+    );
+
+    let tags = get_tags(temp_path);
+    assert!(
+        matches!(tags.as_slice(), [tag] if tag.starts_with("v1.3.0+") && tag.len() == "v1.3.0+".len() + 7),
+        "Expected a v1.3.0 tag with a short SHA, found {tags:?}"
+    );
+}
+
+/// With `versioning = "calver"`, the version is based on today's date—and the release counter
+/// continues from the latest release of the current month.
+#[test]
+fn prepare_release_calver() {
+    // Arrange.
+    let temp_dir = tempfile::tempdir().unwrap();
+    let temp_path = temp_dir.path();
+    let source_path = Path::new("tests/prepare_release/calver");
+    let today = time::OffsetDateTime::now_utc().date();
+    let this_month = format!("{}.{}", today.year(), u8::from(today.month()));
+
+    init(temp_path);
+    copy(source_path.join("knope.toml"), temp_path.join("knope.toml")).unwrap();
+    write(
+        temp_path.join("Cargo.toml"),
+        format!("[package]\nname = \"calver\"\nversion = \"{this_month}.3\"\n"),
+    )
+    .unwrap();
+    add_all(temp_path);
+    commit(temp_path, "fix: Existing fix");
+    tag(temp_path, "v2020.1.0");
+    tag(temp_path, &format!("v{this_month}.3"));
+    commit(temp_path, "feat!: Breaking change");
+
+    // Act.
+    let dry_run_assert = Command::new(cargo_bin!("knope"))
+        .arg("release")
+        .arg("--dry-run")
+        .current_dir(temp_dir.path())
+        .assert();
+
+    // Assert.
+    dry_run_assert.success().stdout_eq(format!(
+        "Would add the following to Cargo.toml: {this_month}.4\n\
+        Would add files to git:\n  Cargo.toml\n\
+        Would create Git tag v{this_month}.4\n"
+    ));
+}
+
+/// Python packages are written with PEP 440 versions, and tags in that format are recognized.
+#[test]
+fn prepare_release_pep440() {
+    // Arrange.
+    let temp_dir = tempfile::tempdir().unwrap();
+    let temp_path = temp_dir.path();
+    let source_path = Path::new("tests/prepare_release/pep440");
+
+    init(temp_path);
+    for file in ["knope.toml", "pyproject.toml"] {
+        copy(source_path.join(file), temp_path.join(file)).unwrap();
+    }
+    add_all(temp_path);
+    commit(temp_path, "feat: Existing feature");
+    tag(temp_path, "v1.0.0");
+    tag(temp_path, "v1.1.0rc0");
+    commit(temp_path, "feat: New feature");
+
+    // Act.
+    let dry_run_assert = Command::new(cargo_bin!("knope"))
+        .arg("prerelease")
+        .arg("--dry-run")
+        .current_dir(temp_dir.path())
+        .assert();
+    let actual_assert = Command::new(cargo_bin!("knope"))
+        .arg("prerelease")
+        .current_dir(temp_dir.path())
+        .assert();
+
+    // Assert.
+    dry_run_assert
+        .success()
+        .with_assert(assert())
+        .stdout_matches_path(source_path.join("dry_run_output.txt"));
+    actual_assert.success().stdout_eq("");
+    assert().matches_path(
+        source_path.join("EXPECTED_pyproject.toml"),
+        read_to_string(temp_path.join("pyproject.toml")).unwrap(),
+    );
+    assert_eq!(get_tags(temp_path), ["v1.1.0-rc.1"]);
+}
+
+/// Only Python packages read tags as PEP 440 versions, so a floating tag like `v1` isn't mistaken
+/// for version 1.0.0 of other packages.
+#[test]
+fn floating_tag() {
+    // Arrange.
+    let temp_dir = tempfile::tempdir().unwrap();
+    let temp_path = temp_dir.path();
+    let source_path = Path::new("tests/prepare_release/floating_tag");
+
+    init(temp_path);
+    for file in ["knope.toml", "Cargo.toml"] {
+        copy(source_path.join(file), temp_path.join(file)).unwrap();
+    }
+    add_all(temp_path);
+    commit(temp_path, "feat: Existing feature");
+    tag(temp_path, "v1.2.0");
+    commit(temp_path, "fix: New fix");
+    tag(temp_path, "v1");
+
+    // Act.
+    let dry_run_assert = Command::new(cargo_bin!("knope"))
+        .arg("release")
+        .arg("--dry-run")
+        .current_dir(temp_dir.path())
+        .assert();
+
+    // Assert.
+    dry_run_assert
+        .success()
+        .with_assert(assert())
+        .stdout_matches_path(source_path.join("dry_run_output.txt"));
+}
+
+/// Fixed groups release every member with the same version, linked groups share the largest bump
+/// between the members which changed.
+#[test]
+fn package_groups() {
+    // Arrange.
+    let temp_dir = tempfile::tempdir().unwrap();
+    let temp_path = temp_dir.path();
+    let source_path = Path::new("tests/prepare_release/package_groups");
+
+    init(temp_path);
+    commit(temp_path, "Initial commit");
+    commit(temp_path, "feat(first): A feature for first");
+    commit(temp_path, "fix(third): A fix for third");
+    commit(temp_path, "feat(fourth)!: A breaking change for fourth");
+    let files = [
+        "knope.toml",
+        "first/package.json",
+        "first/CHANGELOG.md",
+        "second/package.json",
+        "second/CHANGELOG.md",
+        "third/package.json",
+        "fourth/package.json",
+        "fifth/package.json",
+    ];
+    for file in files {
+        let destination = temp_path.join(file);
+        create_dir_all(destination.parent().unwrap()).unwrap();
+        copy(source_path.join(file), destination).unwrap();
+    }
+
+    // Act.
+    let dry_run_assert = Command::new(cargo_bin!("knope"))
+        .arg("release")
+        .arg("--dry-run")
+        .current_dir(temp_dir.path())
+        .assert();
+    let actual_assert = Command::new(cargo_bin!("knope"))
+        .arg("release")
+        .current_dir(temp_dir.path())
+        .assert();
+
+    // Assert.
+    dry_run_assert
+        .success()
+        .with_assert(assert())
+        .stdout_matches_path(source_path.join("dry_run_output.txt"));
+    actual_assert.success().stdout_eq("");
+    for file in files.into_iter().skip(1) {
+        let (directory, file_name) = file.split_once('/').unwrap();
+        assert().matches_path(
+            source_path
+                .join(directory)
+                .join(format!("EXPECTED_{file_name}")),
+            read_to_string(temp_path.join(file)).unwrap(),
+        );
+    }
+}
+
+/// Commits are assigned to packages by the files they change when packages define `paths`.
+#[test]
+fn package_paths() {
+    // Arrange.
+    let temp_dir = tempfile::tempdir().unwrap();
+    let temp_path = temp_dir.path();
+    let source_path = Path::new("tests/prepare_release/package_paths");
+
+    init(temp_path);
+    let files = [
+        "knope.toml",
+        "core/package.json",
+        "core/CHANGELOG.md",
+        "app/package.json",
+        "app/CHANGELOG.md",
+    ];
+    for file in files {
+        let destination = temp_path.join(file);
+        create_dir_all(destination.parent().unwrap()).unwrap();
+        copy(source_path.join(file), destination).unwrap();
+    }
+    add_all(temp_path);
+    commit(temp_path, "Initial commit");
+    tag(temp_path, "core/v1.0.0");
+    tag(temp_path, "app/v1.0.0");
+    create_dir_all(temp_path.join("core/src")).unwrap();
+    write(temp_path.join("core/src/lib.js"), "export {}").unwrap();
+    add_all(temp_path);
+    commit(temp_path, "feat: A feature in core");
+    create_dir_all(temp_path.join("app/src")).unwrap();
+    write(temp_path.join("app/src/main.js"), "export {}").unwrap();
+    add_all(temp_path);
+    commit(temp_path, "fix(core): A fix in app with the wrong scope");
+    write(temp_path.join("README.md"), "# Readme").unwrap();
+    add_all(temp_path);
+    commit(temp_path, "feat: A feature outside of every package");
+    commit(temp_path, "fix(app): A scoped fix for app");
+
+    // Act.
+    let dry_run_assert = Command::new(cargo_bin!("knope"))
+        .arg("release")
+        .arg("--dry-run")
+        .current_dir(temp_dir.path())
+        .assert();
+    let actual_assert = Command::new(cargo_bin!("knope"))
+        .arg("release")
+        .current_dir(temp_dir.path())
+        .assert();
+
+    // Assert.
+    dry_run_assert
+        .success()
+        .with_assert(assert())
+        .stdout_matches_path(source_path.join("dry_run_output.txt"));
+    actual_assert.success().stdout_eq("");
+    for file in files.into_iter().skip(1) {
+        let (directory, file_name) = file.split_once('/').unwrap();
+        assert().matches_path(
+            source_path
+                .join(directory)
+                .join(format!("EXPECTED_{file_name}")),
+            read_to_string(temp_path.join(file)).unwrap(),
+        );
+    }
+}
+
+/// In a shallow clone, the oldest commit has no parent to compare against, so it doesn't match
+/// any package `paths`—but that doesn't stop the release.
+#[test]
+fn shallow_clone_with_paths() {
+    // Arrange.
+    let source_dir = tempfile::tempdir().unwrap();
+    let source = source_dir.path();
+    let temp_dir = tempfile::tempdir().unwrap();
+    let temp_path = temp_dir.path().join("clone");
+    let source_path = Path::new("tests/prepare_release/shallow_clone");
+
+    init(source);
+    for file in ["knope.toml", "package.json", "CHANGELOG.md"] {
+        copy(source_path.join(file), source.join(file)).unwrap();
+    }
+    add_all(source);
+    commit(source, "Initial commit");
+    tag(source, "v1.0.0");
+    create_dir(source.join("src")).unwrap();
+    write(source.join("src/feature.js"), "export {}").unwrap();
+    add_all(source);
+    commit(source, "feat: A feature at the edge of the clone");
+    write(source.join("src/fix.js"), "export {}").unwrap();
+    add_all(source);
+    commit(source, "fix: A fix in the package");
+    write(source.join("README.md"), "# Readme").unwrap();
+    add_all(source);
+    commit(source, "feat: A feature outside of the package");
+    shallow_clone(source, &temp_path, 3);
+
+    // Act.
+    let dry_run_assert = Command::new(cargo_bin!("knope"))
+        .arg("release")
+        .arg("--dry-run")
+        .current_dir(&temp_path)
+        .assert();
+
+    // Assert.
+    dry_run_assert
+        .success()
+        .with_assert(assert())
+        .stdout_matches_path(source_path.join("dry_run_output.txt"));
+}
+
+/// Packages which depend on a released package are released too, with their requirement updated.
+#[test]
+fn dependencies() {
+    // Arrange.
+    let temp_dir = tempfile::tempdir().unwrap();
+    let temp_path = temp_dir.path();
+    let source_path = Path::new("tests/prepare_release/dependencies");
+
+    init(temp_path);
+    commit(temp_path, "Initial commit");
+    commit(temp_path, "feat(core): A feature for core");
+    let files = [
+        "knope.toml",
+        "core/Cargo.toml",
+        "core/CHANGELOG.md",
+        "app/Cargo.toml",
+        "app/CHANGELOG.md",
+        "cli/Cargo.toml",
+        "docs/package.json",
+    ];
+    for file in files {
+        let destination = temp_path.join(file);
+        create_dir_all(destination.parent().unwrap()).unwrap();
+        copy(source_path.join(file), destination).unwrap();
+    }
+
+    // Act.
+    let dry_run_assert = Command::new(cargo_bin!("knope"))
+        .arg("release")
+        .arg("--dry-run")
+        .current_dir(temp_dir.path())
+        .assert();
+    let actual_assert = Command::new(cargo_bin!("knope"))
+        .arg("release")
+        .current_dir(temp_dir.path())
+        .assert();
+
+    // Assert.
+    dry_run_assert
+        .success()
+        .with_assert(assert())
+        .stdout_matches_path(source_path.join("dry_run_output.txt"));
+    actual_assert.success().stdout_eq("");
+    for file in files.into_iter().skip(1) {
+        let (directory, file_name) = file.split_once('/').unwrap();
+        assert().matches_path(
+            source_path
+                .join(directory)
+                .join(format!("EXPECTED_{file_name}")),
+            read_to_string(temp_path.join(file)).unwrap(),
+        );
+    }
+}
+
 /// Snapshot the error messages when a required file is missing.
 #[rstest]
 #[case("Cargo.toml_knope.toml")]
@@ -1546,6 +2334,191 @@ fn notes() {
     );
 }
 
+/// `commit_types` decide how commits of other types bump versions and where they go in the changelog.
+#[test]
+fn commit_types() {
+    // Arrange.
+    let temp_dir = tempfile::tempdir().unwrap();
+    let temp_path = temp_dir.path();
+    let source_path = Path::new("tests/prepare_release/commit_types");
+    init(temp_path);
+    commit(temp_path, "Initial commit");
+    tag(temp_path, "v1.2.3");
+    commit(temp_path, "refactor: Reorganize modules");
+    commit(temp_path, "docs: Not in the changelog");
+    for file in ["knope.toml", "Cargo.toml", "CHANGELOG.md"] {
+        copy(source_path.join(file), temp_path.join(file)).unwrap();
+    }
+
+    // Changes which don't bump the version can't cause a release on their own.
+    Command::new(cargo_bin!("knope"))
+        .arg("release")
+        .current_dir(temp_dir.path())
+        .assert()
+        .failure()
+        .with_assert(assert())
+        .stderr_matches_path(source_path.join("no_release_output.txt"));
+
+    commit(temp_path, "perf: Faster startup");
+    commit(temp_path, "deps: Update serde");
+
+    // Act.
+    let dry_run_assert = Command::new(cargo_bin!("knope"))
+        .arg("release")
+        .arg("--dry-run")
+        .current_dir(temp_dir.path())
+        .assert();
+    let actual_assert = Command::new(cargo_bin!("knope"))
+        .arg("release")
+        .current_dir(temp_dir.path())
+        .assert();
+
+    // Assert.
+    dry_run_assert
+        .success()
+        .with_assert(assert())
+        .stdout_matches_path(source_path.join("dry_run_output.txt"));
+    actual_assert.success().stderr_eq("");
+    assert().matches_path(
+        source_path.join("EXPECTED_Cargo.toml"),
+        read_to_string(temp_path.join("Cargo.toml")).unwrap(),
+    );
+    assert().matches_path(
+        source_path.join("EXPECTED_CHANGELOG.md"),
+        read_to_string(temp_path.join("CHANGELOG.md")).unwrap(),
+    );
+}
+
+/// Add the body, author, and a link to each commit in its changelog entry.
+#[test]
+fn commit_details() {
+    // Arrange.
+    let temp_dir = tempfile::tempdir().unwrap();
+    let temp_path = temp_dir.path();
+    let source_path = Path::new("tests/prepare_release/commit_details");
+    init(temp_path);
+    commit(temp_path, "Initial commit");
+    tag(temp_path, "v1.0.0");
+    commit(
+        temp_path,
+        "feat: A feature\n\nA longer description of the feature.",
+    );
+    let feature_id = head_id(temp_path);
+    commit(temp_path, "fix: A fix");
+    let fix_id = head_id(temp_path);
+    for file in ["knope.toml", "Cargo.toml", "CHANGELOG.md"] {
+        copy(source_path.join(file), temp_path.join(file)).unwrap();
+    }
+
+    // Act.
+    let actual_assert = Command::new(cargo_bin!("knope"))
+        .arg("release")
+        .current_dir(temp_dir.path())
+        .assert();
+
+    // Assert.
+    actual_assert.success().stderr_eq("");
+    let changelog = read_to_string(temp_path.join("CHANGELOG.md"))
+        .unwrap()
+        .replace(&feature_id, "[FEATURE_ID]")
+        .replace(&feature_id[..7], "[FEATURE_SHORT_ID]")
+        .replace(&fix_id, "[FIX_ID]")
+        .replace(&fix_id[..7], "[FIX_SHORT_ID]");
+    assert().matches_path(source_path.join("EXPECTED_CHANGELOG.md"), changelog);
+}
+
+/// Link references to GitHub issues and Jira issues, and list them under each entry.
+#[test]
+fn issue_references() {
+    // Arrange.
+    let temp_dir = tempfile::tempdir().unwrap();
+    let temp_path = temp_dir.path();
+    let source_path = Path::new("tests/prepare_release/issue_references");
+    init(temp_path);
+    commit(temp_path, "Initial commit");
+    tag(temp_path, "v1.0.0");
+    commit(temp_path, "feat: A feature (#12)\n\nCloses #3");
+    commit(temp_path, "fix: Fix PROJ-4, but not `#5`");
+    for file in ["knope.toml", "Cargo.toml", "CHANGELOG.md"] {
+        copy(source_path.join(file), temp_path.join(file)).unwrap();
+    }
+
+    // Act.
+    let dry_run_assert = Command::new(cargo_bin!("knope"))
+        .arg("release")
+        .arg("--dry-run")
+        .current_dir(temp_dir.path())
+        .assert();
+    let actual_assert = Command::new(cargo_bin!("knope"))
+        .arg("release")
+        .current_dir(temp_dir.path())
+        .assert();
+
+    // Assert.
+    dry_run_assert
+        .success()
+        .with_assert(assert())
+        .stdout_matches_path(source_path.join("dry_run_output.txt"));
+    actual_assert.success().stderr_eq("");
+    assert().matches_path(
+        source_path.join("EXPECTED_CHANGELOG.md"),
+        read_to_string(temp_path.join("CHANGELOG.md")).unwrap(),
+    );
+}
+
+/// Reverted commits are left out of the release, reverts of released commits get their own section.
+#[test]
+fn reverts() {
+    // Arrange.
+    let temp_dir = tempfile::tempdir().unwrap();
+    let temp_path = temp_dir.path();
+    let source_path = Path::new("tests/prepare_release/reverts");
+    init(temp_path);
+    for file in ["knope.toml", "Cargo.toml", "CHANGELOG.md"] {
+        copy(source_path.join(file), temp_path.join(file)).unwrap();
+    }
+    add_all(temp_path);
+    commit(temp_path, "Initial commit");
+    write(temp_path.join("fix.txt"), "A fix").unwrap();
+    add_all(temp_path);
+    commit(temp_path, "fix: A released fix");
+    tag(temp_path, "v1.0.0");
+    write(temp_path.join("feature.txt"), "A feature").unwrap();
+    add_all(temp_path);
+    commit(
+        temp_path,
+        "feat: A feature which is reverted before release",
+    );
+    revert(temp_path, "HEAD");
+    revert(temp_path, "v1.0.0");
+
+    // Act.
+    let dry_run_assert = Command::new(cargo_bin!("knope"))
+        .arg("release")
+        .arg("--dry-run")
+        .current_dir(temp_dir.path())
+        .assert();
+    let actual_assert = Command::new(cargo_bin!("knope"))
+        .arg("release")
+        .current_dir(temp_dir.path())
+        .assert();
+
+    // Assert.
+    dry_run_assert
+        .success()
+        .with_assert(assert())
+        .stdout_matches_path(source_path.join("dry_run_output.txt"));
+    actual_assert.success().stderr_eq("");
+    assert().matches_path(
+        source_path.join("EXPECTED_Cargo.toml"),
+        read_to_string(temp_path.join("Cargo.toml")).unwrap(),
+    );
+    assert().matches_path(
+        source_path.join("EXPECTED_CHANGELOG.md"),
+        read_to_string(temp_path.join("CHANGELOG.md")).unwrap(),
+    );
+}
+
 #[test]
 fn changesets() {
     // Arrange a project with two packages. Add a changeset file for the _first_ package only
@@ -2006,3 +2979,113 @@ fn pick_correct_tag_from_branching_history() {
         );
     }
 }
+
+/// Render the new changelog entry from a template, with a custom date format.
+#[test]
+fn changelog_template() {
+    // Arrange.
+    let temp_dir = tempfile::tempdir().unwrap();
+    let temp_path = temp_dir.path();
+    let source_path = Path::new("tests/prepare_release/changelog_template");
+    init(temp_path);
+    commit(temp_path, "Initial commit");
+    tag(temp_path, "v1.0.0");
+    commit(
+        temp_path,
+        "feat: A feature\n\nA longer description of the feature.",
+    );
+    commit(temp_path, "fix: A fix");
+    commit(temp_path, "feat!: A breaking feature");
+    for file in [
+        "knope.toml",
+        "Cargo.toml",
+        "CHANGELOG.md",
+        "changelog.md.tmpl",
+    ] {
+        copy(source_path.join(file), temp_path.join(file)).unwrap();
+    }
+
+    // Act.
+    let actual_assert = Command::new(cargo_bin!("knope"))
+        .arg("release")
+        .current_dir(temp_dir.path())
+        .assert();
+
+    // Assert.
+    actual_assert.success().stderr_eq("");
+    let date = OffsetDateTime::now_utc()
+        .format(format_description!("[month repr:long] [day], [year]"))
+        .unwrap();
+    let changelog = read_to_string(temp_path.join("CHANGELOG.md"))
+        .unwrap()
+        .replace(&date, "[LONG_DATE]");
+    assert().matches_path(source_path.join("EXPECTED_CHANGELOG.md"), changelog);
+}
+
+/// Merge the changes from a Keep a Changelog "Unreleased" section into the new version.
+#[test]
+fn unreleased_section() {
+    // Arrange.
+    let temp_dir = tempfile::tempdir().unwrap();
+    let temp_path = temp_dir.path();
+    let source_path = Path::new("tests/prepare_release/unreleased_section");
+    init(temp_path);
+    commit(temp_path, "Initial commit");
+    tag(temp_path, "v1.0.0");
+    commit(temp_path, "feat: A feature");
+    commit(temp_path, "fix: A fix");
+    for file in ["knope.toml", "Cargo.toml", "CHANGELOG.md"] {
+        copy(source_path.join(file), temp_path.join(file)).unwrap();
+    }
+
+    // Act.
+    let dry_run_assert = Command::new(cargo_bin!("knope"))
+        .arg("release")
+        .arg("--dry-run")
+        .current_dir(temp_dir.path())
+        .assert();
+    let actual_assert = Command::new(cargo_bin!("knope"))
+        .arg("release")
+        .current_dir(temp_dir.path())
+        .assert();
+
+    // Assert.
+    dry_run_assert
+        .success()
+        .with_assert(assert())
+        .stdout_matches_path(source_path.join("dry_run_output.txt"));
+    actual_assert.success().stderr_eq("");
+    assert().matches_path(
+        source_path.join("EXPECTED_CHANGELOG.md"),
+        read_to_string(temp_path.join("CHANGELOG.md")).unwrap(),
+    );
+}
+
+/// Link the new version to a comparison with the previous one on GitHub.
+#[test]
+fn compare_links() {
+    // Arrange.
+    let temp_dir = tempfile::tempdir().unwrap();
+    let temp_path = temp_dir.path();
+    let source_path = Path::new("tests/prepare_release/compare_links");
+    init(temp_path);
+    commit(temp_path, "Initial commit");
+    tag(temp_path, "v1.0.0");
+    commit(temp_path, "feat: A feature");
+    for file in ["knope.toml", "Cargo.toml", "CHANGELOG.md"] {
+        copy(source_path.join(file), temp_path.join(file)).unwrap();
+    }
+
+    // Act.
+    let actual_assert = Command::new(cargo_bin!("knope"))
+        .arg("release")
+        .current_dir(temp_dir.path())
+        .assert();
+
+    // Assert.
+    actual_assert.success().stderr_eq("");
+    assert().matches_path(
+        source_path.join("EXPECTED_CHANGELOG.md"),
+        read_to_string(temp_path.join("CHANGELOG.md")).unwrap(),
+    );
+}
@@ -0,0 +1,20 @@
+plugins {
+    id("com.android.application")
+    kotlin("android") version "1.9.10"
+}
+
+android {
+    namespace = "org.example.app"
+    compileSdk = 34
+
+    defaultConfig {
+        applicationId = "org.example.app"
+        minSdk = 24
+        versionCode = 7
+        versionName = "1.0.0"
+    }
+}
+
+dependencies {
+    implementation("androidx.core:core-ktx:1.12.0")
+}
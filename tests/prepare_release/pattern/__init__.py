@@ -0,0 +1,3 @@
+"""An example package."""
+
+__version__ = "1.0.0"
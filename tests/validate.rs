@@ -44,3 +44,53 @@ fn validate_conflicting_packages() {
         .failure()
         .stderr_eq_path("tests/validate/multiple_package_formats.txt");
 }
+
+/// Run `--validate` with a changelog that has problems.
+#[test]
+fn validate_changelog() {
+    // Arrange.
+    let temp_dir = tempfile::tempdir().unwrap();
+    let temp_path = temp_dir.path();
+    let source_path = Path::new("tests/validate/changelog");
+    init(temp_path);
+    commit(temp_path, "Initial commit");
+    tag(temp_path, "v0.1.0");
+    commit(temp_path, "feat: A feature");
+    tag(temp_path, "v0.2.0");
+    for file in ["knope.toml", "Cargo.toml", "CHANGELOG.md"] {
+        copy(source_path.join(file), temp_path.join(file)).unwrap();
+    }
+
+    Command::new(cargo_bin!("knope"))
+        .arg("--validate")
+        .current_dir(temp_path)
+        .assert()
+        .failure()
+        .stderr_eq_path(source_path.join("output.txt"));
+}
+
+/// Run `--validate` with a valid changelog which has example headings in code blocks.
+#[test]
+fn validate_changelog_code_block() {
+    // Arrange.
+    let temp_dir = tempfile::tempdir().unwrap();
+    let temp_path = temp_dir.path();
+    let source_path = Path::new("tests/validate/changelog_code_block");
+    init(temp_path);
+    commit(temp_path, "Initial commit");
+    tag(temp_path, "v0.1.0");
+    commit(temp_path, "feat: A feature");
+    tag(temp_path, "v1.0.0");
+    commit(temp_path, "feat: Another feature");
+    tag(temp_path, "v1.1.0");
+    for file in ["knope.toml", "Cargo.toml", "CHANGELOG.md"] {
+        copy(source_path.join(file), temp_path.join(file)).unwrap();
+    }
+
+    Command::new(cargo_bin!("knope"))
+        .arg("--validate")
+        .current_dir(temp_path)
+        .assert()
+        .success()
+        .stderr_eq("");
+}